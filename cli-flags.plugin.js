@@ -3,8 +3,11 @@ import * as fs from "node:fs/promises";
 const MARKER = "cli-flags:";
 
 const STRUCT_MATCHER = /struct\s*Args\s*\{[^}]+\}/;
-const FLAG_MATCHER = /#\[arg\((?<params>.+)\)\].*$\s*\/\/\/(?<comment>.+)$\s*(?<flag>[^:]+):(?<type>[^,]+),?$/gm;
-const DEFAULT_MATCHER = /default_value_t\s*=\s*(?<def>[^,\)]+)/;
+// The parameters of `#[arg(...)]` may span lines (as rustfmt wraps them), and contain strings and
+// calls with parentheses of their own.
+const FLAG_MATCHER =
+  /#\[arg\((?<params>(?:[^()"]|"[^"]*"|\([^()]*\))*)\)\].*$\s*\/\/\/(?<comment>.+)$\s*(?<flag>[^:]+):(?<type>[^,]+),?$/gm;
+const DEFAULT_MATCHER = /default_value(?:_t)?\s*=\s*(?<def>"[^"]*"|[^,\)]+)/;
 
 export default function optionsExtractorPlugin() {
   return {
@@ -19,30 +22,41 @@ export default function optionsExtractorPlugin() {
       const file = id.slice(MARKER.length);
       this.addWatchFile(file);
       const code = await fs.readFile(file, "utf8");
-      const [struct] = STRUCT_MATCHER.exec(code);
-      const flags = [];
-      while (true) {
-        const match = FLAG_MATCHER.exec(struct);
-        if (!match) break;
-
-        const { comment, flag, type, params } = match.groups;
-        const flagDesc = {
-          flag: `--${flag.trim().replaceAll("_", "-")}`,
-          title: flagToTitle(flag.trim()),
-          description: comment.trim(),
-          type: type.trim(),
-        };
-        const def = DEFAULT_MATCHER.exec(params);
-        if (def) {
-          flagDesc.def = def.groups.def.trim();
-        }
-        flags.push(flagDesc);
-      }
-      return `export default ${JSON.stringify(flags)}`;
+      return `export default ${JSON.stringify(extractFlags(code))}`;
     },
   };
 }
 
+// The documented flags of `struct Args` in `code`, with their defaults as the CLI accepts them.
+export function extractFlags(code) {
+  const [struct] = STRUCT_MATCHER.exec(code);
+  const flags = [];
+  for (const match of struct.matchAll(FLAG_MATCHER)) {
+    const { comment, flag, type, params } = match.groups;
+    const flagDesc = {
+      flag: `--${flag.trim().replaceAll("_", "-")}`,
+      title: flagToTitle(flag.trim()),
+      description: comment.trim(),
+      type: type.trim(),
+    };
+    const def = DEFAULT_MATCHER.exec(params);
+    if (def) {
+      flagDesc.def = defaultToValue(def.groups.def.trim());
+    }
+    flags.push(flagDesc);
+  }
+  return flags;
+}
+
+// `default_value = "files"` is the value itself, `default_value_t = Lang::En` names a variant of a
+// value enum, which clap expects in kebab-case ("en").
+function defaultToValue(def) {
+  if (def.startsWith("\"")) return def.slice(1, -1);
+  const variant = /^[\w:]+::(?<name>[A-Z]\w*)$/.exec(def);
+  if (!variant) return def;
+  return variant.groups.name.replace(/(?<!^)([A-Z])/g, "-$1").toLowerCase();
+}
+
 function flagToTitle(s) {
   return s.replace(/(_|^)([a-z])/g, (_, l, m) => l.replace("_", " ") + m.toUpperCase());
 }
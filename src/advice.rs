@@ -0,0 +1,371 @@
+//! Compiler flag suggestions, based on the toolchains that built the module and the flags they
+//! recorded in the `producers` section and `DW_AT_producer`.
+
+use std::{collections::BTreeMap, fmt, io::Write};
+
+use addr2line::gimli;
+use regex::bytes::Regex;
+use wasmparser::{Parser, Payload, ProducersSectionReader};
+
+use crate::{
+    dwarf::{self, DwarfSections},
+    eh, exports,
+    messages::{Lang, Message},
+    profile, sections, wasm,
+};
+
+/// A toolchain whose flags we know how to suggest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Toolchain {
+    Rustc,
+    Clang,
+    Emscripten,
+    TinyGo,
+    WasmBindgen,
+}
+
+impl fmt::Display for Toolchain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Toolchain::Rustc => "rustc",
+            Toolchain::Clang => "clang",
+            Toolchain::Emscripten => "emcc",
+            Toolchain::TinyGo => "tinygo",
+            Toolchain::WasmBindgen => "wasm-bindgen",
+        })
+    }
+}
+
+/// What a suggested flag changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// Optimize for size rather than speed, or at all.
+    OptimizeForSize,
+    /// Optimize across compilation units.
+    Lto,
+    /// Abort on panics (or exceptions) instead of unwinding.
+    AbortOnPanic,
+    /// Trap on panics instead of printing a message first.
+    TrapOnPanic,
+    /// Leave out the `name` section.
+    RemoveNameSection,
+    /// Rename imports and exports to the shortest possible names.
+    ShortenNames,
+}
+
+/// A flag to build with, and how many bytes it would likely save.
+#[derive(Clone, Debug)]
+pub struct Suggestion {
+    pub toolchain: Toolchain,
+    pub change: Change,
+    pub flag: &'static str,
+    /// Rough range of the savings in bytes. Only meant to give an idea of the order of magnitude.
+    pub savings: (u64, u64),
+}
+
+impl Suggestion {
+    fn message(&self) -> Message {
+        let (flag, low, high) = (self.flag.to_string(), self.savings.0, self.savings.1);
+        match self.change {
+            Change::OptimizeForSize => Message::OptimizeForSize { flag, low, high },
+            Change::Lto => Message::EnableLto { flag, low, high },
+            Change::AbortOnPanic => Message::AbortOnPanic { flag, low, high },
+            Change::TrapOnPanic => Message::TrapOnPanic { flag, low, high },
+            Change::RemoveNameSection => Message::RemoveNameSection { flag, bytes: high },
+            Change::ShortenNames => Message::ShortenNames { flag, low, high },
+        }
+    }
+}
+
+/// Code built by one toolchain, and what its units say about how it was built.
+#[derive(Clone, Debug, Default)]
+struct Build {
+    units: usize,
+    /// Address ranges of the toolchain's units, coalesced.
+    ranges: Vec<(u64, u64)>,
+    /// Whether a unit recorded its command line in `DW_AT_producer`.
+    flags_recorded: bool,
+    /// The lowest optimization level recorded (`0`-`3`, `s` or `z`), if any.
+    opt_level: Option<String>,
+    /// Whether a unit recorded `-flto`.
+    lto: bool,
+}
+
+impl Build {
+    fn size(&self) -> u64 {
+        self.ranges.iter().map(|(begin, end)| end - begin).sum()
+    }
+
+    /// `size` scaled by a range of percentages.
+    fn share(&self, (low, high): (u64, u64)) -> (u64, u64) {
+        (self.size() * low / 100, self.size() * high / 100)
+    }
+}
+
+/// Names and versions from the `producers` custom section.
+#[derive(Clone, Debug, Default)]
+pub struct Producers {
+    pub languages: Vec<(String, String)>,
+    pub processed_by: Vec<(String, String)>,
+}
+
+/// Returns the contents of the module's `producers` section. A malformed section is ignored.
+pub fn producers(module: &[u8]) -> Producers {
+    let mut producers = Producers::default();
+    for payload in Parser::new(0).parse_all(module) {
+        let Ok(Payload::CustomSection(section)) = payload else {
+            continue;
+        };
+        if section.name() != "producers" {
+            continue;
+        }
+        let Ok(reader) = ProducersSectionReader::new(section.data(), section.data_offset()) else {
+            break;
+        };
+        for field in reader.into_iter().flatten() {
+            let values = field
+                .values
+                .into_iter()
+                .flatten()
+                .map(|value| (value.name.to_string(), value.version.to_string()));
+            match field.name {
+                "language" => producers.languages.extend(values),
+                "processed-by" => producers.processed_by.extend(values),
+                _ => {}
+            }
+        }
+        break;
+    }
+    producers
+}
+
+impl Producers {
+    fn mentions(&self, name: &str) -> bool {
+        self.languages
+            .iter()
+            .chain(&self.processed_by)
+            .any(|(producer, version)| {
+                producer.to_lowercase().contains(name) || version.to_lowercase().contains(name)
+            })
+    }
+}
+
+/// The toolchain of a unit, from its `DW_AT_producer` and the module's `producers` section.
+fn toolchain(producer: &str, producers: &Producers) -> Option<Toolchain> {
+    let lowercase = producer.to_lowercase();
+    if lowercase.contains("tinygo") {
+        Some(Toolchain::TinyGo)
+    } else if lowercase.contains("rustc") {
+        Some(Toolchain::Rustc)
+    } else if lowercase.contains("emscripten") {
+        Some(Toolchain::Emscripten)
+    } else if lowercase.contains("clang") {
+        // emcc doesn't mark the units it compiles, only the module.
+        match producers.mentions("emscripten") {
+            true => Some(Toolchain::Emscripten),
+            false => Some(Toolchain::Clang),
+        }
+    } else {
+        None
+    }
+}
+
+/// Suggests flags that would make the module smaller, per toolchain.
+///
+/// The toolchain of every compilation unit comes from its `DW_AT_producer`. Clang records its
+/// flags there with `-grecord-command-line`, which shows whether it optimized for size and used
+/// LTO. Other toolchains don't, so a debug build is recognized by its runtime checks instead (see
+/// `profile`), and missing LTO by units whose code isn't interleaved. Unwinding is recognized by
+/// exception handling instructions. Savings are the usual share of the toolchain's code that a
+/// flag saves, so only the order of magnitude is meaningful.
+///
+/// wasm-bindgen compiles no units of its own and is recognized by the bindings it imports. What
+/// its suggestions save is measured instead: the `name` section, and the names of the imports and
+/// exports that only its JS glue calls.
+pub fn suggestions(module: &[u8]) -> anyhow::Result<Vec<Suggestion>> {
+    let wasm_file = object::wasm::WasmFile::parse(module)?;
+    let sections = DwarfSections::load(&wasm_file)?;
+    let dwarf = sections.dwarf();
+    let producers = producers(module);
+
+    let opt_level = Regex::new(r"^-O([0-3sz]?)$")?;
+    let mut builds: BTreeMap<Toolchain, Build> = BTreeMap::new();
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        let unit = dwarf.unit(header)?;
+        let mut entries = unit.entries();
+        let Some((_, root)) = entries.next_dfs()? else {
+            continue;
+        };
+        let producer = match root.attr_value(gimli::DW_AT_producer)? {
+            Some(value) => dwarf::attr_string(&dwarf, &unit, value).unwrap_or_default(),
+            None => String::new(),
+        };
+        let language = root.attr_value(gimli::DW_AT_language)?;
+        let toolchain = match language {
+            Some(gimli::AttributeValue::Language(gimli::DW_LANG_Go)) => Some(Toolchain::TinyGo),
+            _ => toolchain(&producer, &producers),
+        };
+        let Some(toolchain) = toolchain else {
+            continue;
+        };
+        let build = builds.entry(toolchain).or_default();
+        build.units += 1;
+        for flag in producer.split_whitespace() {
+            if let Some(captures) = opt_level.captures(flag.as_bytes()) {
+                build.flags_recorded = true;
+                // A bare `-O` is `-O1`, in clang's order `s` and `z` rank above `3`.
+                let level = match &captures[1] {
+                    b"" => "1".to_string(),
+                    level => String::from_utf8_lossy(level).into_owned(),
+                };
+                let rank = |level: &str| "0123sz".find(level);
+                if build
+                    .opt_level
+                    .as_deref()
+                    .is_none_or(|lowest| rank(&level) < rank(lowest))
+                {
+                    build.opt_level = Some(level);
+                }
+            } else if flag.starts_with("-flto") {
+                build.lto = true;
+            } else if flag.starts_with('-') {
+                build.flags_recorded = true;
+            }
+        }
+        let tombstone = !0u64 >> (64 - 8 * u32::from(unit.encoding().address_size));
+        let mut unit_ranges = dwarf.unit_ranges(&unit)?;
+        while let Some(range) = unit_ranges.next()? {
+            if range.begin < tombstone && range.end > range.begin {
+                build.ranges.push((range.begin, range.end));
+            }
+        }
+    }
+    for build in builds.values_mut() {
+        dwarf::coalesce(&mut build.ranges);
+    }
+
+    let profile = profile::detect(module, &dwarf)?;
+    let merged = !crate::lto::merged_units(&dwarf)?.is_empty();
+    // Exception handling overhead per toolchain, by the unit the function's code belongs to.
+    let mut unwinding: BTreeMap<Toolchain, u64> = BTreeMap::new();
+    let handlers = eh::handlers(module)?;
+    if !handlers.is_empty() {
+        let functions = wasm::functions(module)?;
+        for handler in &handlers {
+            let Some(function) = functions.iter().find(|f| f.index == handler.index) else {
+                continue;
+            };
+            let toolchain = builds
+                .iter()
+                .find(|(_, build)| dwarf::contains(&build.ranges, function.code_offset));
+            if let Some((&toolchain, _)) = toolchain {
+                *unwinding.entry(toolchain).or_default() += handler.overhead;
+            }
+        }
+    }
+    let panic_messages = wasm::data_segments(module)?
+        .iter()
+        .any(|segment| segment.data.windows(7).any(|window| window == b"panic: "));
+
+    let mut suggestions = vec![];
+    for (&toolchain, build) in &builds {
+        let mut suggest = |change, flag, savings| {
+            suggestions.push(Suggestion {
+                toolchain,
+                change,
+                flag,
+                savings,
+            })
+        };
+        // Unwinding costs the landing pads we can count, plus cleanup code we can't.
+        let unwind_savings = |overhead: u64| {
+            let (low, high) = build.share((2, 10));
+            (overhead.max(low), overhead + high)
+        };
+        match toolchain {
+            Toolchain::Rustc => {
+                if profile.looks_like_debug() {
+                    let flag = "opt-level = \"z\" (in [profile.release])";
+                    suggest(Change::OptimizeForSize, flag, build.share((40, 60)));
+                }
+                if !merged && build.units > 1 {
+                    let flag = "lto = true, codegen-units = 1";
+                    suggest(Change::Lto, flag, build.share((5, 20)));
+                }
+                if let Some(&overhead) = unwinding.get(&toolchain) {
+                    let flag = "panic = \"abort\"";
+                    suggest(Change::AbortOnPanic, flag, unwind_savings(overhead));
+                }
+            }
+            Toolchain::Clang | Toolchain::Emscripten => {
+                let savings = match build.opt_level.as_deref() {
+                    Some("0") => Some((40, 60)),
+                    Some("1" | "2" | "3") => Some((5, 20)),
+                    _ => None,
+                };
+                if let Some(savings) = savings {
+                    suggest(Change::OptimizeForSize, "-Oz", build.share(savings));
+                }
+                // Without a recorded command line, a missing `-flto` says nothing.
+                if build.flags_recorded && !build.lto && build.units > 1 {
+                    suggest(Change::Lto, "-flto", build.share((5, 15)));
+                }
+                if let Some(&overhead) = unwinding.get(&toolchain) {
+                    let flag = "-fno-exceptions";
+                    suggest(Change::AbortOnPanic, flag, unwind_savings(overhead));
+                }
+            }
+            Toolchain::TinyGo => {
+                // TinyGo always uses LTO, and optimizes for size unless told otherwise.
+                if panic_messages {
+                    suggest(Change::TrapOnPanic, "-panic=trap", build.share((1, 5)));
+                }
+            }
+            // Never the toolchain of a unit, see below.
+            Toolchain::WasmBindgen => {}
+        }
+    }
+    let names = exports::names(module)?;
+    if exports::uses_bindgen(&names) || producers.mentions("wasm-bindgen") {
+        let name_section: u64 = sections::sections(module)
+            .iter()
+            .filter(|section| section.kind == sections::Kind::Names)
+            .map(|section| section.size)
+            .sum();
+        suggestions.push(Suggestion {
+            toolchain: Toolchain::WasmBindgen,
+            change: Change::RemoveNameSection,
+            flag: "--remove-name-section",
+            savings: (name_section, name_section),
+        });
+        // The shortest possible names, which a renaming tool won't quite get to.
+        let savings = exports::shortening_savings(&names);
+        suggestions.push(Suggestion {
+            toolchain: Toolchain::WasmBindgen,
+            change: Change::ShortenNames,
+            flag: "wasm-opt --minify-imports-and-exports-and-modules",
+            savings: (savings * 3 / 4, savings),
+        });
+    }
+    suggestions.retain(|suggestion| suggestion.savings.1 > 0);
+    Ok(suggestions)
+}
+
+pub fn write_report(module: &[u8], lang: Lang, mut output: impl Write) -> anyhow::Result<()> {
+    writeln!(output, "== advice ==")?;
+    let suggestions = suggestions(module)?;
+    if suggestions.is_empty() {
+        writeln!(output, "{}", Message::NoFlagSuggestions.text(lang))?;
+        return Ok(());
+    }
+    let mut toolchain = None;
+    for suggestion in &suggestions {
+        if toolchain != Some(suggestion.toolchain) {
+            toolchain = Some(suggestion.toolchain);
+            writeln!(output, "{}:", suggestion.toolchain)?;
+        }
+        writeln!(output, "  {}", suggestion.message().text(lang))?;
+    }
+    Ok(())
+}
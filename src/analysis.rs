@@ -0,0 +1,1616 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fmt,
+    ops::Range,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use addr2line::fallible_iterator::FallibleIterator;
+use anyhow::Context;
+use clap::ValueEnum;
+use object::{Object, ObjectSection};
+
+use crate::{
+    analysis_tree::AnalysisTree,
+    artificial,
+    compressed::{self, Uncertainty},
+    coverage::{Envelopes, UnitCoverage},
+    custom::{self, Content, CustomSection, CustomSectionParser},
+    data,
+    demangle::Demangler,
+    dies,
+    dwarf::{self, DwarfSections, Segments, SplitDwarf},
+    eh,
+    events::{Event, Ignore, Observer, Phase, StopReason},
+    glob::{self, PathFilter},
+    group::{self, GroupBy, PathNormalizer},
+    libc, lineless, lto,
+    messages::{Lang, Message},
+    names::{self, NameCheck},
+    profile::{self, BuildProfile},
+    sections,
+    sourcemap::SourceMap,
+    strings, threads, tinygo,
+    tree::Instantiation,
+    wasm,
+};
+
+/// Map from `;`-separated frame paths (in folded flame graph notation) to the number of bytes
+/// attributed to them. Frames never contain a `;` themselves, see [`escape_frame`].
+pub type Contributors = HashMap<String, u64>;
+
+/// Frame below the code section that groups the bytes of functions no debug info covers, with
+/// [`Options::unmapped_functions`].
+pub const UNMAPPED_FRAME: &str = "@unmapped";
+
+/// Frame below the function and code sections for the bytes that encode the functions rather
+/// than belong to any of them, like the size prefixes of the bodies.
+pub const ENCODING_OVERHEAD_FRAME: &str = "@encoding-overhead";
+
+/// Makes `name` safe to use as a frame of a [`Contributors`] key. A `;` in a function name, path
+/// or section name would split it into bogus frames in every output, so it is replaced with a
+/// full-width semicolon (`；`), which reads the same.
+pub fn escape_frame(name: &str) -> Cow<'_, str> {
+    match name.contains(';') {
+        true => Cow::Owned(name.replace(';', "\u{ff1b}")),
+        false => Cow::Borrowed(name),
+    }
+}
+
+/// Result of analyzing a module.
+#[derive(Clone, Debug, Default)]
+pub struct Analysis {
+    pub contributors: Contributors,
+    /// Coverage of every compilation unit, in the order they appear in the DWARF data.
+    pub units: Vec<UnitCoverage>,
+    /// Agreement between the `name` section and DWARF.
+    pub names: NameCheck,
+    /// Whether the module looks like a debug build.
+    pub profile: BuildProfile,
+    /// Notes about the module worth keeping with the report, as key/value pairs.
+    pub metadata: Vec<(String, String)>,
+    /// What was found in the custom sections given in [`Options::custom_sections`].
+    pub custom_sections: Vec<custom::Summary>,
+    /// With [`Options::collapse_generics`], the instantiations behind every collapsed frame, by
+    /// its `;`-separated path.
+    pub instantiations: HashMap<String, Vec<Instantiation>>,
+    /// With [`Metric::Gzip`], the range of estimates for every contributor, by its `;`-separated
+    /// path.
+    pub uncertainty: Option<Uncertainty>,
+    /// How long each phase of the analysis took, in order. Empty for [`Metric::DieCount`], which
+    /// doesn't attribute code.
+    pub phases: Vec<(Phase, Duration)>,
+    address_map: Vec<AddressRange>,
+    /// Where the part of a truncated or corrupted module starts that can't be parsed.
+    unparseable_from: Option<usize>,
+}
+
+impl Analysis {
+    /// The code attributed by the debug info, as address ranges with the function and file they
+    /// belong to, sorted by address. Adjacent ranges of the same function and file are merged.
+    ///
+    /// Addresses are DWARF addresses, i.e. offsets into the code section's contents. This is the
+    /// mapping profilers, coverage tools and crash symbolizers need, so they can reuse the DWARF
+    /// work of the analysis. It is empty for [`Metric::DieCount`].
+    pub fn address_map(&self) -> &[AddressRange] {
+        &self.address_map
+    }
+
+    /// The part of the analyzed `module` that could be parsed: all of it, unless it is truncated
+    /// or corrupted. Reports and tools that parse the module again can read this much of it.
+    pub fn parseable<'a>(&self, module: &'a [u8]) -> &'a [u8] {
+        match self.unparseable_from {
+            Some(end) => &module[..end.min(module.len())],
+            None => module,
+        }
+    }
+
+    /// The breakdown as a tree of typed nodes. `group_by` must be the grouping the analysis was
+    /// done with.
+    pub fn tree(&self, group_by: GroupBy) -> AnalysisTree {
+        AnalysisTree::from_contributors(&self.contributors, group_by)
+    }
+}
+
+/// Code at `start..end` and where it came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AddressRange {
+    pub start: u64,
+    pub end: u64,
+    /// The function the code physically lives in (rather than functions inlined into it),
+    /// demangled unless [`Options::raw_symbols`] is set.
+    pub symbol: Option<String>,
+    pub file: Option<String>,
+}
+
+/// Options controlling how bytes are attributed.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// Only break down to files, not functions.
+    pub files_only: bool,
+    /// Keep raw object symbol names for functions, rather than demangling them.
+    pub raw_symbols: bool,
+    /// Include DWARF debug sections in the breakdown.
+    pub show_debug_sections: bool,
+    pub group_by: GroupBy,
+    /// Maximum number of demangled names to keep cached, or 0 for no limit.
+    pub demangle_cache_size: usize,
+    /// External demangler for names the built-in ones don't understand, see
+    /// [`Demangler::with_command`].
+    pub demangler_cmd: Option<String>,
+    pub metric: Metric,
+    /// Glob patterns of compilation unit names. If not empty, only code of matching units is
+    /// attributed, and the line tables of all other units are never parsed.
+    pub only_units: Vec<String>,
+    /// How to analyze custom sections that would otherwise only show up as unmapped bytes.
+    pub custom_sections: Vec<CustomSection>,
+    /// Treat source paths that differ only in case as the same file.
+    pub case_insensitive_paths: bool,
+    /// Language of the warnings.
+    pub lang: Lang,
+    /// Break assembly without function debug info down by the functions of the name section.
+    pub assembly_symbols: bool,
+    pub attribution: Attribution,
+    /// Show all instantiations of generic code as one frame, and keep their generic arguments and
+    /// sizes in [`Analysis::instantiations`] instead.
+    pub collapse_generics: bool,
+    /// Group compiler-generated functions (`DW_AT_artificial`) under [`artificial::FRAME`], right
+    /// below their section.
+    pub compiler_generated: bool,
+    /// Source directories (like `src` or `vendor`) that make up the first level below a section,
+    /// with all other files under [`group::OTHER_ROOT`]. Empty to keep the full paths.
+    pub roots: Vec<String>,
+    /// Glob patterns of source file paths. If not empty, only code from matching files is
+    /// attributed, and everything else is left out of the breakdown.
+    pub include: Vec<String>,
+    /// Glob patterns of source file paths whose code is left out of the breakdown.
+    pub exclude: Vec<String>,
+    /// Break the code no debug info covers down by the functions it belongs to, named after the
+    /// name section, under [`UNMAPPED_FRAME`]. With the [`ENCODING_OVERHEAD_FRAME`], the code
+    /// section is then fully accounted for.
+    pub unmapped_functions: bool,
+    /// Debug info from outside the module: a separate debug module, whose DWARF is used instead
+    /// of the module's own, and split DWARF objects (`.dwo`) of its skeleton units.
+    pub debug_files: Vec<DebugFile>,
+}
+
+/// A file with debug info for the module, see [`Options::debug_files`].
+#[derive(Clone)]
+pub struct DebugFile {
+    /// Path of the file, to match it with the skeleton units of split DWARF and for warnings.
+    pub name: String,
+    pub data: Arc<[u8]>,
+}
+
+impl fmt::Debug for DebugFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DebugFile")
+            .field("name", &self.name)
+            .field("size", &self.data.len())
+            .finish()
+    }
+}
+
+/// What the numbers in the breakdown count.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Metric {
+    /// Bytes of the module.
+    #[default]
+    Bytes,
+    /// Debug info entries (DIEs) per source file.
+    DieCount,
+    /// Estimated bytes of the gzip-compressed module, i.e. its size on the wire.
+    Gzip,
+}
+
+/// How code bytes are assigned to functions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Attribution {
+    /// Attribute the address ranges of the DWARF line tables.
+    #[default]
+    Dwarf,
+    /// Attribute every function's entry in the code section, size prefix included, and only use
+    /// DWARF to name the code within it. Functions add up to their exact encoded size even where
+    /// the line tables have gaps or overlaps.
+    CodeSection,
+}
+
+/// Number of locations attributed between calls to [`Observer::checkpoint`].
+const CHECKPOINT_STEP: usize = 4096;
+
+struct Segment {
+    name: String,
+    start: u64,
+    end: u64,
+    mapped: u64,
+}
+
+impl Segment {
+    fn size(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Bytes nothing was attributed to. Overlapping attributions (like line table ranges, or
+    /// variables and encoding overhead) can count some bytes twice, so `mapped` may exceed the
+    /// size.
+    fn unmapped(&self) -> u64 {
+        self.size().saturating_sub(self.mapped)
+    }
+}
+
+/// Attributes every byte of the wasm module in `module` to a section, source file and function.
+pub fn analyze(module: &[u8], options: &Options) -> anyhow::Result<Analysis> {
+    analyze_with_observer(module, options, &mut Ignore)
+}
+
+/// Like [`analyze`], but returns the breakdown as a tree of typed nodes (sections, directories,
+/// files, functions and so on) rather than as frame paths.
+pub fn analyze_tree(module: &[u8], options: &Options) -> anyhow::Result<AnalysisTree> {
+    Ok(analyze(module, options)?.tree(options.group_by))
+}
+
+/// Like [`analyze`], but reports progress and warnings to `observer` while the analysis runs.
+pub fn analyze_with_observer(
+    module: &[u8],
+    options: &Options,
+    observer: &mut dyn Observer,
+) -> anyhow::Result<Analysis> {
+    let started = Instant::now();
+    let module_size = module.len() as u64;
+    observer.event(Event::AnalysisStarted { module_size });
+
+    // Broken toolchains sometimes emit a section more than once. The duplicates are hidden from
+    // the parsers, and shown as sections of their own.
+    let duplicates: Vec<_> = sections::sections(module)
+        .into_iter()
+        .filter(|section| section.is_duplicate())
+        .collect();
+    let without_duplicates;
+    let module = match duplicates.is_empty() {
+        true => module,
+        false => {
+            let names: Vec<_> = duplicates.iter().map(|s| s.name.as_str()).collect();
+            let message = Message::DuplicateSections {
+                sections: names.join(", "),
+            };
+            observer.event(Event::Warning {
+                message: message.text(options.lang),
+            });
+            without_duplicates = sections::hide_duplicates(module, &duplicates);
+            &without_duplicates[..]
+        }
+    };
+
+    // A truncated or corrupted module is analyzed as far as it can be parsed.
+    let (parseable, corruption) = wasm::parseable_prefix(module)?;
+    if let Some(err) = corruption {
+        let message = Message::Truncated {
+            error: err.to_string(),
+            analyzed: parseable,
+        };
+        observer.event(Event::Warning {
+            message: message.text(options.lang),
+        });
+    }
+    let corrupted = module_size - parseable as u64;
+    let unparseable_from = (corrupted > 0).then_some(parseable);
+    let module = &module[..parseable];
+    let wasm_file = object::wasm::WasmFile::parse(module)?;
+
+    let mut segments: Vec<_> = wasm_file
+        .sections()
+        .filter_map(|s| {
+            let name = s.name().ok()?.to_string();
+            if !options.show_debug_sections && name.starts_with(".debug_") {
+                return None;
+            }
+            let (start, end) = s.file_range()?;
+            // A hidden duplicate is a custom section without a name, starting right after the
+            // name's length.
+            let duplicate = duplicates
+                .iter()
+                .find(|d| name.is_empty() && d.contents.start + 1 == start);
+            if let Some(duplicate) = duplicate {
+                return Some(Segment {
+                    name: duplicate.name.clone(),
+                    start: duplicate.contents.start,
+                    end,
+                    mapped: 0,
+                });
+            }
+            Some(Segment {
+                name,
+                start,
+                end,
+                mapped: 0,
+            })
+        })
+        .collect::<Vec<_>>();
+    // The object crate skips the string literals section of the stringref proposal.
+    segments.extend(
+        sections::sections(module)
+            .into_iter()
+            .filter(|section| section.name.starts_with("<strings>"))
+            .map(|section| Segment {
+                name: section.name,
+                start: section.contents.start,
+                end: section.contents.end,
+                mapped: 0,
+            }),
+    );
+    segments.sort_by_key(|segment| segment.start);
+
+    let section_ranges: Vec<_> = segments
+        .iter()
+        .map(|segment| (segment.name.clone(), segment.start..segment.end))
+        .collect();
+
+    let filter = PathFilter::new(&options.include, &options.exclude)?;
+    // With only some units or files selected, everything else would be unmapped and drown them
+    // out.
+    let whole_module = options.only_units.is_empty() && options.include.is_empty();
+
+    let mut sections = DwarfSections::load(&wasm_file)?;
+    let mut split_dwarf = SplitDwarf::default();
+    let mut source_map = None;
+    for file in &options.debug_files {
+        if SourceMap::detect(&file.data) {
+            let map = SourceMap::parse(&file.data)
+                .with_context(|| format!("Reading source map {}", file.name))?;
+            source_map = Some((file.name.clone(), map));
+            continue;
+        }
+        let object = object::File::parse(&*file.data)
+            .with_context(|| format!("Reading debug file {}", file.name))?;
+        let dwo = DwarfSections::load_dwo(&object)?;
+        if dwo.has_units() {
+            split_dwarf.add(&file.name, dwo)?;
+            continue;
+        }
+        let separate = DwarfSections::load(&object)?;
+        if separate.has_units() {
+            sections = separate;
+            continue;
+        }
+        let message = Message::DebugFileWithoutDwarf {
+            file: file.name.clone(),
+        };
+        observer.event(Event::Warning {
+            message: message.text(options.lang),
+        });
+    }
+    // Without their split DWARF, skeleton units have line tables but no functions.
+    let units = split_dwarf.missing_units(&sections.dwarf())?;
+    if units > 0 {
+        let message = Message::SplitDwarfMissing { units };
+        observer.event(Event::Warning {
+            message: message.text(options.lang),
+        });
+    }
+    if options.metric == Metric::DieCount {
+        let mut paths = PathNormalizer::new(options.case_insensitive_paths);
+        let contributors = dies::die_counts(&sections.dwarf(), &mut paths, &filter)?;
+        let total = contributors.values().sum();
+        observer.event(Event::AnalysisFinished {
+            attributed: total,
+            unattributed: 0,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        });
+        return Ok(Analysis {
+            contributors,
+            units: vec![],
+            names: NameCheck::default(),
+            profile: BuildProfile::default(),
+            metadata: vec![],
+            custom_sections: vec![],
+            instantiations: HashMap::new(),
+            uncertainty: None,
+            phases: vec![],
+            address_map: vec![],
+            unparseable_from,
+        });
+    }
+    let dwarf_segments = match Segments::load(&sections.dwarf()) {
+        Ok(segments) => segments,
+        Err(err) => {
+            let message = Message::SegmentsUnreadable {
+                error: err.to_string(),
+            };
+            observer.event(Event::Warning {
+                message: message.text(options.lang),
+            });
+            Segments::default()
+        }
+    };
+    let mut envelopes = match Envelopes::load(&sections.dwarf(), &dwarf_segments) {
+        Ok(envelopes) => {
+            for message in &envelopes.warnings {
+                observer.event(Event::Warning {
+                    message: message.text(options.lang),
+                });
+            }
+            Some(envelopes)
+        }
+        Err(err) => {
+            let message = Message::UnitRangesUnreadable {
+                error: err.to_string(),
+            };
+            observer.event(Event::Warning {
+                message: message.text(options.lang),
+            });
+            None
+        }
+    };
+    let mut metadata = vec![];
+    if !duplicates.is_empty() {
+        let names: Vec<_> = duplicates.iter().map(|s| s.name.as_str()).collect();
+        metadata.push(("duplicate-sections".to_string(), names.join(", ")));
+    }
+    match lto::merged_units(&sections.dwarf()) {
+        Ok(units) if units.is_empty() => {}
+        Ok(units) => metadata.push(("lto-merged-units".to_string(), units.join(", "))),
+        Err(err) => {
+            let message = Message::LtoCheckFailed {
+                error: err.to_string(),
+            };
+            observer.event(Event::Warning {
+                message: message.text(options.lang),
+            })
+        }
+    }
+    // Units without a line program are located by their debug info entries instead.
+    let lineless = match lineless::ranges(&sections.dwarf()) {
+        Ok(ranges) => ranges,
+        Err(err) => {
+            let message = Message::LinelessUnitsUnreadable {
+                error: err.to_string(),
+            };
+            observer.event(Event::Warning {
+                message: message.text(options.lang),
+            });
+            vec![]
+        }
+    };
+    let profile = profile::detect(module, &sections.dwarf())?;
+    if let Some(message) = profile.warning(options.lang) {
+        observer.event(Event::Warning { message });
+        metadata.push(("build-profile".to_string(), "debug".to_string()));
+    }
+    let context = addr2line::Context::from_dwarf(sections.dwarf())?;
+
+    let mut demangler = Demangler::new(options.demangle_cache_size);
+    if let Some(command) = options
+        .demangler_cmd
+        .as_deref()
+        .filter(|_| !options.raw_symbols)
+    {
+        demangler = demangler.with_command(command)?;
+        // One batch for all names is much faster than a round trip to the demangler per name.
+        let names = symbol_names(module, &sections.dwarf())?;
+        demangler.demangle_batch(names.iter().map(String::as_str));
+    }
+    let names = names::check(module, &context, &mut demangler)?;
+    for (key, count) in [
+        ("functions-without-dwarf", names.without_dwarf.len()),
+        ("name-mismatches", names.mismatches.len()),
+    ] {
+        if count > 0 {
+            metadata.push((key.to_string(), count.to_string()));
+        }
+    }
+    let mut phases = vec![];
+    let mut phase_started = started;
+    finish_phase(Phase::Load, &mut phase_started, &mut phases, observer);
+
+    let mut contributors = HashMap::new();
+    let mut paths = PathNormalizer::new(options.case_insensitive_paths);
+    let ranges = if options.only_units.is_empty() {
+        vec![(0, module_size)]
+    } else {
+        selected_unit_ranges(&sections.dwarf(), &options.only_units)?
+    };
+    // Without any debug info, the name section is all there is to go by, unless a source map
+    // locates the code.
+    let name_section = options.only_units.is_empty() && sections.dwarf().units().next()?.is_none();
+    let source_map = match source_map {
+        Some((_, map)) if name_section => Some(map),
+        Some((file, _)) => {
+            let message = Message::SourceMapIgnored { file };
+            observer.event(Event::Warning {
+                message: message.text(options.lang),
+            });
+            None
+        }
+        None => None,
+    };
+    let locations = match options.attribution {
+        _ if name_section && source_map.is_some() => {
+            let map = source_map.as_ref().expect("checked above");
+            metadata.push(("attribution".to_string(), "source-map".to_string()));
+            source_map_locations(module, map)?
+        }
+        _ if name_section => {
+            let locations = name_section_locations(module)?;
+            let message = Message::NoDebugInfo {
+                functions: locations.len(),
+            };
+            observer.event(Event::Warning {
+                message: message.text(options.lang),
+            });
+            metadata.push(("attribution".to_string(), "name-section".to_string()));
+            locations
+        }
+        Attribution::Dwarf => {
+            let mut locations = vec![];
+            // DWARF addresses are offsets into the code section's contents.
+            let code_start = wasm::code_start(module)?;
+            for &(begin, end) in &ranges {
+                let found = locations_in(&context, &lineless, begin, end)?;
+                locations.extend(
+                    found
+                        .into_iter()
+                        .map(|(address, size, location)| Attributed {
+                            address,
+                            start: address,
+                            file_start: code_start + address,
+                            size,
+                            location,
+                        }),
+                );
+            }
+            locations
+        }
+        Attribution::CodeSection => code_section_locations(module, &context, &lineless, &ranges)?,
+    };
+    let mut locations = match name_section {
+        true => locations,
+        false => split_at(locations, &dwarf::inlined_boundaries(&sections.dwarf())?),
+    };
+    let compiler_generated = match options.compiler_generated {
+        true => artificial::ranges(&sections.dwarf())?,
+        false => vec![],
+    };
+    let symbols = match options.assembly_symbols || name_section {
+        true => symbols(module, options, &mut demangler)?,
+        false => vec![],
+    };
+    let mut stopped = None;
+    // The functions at every location are looked up first, in address order, so consecutive
+    // lookups stay within the same unit and its debug info entries. Attribution then only
+    // assembles the frames.
+    locations.sort_by_key(|location| location.address);
+    let mut functions = Vec::with_capacity(locations.len());
+    // Most functions appear at many locations, and inlined ones in many functions.
+    let mut frames = Frames::default();
+    let progress_step = (locations.len() / 20).max(1);
+    for (i, location) in locations.iter().enumerate() {
+        if i % progress_step == 0 {
+            observer.event(Event::LocationsIndexed {
+                indexed: i as u64,
+                total: locations.len() as u64,
+            });
+        }
+        if i % CHECKPOINT_STEP == 0 {
+            // Nothing is attributed yet, but checkpoint observers still get their turn.
+            observer.checkpoint(&contributors);
+            stopped = observer.should_stop();
+            if stopped.is_some() {
+                break;
+            }
+        }
+        functions.push(match name_section {
+            true => vec![],
+            false => functions_for_address(
+                options,
+                &mut demangler,
+                &mut frames,
+                &context,
+                &split_dwarf,
+                location.address,
+            )?,
+        });
+    }
+    finish_phase(Phase::Index, &mut phase_started, &mut phases, observer);
+
+    let mut attributed = 0;
+    let mut outside_sections = 0;
+    let mut other_segments = 0;
+    let mut address_map = vec![];
+    let mut covered = vec![];
+    // Locations the index didn't get to are left unattributed.
+    for (i, (attributed_code, indexed)) in locations.into_iter().zip(functions).rev().enumerate() {
+        let Attributed {
+            address: map_start,
+            start,
+            file_start,
+            size,
+            location: loc,
+        } = attributed_code;
+        if i % progress_step == 0 {
+            observer.event(Event::BytesAttributed {
+                attributed,
+                total: module_size,
+            });
+        }
+        if i % CHECKPOINT_STEP == 0 {
+            observer.checkpoint(&contributors);
+            if stopped.is_none() {
+                stopped = observer.should_stop();
+                if stopped.is_some() {
+                    break;
+                }
+            }
+        }
+        let dwarf_segment = dwarf_segments.segment_of(start);
+        if let Some(envelopes) = &mut envelopes {
+            envelopes.attribute(dwarf_segment.unwrap_or(0), start, size);
+        }
+        let file_end = file_start + size;
+        // Code in other segments doesn't live in the module's address space, so it gets its own
+        // subtree rather than being attributed to whatever section shares its addresses.
+        let root = if let Some(dwarf_segment) = dwarf_segment {
+            other_segments += size;
+            format!("@segment: {dwarf_segment}")
+        } else if let Some(section) = segments
+            .iter_mut()
+            .find(|s| s.start <= file_start && s.end >= file_end)
+        {
+            attributed += size;
+            section.mapped += size;
+            if whole_module {
+                covered.push(file_start..file_end);
+            }
+            format!("@section: {}", section.name)
+        } else {
+            attributed += size;
+            outside_sections += size;
+            "@section: <unknown section>".to_string()
+        };
+        // Filtered code counts as mapped, so that it doesn't show up as unmapped bytes instead.
+        if !filter.keeps(loc.file.map(|file| paths.normalize(file)).as_deref()) {
+            continue;
+        }
+        let mut funcs = match name_section {
+            true => {
+                vec![frames.intern(symbol_for_address(&symbols, map_start).unwrap_or("<Unknown>"))]
+            }
+            false => indexed,
+        };
+        // The last frame is the function the code physically lives in; inlined callees are attributed to it.
+        let outermost = funcs.last().map(|name| &**name).unwrap_or("<Unknown>");
+        let mut path = vec![root];
+        if threads::is_threads_runtime(outermost) {
+            path.push(threads::FRAME.to_string());
+        } else if let Some(frame) = tinygo::frame_for(outermost) {
+            path.push(frame.to_string());
+        }
+        if dwarf::contains(&compiler_generated, map_start) {
+            path.push(artificial::FRAME.to_string());
+        }
+        match options.group_by {
+            GroupBy::Files => {
+                let file = paths.normalize(loc.file.unwrap_or("<unknown file>"));
+                let libc_symbol = match loc.file {
+                    Some(_) => None,
+                    None => libc::runtime_for_symbol(outermost),
+                };
+                if let Some((group, within)) = libc::runtime_for_path(&file) {
+                    path.push(libc::FRAME.to_string());
+                    path.push(group.to_string());
+                    path.extend(within.into_iter().map(str::to_string));
+                } else if let Some(group) = libc_symbol {
+                    path.push(libc::FRAME.to_string());
+                    path.push(group.to_string());
+                    path.push(file);
+                } else if name_section && loc.file.is_none() {
+                    // There are no files, only functions.
+                } else if let Some((library, within)) = group::c_library_for_path(&file) {
+                    path.push(group::C_LIBRARIES.to_string());
+                    path.push(library.to_string());
+                    path.extend(within.into_iter().map(str::to_string));
+                } else if group::is_assembly(&file) {
+                    path.push(group::ASSEMBLY.to_string());
+                    path.push(file.trim_start_matches('/').to_string());
+                    if outermost == "<Unknown>" {
+                        if let Some(name) = symbol_for_address(&symbols, map_start) {
+                            funcs = vec![frames.intern(name)];
+                        }
+                    }
+                } else if !options.roots.is_empty() {
+                    let (root, within) = group::root_path(&file, &options.roots);
+                    path.push(root.to_string());
+                    path.extend(within.into_iter().map(str::to_string));
+                } else {
+                    path.extend(
+                        file.split('/')
+                            .skip_while(|c| c.is_empty())
+                            .map(str::to_string),
+                    );
+                }
+                if !options.files_only {
+                    path.extend(funcs.iter().map(|name| format!("@function: {name}")));
+                }
+            }
+            GroupBy::Crate => {
+                let file = loc.file.map(|file| paths.normalize(file));
+                let runtime = match &file {
+                    Some(file) => libc::runtime_for_path(file).map(|(group, _)| group),
+                    None => libc::runtime_for_symbol(outermost),
+                };
+                if let Some(group) = runtime {
+                    path.push(libc::FRAME.to_string());
+                    path.push(group.to_string());
+                } else if let Some((library, _)) =
+                    file.as_deref().and_then(group::c_library_for_path)
+                {
+                    path.push(group::C_LIBRARIES.to_string());
+                    path.push(library.to_string());
+                } else {
+                    // Single-file crates and code without line info go by the function's name.
+                    let name = file
+                        .as_deref()
+                        .and_then(group::crate_for_path)
+                        .or_else(|| group::crate_for_symbol(outermost))
+                        .unwrap_or_else(|| "<unknown crate>".to_string());
+                    path.push(format!("{}{name}", group::CRATE_PREFIX));
+                    if file
+                        .as_deref()
+                        .and_then(group::generated_by_crate)
+                        .is_some()
+                    {
+                        path.push(group::GENERATED.to_string());
+                    }
+                }
+                if !options.files_only {
+                    path.extend(funcs.iter().map(|name| format!("@function: {name}")));
+                }
+            }
+            GroupBy::Namespace => {
+                let mut namespace = group::namespace_path(outermost);
+                let function = namespace.pop().unwrap_or_else(|| outermost.to_string());
+                path.extend(namespace);
+                if !options.files_only {
+                    path.push(format!("@function: {function}"));
+                }
+            }
+        }
+        let key = path
+            .iter()
+            .map(|frame| escape_frame(frame))
+            .collect::<Vec<_>>()
+            .join(";");
+
+        *contributors.entry(key).or_insert(0) += size;
+        address_map.push(AddressRange {
+            start,
+            end: start + size,
+            symbol: funcs
+                .last()
+                .filter(|name| &***name != "<Unknown>")
+                .map(|name| name.to_string()),
+            file: loc.file.map(|file| paths.normalize(file)),
+        });
+    }
+    if let Some(StopReason::Memory { limit }) = stopped {
+        let message = Message::OutOfMemory {
+            limit_mb: limit / 1_000_000,
+        };
+        observer.event(Event::Warning {
+            message: message.text(options.lang),
+        });
+        metadata.push(("analysis-truncated".to_string(), "memory".to_string()));
+    }
+    address_map.sort_by_key(|range| range.start);
+    address_map.dedup_by(|next, range| {
+        let merge =
+            range.end == next.start && range.symbol == next.symbol && range.file == next.file;
+        if merge {
+            range.end = next.end;
+        }
+        merge
+    });
+
+    // Functions synthesized by the linker for threads have no debug info, but their names do.
+    // They don't belong to any unit or file, so they are left out when only some are analyzed.
+    let threads_runtime = match whole_module {
+        true => unmapped_threads_runtime(module, &context)?,
+        false => vec![],
+    };
+    for (name, range) in threads_runtime {
+        let size = range.end - range.start;
+        let Some(segment) = segments
+            .iter_mut()
+            .find(|s| s.start <= range.start && s.end >= range.end)
+        else {
+            continue;
+        };
+        segment.mapped += size;
+        attributed += size;
+        covered.push(range);
+        let key = format!(
+            "@section: {};{};@function: {}",
+            escape_frame(&segment.name),
+            threads::FRAME,
+            escape_frame(&name)
+        );
+        *contributors.entry(key).or_insert(0) += size;
+    }
+
+    if other_segments > 0 {
+        let message = Message::NonDefaultSegments {
+            bytes: other_segments,
+        };
+        observer.event(Event::Warning {
+            message: message.text(options.lang),
+        });
+    }
+
+    if outside_sections > 0 {
+        let message = Message::OutsideSections {
+            bytes: outside_sections,
+        };
+        observer.event(Event::Warning {
+            message: message.text(options.lang),
+        });
+    }
+
+    // Like the threads runtime, custom sections don't belong to any unit.
+    let mut custom_sections = vec![];
+    if whole_module {
+        for hint in &options.custom_sections {
+            let mut summary = custom::Summary {
+                name: hint.name.clone(),
+                ..Default::default()
+            };
+            for segment in segments.iter_mut().filter(|s| s.name == hint.name) {
+                let data = &module[segment.start as usize..segment.end as usize];
+                let parsed = match hint.parser.parse(data) {
+                    Ok(parsed) => parsed,
+                    Err(err) => {
+                        let message = Message::CustomSectionUnparsable {
+                            name: hint.name.clone(),
+                            error: err.to_string(),
+                        };
+                        observer.event(Event::Warning {
+                            message: message.text(options.lang),
+                        });
+                        Content::Opaque.parse(data)?
+                    }
+                };
+                for (part, size) in &parsed.parts {
+                    let size = (*size).min(segment.unmapped());
+                    segment.mapped += size;
+                    attributed += size;
+                    let key = format!(
+                        "@section: {};{}",
+                        escape_frame(&segment.name),
+                        escape_frame(part)
+                    );
+                    *contributors.entry(key).or_insert(0) += size;
+                }
+                summary.add(segment.size(), &parsed);
+            }
+            if summary.sections > 0 {
+                custom_sections.push(summary);
+            }
+        }
+    }
+
+    // Tags have no debug info, but the name section may name them.
+    let tags = match whole_module {
+        true => eh::tags(module)?,
+        false => vec![],
+    };
+    for tag in tags {
+        let range = tag.file_range.clone();
+        let Some(segment) = segments
+            .iter_mut()
+            .find(|s| s.start <= range.start && s.end >= range.end)
+        else {
+            continue;
+        };
+        let size = range.end - range.start;
+        segment.mapped += size;
+        attributed += size;
+        let key = format!(
+            "@section: {};{}",
+            escape_frame(&segment.name),
+            escape_frame(&tag.frame())
+        );
+        *contributors.entry(key).or_insert(0) += size;
+    }
+
+    // Strings of the string proposals are counted apart from the rest of their sections, to
+    // compare them with the module's current string encoding.
+    let strings = match whole_module {
+        true => strings::entries(module)?,
+        false => vec![],
+    };
+    for (kind, key) in [
+        (strings::Kind::Builtin, "js-string-builtins"),
+        (strings::Kind::Constant, "string-constants"),
+        (strings::Kind::Literal, "string-literals"),
+    ] {
+        let count = strings.iter().filter(|entry| entry.kind == kind).count();
+        if count > 0 {
+            metadata.push((key.to_string(), count.to_string()));
+        }
+    }
+    for entry in strings {
+        let range = entry.file_range.clone();
+        let Some(segment) = segments
+            .iter_mut()
+            .find(|s| s.start <= range.start && s.end >= range.end)
+        else {
+            continue;
+        };
+        let size = range.end - range.start;
+        segment.mapped += size;
+        attributed += size;
+        let [group, name] = entry.frames();
+        let key = format!(
+            "@section: {};{group};{}",
+            escape_frame(&segment.name),
+            escape_frame(&name)
+        );
+        *contributors.entry(key).or_insert(0) += size;
+    }
+
+    // Static data, by the variables the debug info places in it, and the strings among the rest.
+    let (variables, data_strings) = match whole_module {
+        true => (
+            data::variables(module, &sections.dwarf())?,
+            data::strings(module)?,
+        ),
+        false => (vec![], vec![]),
+    };
+    let variable_ranges = merge_ranges(
+        variables
+            .iter()
+            .map(|variable| variable.file_range.clone())
+            .collect(),
+    );
+    for variable in variables {
+        let range = variable.file_range;
+        let Some(segment) = segments
+            .iter_mut()
+            .find(|s| s.start <= range.start && s.end >= range.end)
+        else {
+            continue;
+        };
+        let size = range.end - range.start;
+        segment.mapped += size;
+        attributed += size;
+        let file = variable.file.map(|file| paths.normalize(&file));
+        // Like filtered code, filtered data counts as mapped.
+        if !filter.keeps(file.as_deref()) {
+            continue;
+        }
+        let name = match options.raw_symbols {
+            true => variable.name,
+            false => demangler.demangle(&variable.name).to_string(),
+        };
+        let mut path = vec![
+            format!("@section: {}", segment.name),
+            data::FRAME.to_string(),
+        ];
+        path.extend(variable_frames(file.as_deref(), &name, options));
+        if !options.files_only {
+            path.push(format!("{}{name}", data::VARIABLE_PREFIX));
+        }
+        let key = path
+            .iter()
+            .map(|frame| escape_frame(frame))
+            .collect::<Vec<_>>()
+            .join(";");
+        *contributors.entry(key).or_insert(0) += size;
+    }
+    for range in data_strings {
+        let Some(segment) = segments
+            .iter_mut()
+            .find(|s| s.start <= range.start && s.end >= range.end)
+        else {
+            continue;
+        };
+        let size = range.end - range.start - covered_bytes(&variable_ranges, &range);
+        if size == 0 {
+            continue;
+        }
+        segment.mapped += size;
+        attributed += size;
+        let key = format!(
+            "@section: {};{};{}",
+            escape_frame(&segment.name),
+            data::FRAME,
+            data::STRINGS_FRAME
+        );
+        *contributors.entry(key).or_insert(0) += size;
+    }
+
+    // Type indices and size prefixes belong to no function, unless a function's entry was
+    // attributed as a whole. With many small functions, they add up.
+    let overhead = match whole_module {
+        true => wasm::encoding_overhead(module)?,
+        false => vec![],
+    };
+    let mut covered = merge_ranges(covered);
+    for range in &overhead {
+        let Some(segment) = segments
+            .iter_mut()
+            .find(|s| s.start <= range.start && s.end >= range.end)
+        else {
+            continue;
+        };
+        let size = range.end - range.start - covered_bytes(&covered, range);
+        if size == 0 {
+            continue;
+        }
+        segment.mapped += size;
+        attributed += size;
+        let key = format!(
+            "@section: {};{ENCODING_OVERHEAD_FRAME}",
+            escape_frame(&segment.name)
+        );
+        *contributors.entry(key).or_insert(0) += size;
+    }
+
+    // What is left of every function after the debug info, so the code section's unmapped bytes
+    // point at the functions to look at.
+    let unmapped = match options.unmapped_functions && whole_module {
+        true => {
+            covered.extend(overhead);
+            let covered = merge_ranges(covered);
+            unmapped_functions(module, &covered, options, &mut demangler)?
+        }
+        false => vec![],
+    };
+    for (range, name, size) in unmapped {
+        let Some(segment) = segments
+            .iter_mut()
+            .find(|s| s.start <= range.start && s.end >= range.end)
+        else {
+            continue;
+        };
+        // Overlapping line table ranges may already have counted some of these bytes as mapped.
+        let size = size.min(segment.unmapped());
+        segment.mapped += size;
+        let key = format!(
+            "@section: {};{UNMAPPED_FRAME};@function: {}",
+            escape_frame(&segment.name),
+            escape_frame(&name)
+        );
+        *contributors.entry(key).or_insert(0) += size;
+    }
+
+    let mut unattributed = 0;
+    let unmapped_segments = match whole_module {
+        true => segments,
+        false => vec![],
+    };
+    for segment in unmapped_segments {
+        let key = format!(
+            "@section: {};<no mapping info>",
+            escape_frame(&segment.name)
+        );
+        let unmapped = segment.unmapped();
+        unattributed += unmapped;
+        *contributors.entry(key).or_insert(0) += unmapped;
+    }
+    if corrupted > 0 && whole_module {
+        unattributed += corrupted;
+        let key = format!("@corrupted/truncated ({corrupted} bytes)");
+        contributors.insert(key, corrupted);
+    }
+
+    let units = envelopes.map(|e| e.units).unwrap_or_default();
+    for unit in &units {
+        observer.event(Event::UnitAnalyzed {
+            name: unit.name.clone(),
+            size: unit.envelope,
+            attributed: unit.attributed,
+        });
+    }
+
+    let mut uncertainty = None;
+    if options.metric == Metric::Gzip {
+        let estimate;
+        (contributors, estimate) = compressed::estimate(module, &section_ranges, &contributors)?;
+        uncertainty = Some(estimate);
+        let size: u64 = contributors.values().sum();
+        metadata.push(("gzip-size".to_string(), size.to_string()));
+    }
+    let mut instantiations = HashMap::new();
+    if options.collapse_generics {
+        (contributors, instantiations) = group::collapse_generic_frames(&contributors);
+        if let Some(Uncertainty { low, high }) = &mut uncertainty {
+            *low = group::collapse_generic_frames(low).0;
+            *high = group::collapse_generic_frames(high).0;
+        }
+    }
+
+    finish_phase(Phase::Attribute, &mut phase_started, &mut phases, observer);
+    observer.event(Event::AnalysisFinished {
+        attributed,
+        unattributed,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    });
+    Ok(Analysis {
+        contributors,
+        units,
+        names,
+        profile,
+        metadata,
+        custom_sections,
+        instantiations,
+        uncertainty,
+        phases,
+        address_map,
+        unparseable_from,
+    })
+}
+
+/// Records that `phase` ended now, and starts the next one.
+fn finish_phase(
+    phase: Phase,
+    started: &mut Instant,
+    phases: &mut Vec<(Phase, Duration)>,
+    observer: &mut dyn Observer,
+) {
+    let elapsed = started.elapsed();
+    *started = Instant::now();
+    phases.push((phase, elapsed));
+    observer.event(Event::PhaseFinished {
+        phase,
+        elapsed_ms: elapsed.as_millis() as u64,
+    });
+}
+
+/// Frames between [`data::FRAME`] and a variable, grouped like code.
+fn variable_frames(file: Option<&str>, name: &str, options: &Options) -> Vec<String> {
+    let mut path = vec![];
+    let runtime = file.and_then(libc::runtime_for_path);
+    let library = file.and_then(group::c_library_for_path);
+    match options.group_by {
+        GroupBy::Files => {
+            let file = file.unwrap_or("<unknown file>");
+            if let Some((group, within)) = runtime {
+                path.push(libc::FRAME.to_string());
+                path.push(group.to_string());
+                path.extend(within.into_iter().map(str::to_string));
+            } else if let Some((library, within)) = library {
+                path.push(group::C_LIBRARIES.to_string());
+                path.push(library.to_string());
+                path.extend(within.into_iter().map(str::to_string));
+            } else if !options.roots.is_empty() {
+                let (root, within) = group::root_path(file, &options.roots);
+                path.push(root.to_string());
+                path.extend(within.into_iter().map(str::to_string));
+            } else {
+                path.extend(
+                    file.split('/')
+                        .skip_while(|c| c.is_empty())
+                        .map(str::to_string),
+                );
+            }
+        }
+        GroupBy::Crate => {
+            if let Some((group, _)) = runtime {
+                path.push(libc::FRAME.to_string());
+                path.push(group.to_string());
+            } else if let Some((library, _)) = library {
+                path.push(group::C_LIBRARIES.to_string());
+                path.push(library.to_string());
+            } else {
+                let name = file
+                    .and_then(group::crate_for_path)
+                    .or_else(|| group::crate_for_symbol(name))
+                    .unwrap_or_else(|| "<unknown crate>".to_string());
+                path.push(format!("{}{name}", group::CRATE_PREFIX));
+            }
+        }
+        GroupBy::Namespace => {
+            path = group::namespace_path(name);
+            path.pop();
+        }
+    }
+    path
+}
+
+/// Sorts `ranges` and merges the ones that overlap or touch.
+fn merge_ranges(mut ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    ranges.sort_by_key(|range| range.start);
+    ranges.dedup_by(|next, range| {
+        let merge = next.start <= range.end;
+        if merge {
+            range.end = range.end.max(next.end);
+        }
+        merge
+    });
+    ranges
+}
+
+/// How many bytes of `range` the sorted, merged `covered` ranges cover.
+fn covered_bytes(covered: &[Range<u64>], range: &Range<u64>) -> u64 {
+    let first = covered.partition_point(|covered| covered.end <= range.start);
+    covered[first..]
+        .iter()
+        .take_while(|covered| covered.start < range.end)
+        .map(|covered| covered.end.min(range.end) - covered.start.max(range.start))
+        .sum()
+}
+
+/// The file ranges of the functions' code section entries that the sorted, merged `covered`
+/// ranges leave out in part, with the functions' names and how many of their bytes aren't
+/// covered.
+fn unmapped_functions(
+    module: &[u8],
+    covered: &[Range<u64>],
+    options: &Options,
+    demangler: &mut Demangler,
+) -> anyhow::Result<Vec<(Range<u64>, String, u64)>> {
+    let names = wasm::function_names(module)?;
+    let mut unmapped = vec![];
+    for function in wasm::functions(module)? {
+        let entry =
+            function.file_offset - function.size_prefix..function.file_offset + function.size;
+        let size = function.encoded_size() - covered_bytes(covered, &entry);
+        if size == 0 {
+            continue;
+        }
+        let name = match names.get(&function.index) {
+            Some(name) if options.raw_symbols => name.to_string(),
+            Some(name) => demangler.demangle(name).to_string(),
+            None => format!("<function {}>", function.index),
+        };
+        unmapped.push((entry, name, size));
+    }
+    Ok(unmapped)
+}
+
+/// File ranges and names of the threads runtime functions that have no debug info.
+fn unmapped_threads_runtime<R: addr2line::gimli::Reader>(
+    module: &[u8],
+    context: &addr2line::Context<R>,
+) -> anyhow::Result<Vec<(String, std::ops::Range<u64>)>> {
+    let names = wasm::function_names(module)?;
+    let mut unmapped = vec![];
+    for function in wasm::functions(module)? {
+        let Some(name) = names.get(&function.index) else {
+            continue;
+        };
+        if !threads::is_threads_runtime(name) {
+            continue;
+        }
+        let range = function.code_range();
+        let mut locations = context.find_location_range(range.start, range.end)?;
+        if FallibleIterator::next(&mut locations)?.is_some() {
+            continue;
+        }
+        let file_range = function.file_offset..function.file_offset + function.size;
+        unmapped.push((name.to_string(), file_range));
+    }
+    Ok(unmapped)
+}
+
+/// Locations of the code between `begin` and `end`: the rows of the line tables, plus the ranges
+/// of units without line tables, which only have a file.
+fn locations_in<'ctx, R: addr2line::gimli::Reader>(
+    context: &'ctx addr2line::Context<R>,
+    lineless: &'ctx [lineless::Range],
+    begin: u64,
+    end: u64,
+) -> anyhow::Result<Vec<(u64, u64, addr2line::Location<'ctx>)>> {
+    let mut locations: Vec<_> =
+        FallibleIterator::collect(context.find_location_range(begin, end)?)?;
+    let first = lineless.partition_point(|range| range.end <= begin);
+    for range in lineless[first..]
+        .iter()
+        .take_while(|range| range.begin < end)
+    {
+        let (start, stop) = (range.begin.max(begin), range.end.min(end));
+        let location = addr2line::Location {
+            file: Some(&range.file),
+            line: None,
+            column: None,
+        };
+        locations.push((start, stop - start, location));
+    }
+    Ok(locations)
+}
+
+/// Code attributed to one source location.
+struct Attributed<'ctx> {
+    /// Address to look up the functions of the code at.
+    address: u64,
+    /// Start of the code, as DWARF address and in the file.
+    start: u64,
+    file_start: u64,
+    size: u64,
+    location: addr2line::Location<'ctx>,
+}
+
+/// Locations of the code of every function with line info in `ranges`. The locations of a function cover its whole entry in the code
+/// section: bytes without a location of their own (the size prefix, local declarations, padding)
+/// go to the location before them, or to the function's first one.
+fn code_section_locations<'ctx, R: addr2line::gimli::Reader>(
+    module: &[u8],
+    context: &'ctx addr2line::Context<R>,
+    lineless: &'ctx [lineless::Range],
+    ranges: &[(u64, u64)],
+) -> anyhow::Result<Vec<Attributed<'ctx>>> {
+    let mut locations = vec![];
+    for function in wasm::functions(module)? {
+        let body = function.code_range();
+        let mut found = vec![];
+        for &(begin, end) in ranges {
+            let (begin, end) = (begin.max(body.start), end.min(body.end));
+            if begin < end {
+                found.extend(locations_in(context, lineless, begin, end)?);
+            }
+        }
+        found.sort_by_key(|(address, _, _)| *address);
+        // The size prefix lies before the body's address.
+        let mut pieces: Vec<Attributed> = vec![];
+        let mut covered = body.start - function.size_prefix;
+        for (address, size, location) in found {
+            let end = (address + size).min(body.end);
+            if end <= covered {
+                continue;
+            }
+            let start = match pieces.last_mut() {
+                Some(last) => {
+                    last.size = address.max(covered) - last.start;
+                    last.start + last.size
+                }
+                None => covered,
+            };
+            pieces.push(Attributed {
+                address: address.max(start),
+                start,
+                file_start: function.file_offset + start - function.code_offset,
+                size: end - start,
+                location,
+            });
+            covered = end;
+        }
+        if let Some(last) = pieces.last_mut() {
+            last.size = body.end - last.start;
+        }
+        locations.extend(pieces);
+    }
+    Ok(locations)
+}
+
+/// Splits the `locations` at the `boundaries` inside them, so that every piece is looked up on its
+/// own. Line table rows usually end where an inlined call begins or ends, but not always, and a row
+/// running across one of its ranges would give all of its bytes to the same chain of functions.
+fn split_at<'ctx>(locations: Vec<Attributed<'ctx>>, boundaries: &[u64]) -> Vec<Attributed<'ctx>> {
+    let mut pieces = Vec::with_capacity(locations.len());
+    for attributed in locations {
+        let end = attributed.start + attributed.size;
+        let first = boundaries.partition_point(|&boundary| boundary <= attributed.address);
+        let mut piece = attributed;
+        for &boundary in boundaries[first..]
+            .iter()
+            .take_while(|&&boundary| boundary < end)
+        {
+            let size = boundary - piece.start;
+            let location = addr2line::Location {
+                file: piece.location.file,
+                line: piece.location.line,
+                column: piece.location.column,
+            };
+            let next = Attributed {
+                address: boundary,
+                start: boundary,
+                file_start: piece.file_start + size,
+                size: piece.size - size,
+                location,
+            };
+            piece.size = size;
+            pieces.push(std::mem::replace(&mut piece, next));
+        }
+        pieces.push(piece);
+    }
+    pieces
+}
+
+/// Locations covering every function's entry in the code section, size prefix included, for
+/// modules without debug info. The functions are named by [`symbols`] instead.
+fn name_section_locations(module: &[u8]) -> anyhow::Result<Vec<Attributed<'static>>> {
+    Ok(wasm::functions(module)?
+        .into_iter()
+        .map(|function| Attributed {
+            address: function.code_offset,
+            start: function.code_offset - function.size_prefix,
+            file_start: function.file_offset - function.size_prefix,
+            size: function.encoded_size(),
+            location: addr2line::Location {
+                file: None,
+                line: None,
+                column: None,
+            },
+        })
+        .collect())
+}
+
+/// Locations of every function's entry in the code section, split where the mappings of `map`
+/// start. The size prefix and any code before the first mapping go to the function's first one.
+fn source_map_locations<'map>(
+    module: &[u8],
+    map: &'map SourceMap,
+) -> anyhow::Result<Vec<Attributed<'map>>> {
+    let mut locations = vec![];
+    for function in wasm::functions(module)? {
+        let entry_start = function.file_offset - function.size_prefix;
+        let end = function.file_offset + function.size;
+        // Source maps locate code by file offset, DWARF and the name section by code offset.
+        let code_start = function.file_offset - function.code_offset;
+        let first = map
+            .mappings
+            .partition_point(|mapping| mapping.offset < entry_start);
+        let within: Vec<_> = map.mappings[first..]
+            .iter()
+            .take_while(|mapping| mapping.offset < end)
+            .collect();
+        if within.is_empty() {
+            locations.push(Attributed {
+                address: function.code_offset,
+                start: entry_start - code_start,
+                file_start: entry_start,
+                size: end - entry_start,
+                location: addr2line::Location {
+                    file: None,
+                    line: None,
+                    column: None,
+                },
+            });
+            continue;
+        }
+        for (i, mapping) in within.iter().enumerate() {
+            let start = match i {
+                0 => entry_start,
+                _ => mapping.offset,
+            };
+            let piece_end = within.get(i + 1).map_or(end, |next| next.offset);
+            if piece_end <= start {
+                continue;
+            }
+            let file = map.source(mapping);
+            locations.push(Attributed {
+                address: start.max(function.file_offset) - code_start,
+                start: start - code_start,
+                file_start: start,
+                size: piece_end - start,
+                location: addr2line::Location {
+                    file,
+                    line: file.map(|_| mapping.line),
+                    column: file.map(|_| mapping.column),
+                },
+            });
+        }
+    }
+    Ok(locations)
+}
+
+/// All function names in the name section and all linkage names in the debug info.
+fn symbol_names(
+    module: &[u8],
+    dwarf: &addr2line::gimli::Dwarf<dwarf::Reader>,
+) -> anyhow::Result<Vec<String>> {
+    let mut names: Vec<_> = wasm::function_names(module)?
+        .into_values()
+        .map(|name| name.to_string())
+        .collect();
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        let unit = dwarf.unit(header)?;
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs()? {
+            for name in [
+                addr2line::gimli::DW_AT_linkage_name,
+                addr2line::gimli::DW_AT_MIPS_linkage_name,
+            ] {
+                let value = entry.attr_value(name)?;
+                names.extend(value.and_then(|value| dwarf::attr_string(dwarf, &unit, value)));
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// Code ranges of the named functions, sorted by address.
+fn symbols(
+    module: &[u8],
+    options: &Options,
+    demangler: &mut Demangler,
+) -> anyhow::Result<Vec<(std::ops::Range<u64>, String)>> {
+    let names = wasm::function_names(module)?;
+    let mut symbols: Vec<_> = wasm::functions(module)?
+        .into_iter()
+        .filter_map(|function| {
+            let name = names.get(&function.index)?;
+            let name = match options.raw_symbols {
+                true => name.to_string(),
+                false => demangler.demangle(name).to_string(),
+            };
+            Some((function.code_range(), name))
+        })
+        .collect();
+    symbols.sort_by_key(|(range, _)| range.start);
+    Ok(symbols)
+}
+
+fn symbol_for_address(symbols: &[(std::ops::Range<u64>, String)], address: u64) -> Option<&str> {
+    let index = symbols.partition_point(|(range, _)| range.start <= address);
+    let (range, name) = &symbols[index.checked_sub(1)?];
+    range.contains(&address).then_some(name.as_str())
+}
+
+/// Coalesced address ranges of the units whose names match one of the glob `patterns`.
+fn selected_unit_ranges(
+    dwarf: &addr2line::gimli::Dwarf<dwarf::Reader>,
+    patterns: &[String],
+) -> anyhow::Result<Vec<(u64, u64)>> {
+    let matcher = glob::matcher(patterns)?;
+    let mut ranges = vec![];
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        let unit = dwarf.unit(header)?;
+        if !matcher.is_match(&dwarf::unit_name(&unit)) {
+            continue;
+        }
+        let tombstone = !0u64 >> (64 - 8 * u32::from(unit.encoding().address_size));
+        let mut unit_ranges = dwarf.unit_ranges(&unit)?;
+        while let Some(range) = unit_ranges.next()? {
+            if range.begin < tombstone && range.end > range.begin {
+                ranges.push((range.begin, range.end));
+            }
+        }
+    }
+    dwarf::coalesce(&mut ranges);
+    Ok(ranges)
+}
+
+fn functions_for_address(
+    options: &Options,
+    demangler: &mut Demangler,
+    frames: &mut Frames,
+    context: &addr2line::Context<dwarf::Reader>,
+    split_dwarf: &SplitDwarf,
+    map_start: u64,
+) -> anyhow::Result<Vec<Rc<str>>> {
+    let funcs: Vec<_> = split_dwarf
+        .resolve(context.find_frames(map_start))?
+        .filter_map(|frame| {
+            let Some(function) = frame.function else {
+                return Ok(Some(frames.intern("<Unknown>")));
+            };
+            let name = addr2line::gimli::Reader::to_string_lossy(&function.name)?;
+            if options.raw_symbols {
+                return Ok(Some(frames.intern(&name)));
+            }
+            // The demangler's cache may have evicted the name since.
+            Ok(Some(frames.intern(&demangler.demangle(&name))))
+        })
+        .collect()?;
+    Ok(funcs)
+}
+
+/// The function names of the index, each stored once however many locations it appears at.
+#[derive(Default)]
+struct Frames(HashSet<Rc<str>>);
+
+impl Frames {
+    fn intern(&mut self, name: &str) -> Rc<str> {
+        if let Some(frame) = self.0.get(name) {
+            return frame.clone();
+        }
+        let frame: Rc<str> = name.into();
+        self.0.insert(frame.clone());
+        frame
+    }
+}
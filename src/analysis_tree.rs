@@ -0,0 +1,183 @@
+//! The breakdown as a tree of typed nodes, for Rust tools that embed the analysis rather than
+//! parse the `;`-joined frame paths of [`Contributors`].
+
+use serde::Serialize;
+
+use crate::{
+    data,
+    group::{self, GroupBy},
+    Contributors,
+};
+
+/// What a node of an [`AnalysisTree`] stands for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NodeKind {
+    /// The whole module.
+    Module,
+    /// A section of the module, like `<code>` or a custom section.
+    Section,
+    /// Code in a non-default DWARF segment (address space).
+    Segment,
+    /// A group wasmphobia added, like `@libc-runtime` or `@compiler-generated`.
+    Group,
+    /// A Rust crate, with [`GroupBy::Crate`].
+    Crate,
+    /// A directory of source files.
+    Directory,
+    /// A source file.
+    File,
+    /// A C++ or Rust namespace or type, with [`GroupBy::Namespace`].
+    Namespace,
+    /// A function. Functions inlined into another function are its children.
+    Function,
+    /// A variable in the data segments, below [`crate::data::FRAME`].
+    Variable,
+    /// Bytes that could not be attributed to anything more specific, like `<no mapping info>`.
+    Unattributed,
+}
+
+/// A node of an [`AnalysisTree`]. Names are shown without the prefixes of the frame paths, e.g. a
+/// section is called `<code>` rather than `@section: <code>`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub name: String,
+    /// Total size, including the children.
+    pub size: u64,
+    /// Bytes attributed to this node directly rather than to one of its children.
+    #[serde(rename = "self")]
+    pub self_size: u64,
+    /// Children, sorted by size in descending order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    fn new(kind: NodeKind, name: &str) -> Node {
+        Node {
+            kind,
+            name: name.to_string(),
+            size: 0,
+            self_size: 0,
+            children: vec![],
+        }
+    }
+
+    /// This node and all nodes below it, depth first.
+    pub fn iter(&self) -> impl Iterator<Item = &Node> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+
+    /// The nodes of `kind` at or below this node, depth first.
+    pub fn find(&self, kind: NodeKind) -> impl Iterator<Item = &Node> {
+        self.iter().filter(move |node| node.kind == kind)
+    }
+
+    fn sort(&mut self) {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            node.children
+                .sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+            stack.extend(node.children.iter_mut());
+        }
+    }
+}
+
+/// The breakdown of a module as nested, typed nodes, see [`crate::analyze_tree`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct AnalysisTree {
+    /// The module, with its sections (and segments) as children.
+    pub root: Node,
+}
+
+impl AnalysisTree {
+    /// Builds the tree from `contributors`, which were produced with `group_by`. Frames below a
+    /// section that are neither functions nor added by wasmphobia are directories and files, or
+    /// namespaces with [`GroupBy::Namespace`].
+    pub fn from_contributors(contributors: &Contributors, group_by: GroupBy) -> AnalysisTree {
+        let mut entries: Vec<(Vec<&str>, u64)> = contributors
+            .iter()
+            .map(|(key, &size)| (key.split(';').collect(), size))
+            .collect();
+        entries.sort();
+        let mut root = Node::new(NodeKind::Module, "all");
+        for (frames, size) in entries {
+            // The last path component before the functions is the file.
+            let file = frames
+                .iter()
+                .rposition(|frame| kind_of(frame, group_by) == Some(NodeKind::Directory));
+            root.size += size;
+            let mut node = &mut root;
+            for (i, frame) in frames.iter().enumerate() {
+                let (kind, name) = match kind_of(frame, group_by) {
+                    Some(NodeKind::Directory) if Some(i) == file => (NodeKind::File, *frame),
+                    Some(kind) => (kind, strip_prefix(frame)),
+                    None => (NodeKind::Namespace, *frame),
+                };
+                // With sorted paths, a frame that already exists is always the most recently added
+                // child.
+                let exists = node
+                    .children
+                    .last()
+                    .is_some_and(|child| child.name == name && child.kind == kind);
+                if !exists {
+                    node.children.push(Node::new(kind, name));
+                }
+                node = node.children.last_mut().expect("just added");
+                node.size += size;
+            }
+            node.self_size += size;
+        }
+        root.sort();
+        AnalysisTree { root }
+    }
+
+    /// Total size of the module.
+    pub fn size(&self) -> u64 {
+        self.root.size
+    }
+}
+
+/// The kind of `frame`. Path components are reported as directories, and as `None` with namespace
+/// grouping, where they are namespaces.
+fn kind_of(frame: &str, group_by: GroupBy) -> Option<NodeKind> {
+    let kind = if frame.starts_with("@section: ") {
+        NodeKind::Section
+    } else if frame.starts_with("@segment: ") {
+        NodeKind::Segment
+    } else if frame.starts_with("@function: ") {
+        NodeKind::Function
+    } else if frame.starts_with(data::VARIABLE_PREFIX) {
+        NodeKind::Variable
+    } else if frame.starts_with('@') {
+        NodeKind::Group
+    } else if frame.starts_with(group::CRATE_PREFIX) {
+        NodeKind::Crate
+    } else if frame.starts_with('<') && frame.ends_with('>') {
+        NodeKind::Unattributed
+    } else if group_by == GroupBy::Namespace {
+        return None;
+    } else {
+        NodeKind::Directory
+    };
+    Some(kind)
+}
+
+fn strip_prefix(frame: &str) -> &str {
+    [
+        "@section: ",
+        "@segment: ",
+        "@function: ",
+        data::VARIABLE_PREFIX,
+        group::CRATE_PREFIX,
+    ]
+    .into_iter()
+    .find_map(|prefix| frame.strip_prefix(prefix))
+    .unwrap_or(frame)
+}
@@ -0,0 +1,40 @@
+use crate::{analysis, Options, SizeTree};
+
+/// Push-based interface to the analysis for embedding in build tools.
+///
+/// The module is fed in chunks as it is produced (e.g. while a bundler emits it), and analyzed
+/// once it is complete. Nothing is read from or written to the filesystem.
+#[derive(Clone, Debug, Default)]
+pub struct Analyzer {
+    options: Options,
+    module: Vec<u8>,
+}
+
+impl Analyzer {
+    pub fn new(options: Options) -> Analyzer {
+        Analyzer {
+            options,
+            module: vec![],
+        }
+    }
+
+    /// Appends the next chunk of the module.
+    pub fn push_bytes(&mut self, bytes: &[u8]) {
+        self.module.extend_from_slice(bytes);
+    }
+
+    /// Number of bytes pushed so far.
+    pub fn len(&self) -> usize {
+        self.module.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.module.is_empty()
+    }
+
+    /// Analyzes the complete module and returns its size breakdown.
+    pub fn finish(self) -> anyhow::Result<SizeTree> {
+        let analysis = analysis::analyze(&self.module, &self.options)?;
+        Ok(SizeTree::from_contributors(&analysis.contributors))
+    }
+}
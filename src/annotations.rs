@@ -0,0 +1,114 @@
+//! Metadata from a sidecar file, like the team that owns some code or the ticket to remove it,
+//! attached to the matching nodes of the JSON and HTML outputs. The file is a JSON array of
+//! rules:
+//!
+//! ```json
+//! [
+//!   { "file": "*/src/legacy/*", "metadata": { "owner": "platform", "deprecated": "yes" } },
+//!   { "symbol": "serde_json::*", "metadata": { "ticket": "DATA-123" } }
+//! ]
+//! ```
+//!
+//! A `file` pattern matches directories and files by their path below the section, a `symbol`
+//! pattern matches functions. A rule with both matches the functions in matching files. Later
+//! rules override the keys of earlier ones.
+
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{bail, Context};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{glob, group, SizeTree};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleJson {
+    #[serde(default)]
+    symbol: Option<String>,
+    #[serde(default)]
+    file: Option<String>,
+    metadata: BTreeMap<String, String>,
+}
+
+struct Rule {
+    symbol: Option<Regex>,
+    file: Option<Regex>,
+    metadata: BTreeMap<String, String>,
+}
+
+/// The rules of an annotations file.
+#[derive(Default)]
+pub struct Annotations {
+    rules: Vec<Rule>,
+}
+
+impl Annotations {
+    pub fn load(path: &Path) -> anyhow::Result<Annotations> {
+        let data = std::fs::read(path).with_context(|| format!("Reading {}", path.display()))?;
+        Annotations::parse(&data).with_context(|| format!("Reading annotations {}", path.display()))
+    }
+
+    pub fn parse(data: &[u8]) -> anyhow::Result<Annotations> {
+        let rules: Vec<RuleJson> = serde_json::from_slice(data)?;
+        let compile =
+            |pattern: Option<String>| pattern.map(|pattern| glob::matcher(&[pattern])).transpose();
+        let rules = rules
+            .into_iter()
+            .map(|rule| {
+                if rule.symbol.is_none() && rule.file.is_none() {
+                    bail!("An annotation needs a symbol or file pattern");
+                }
+                Ok(Rule {
+                    symbol: compile(rule.symbol)?,
+                    file: compile(rule.file)?,
+                    metadata: rule.metadata,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Annotations { rules })
+    }
+
+    /// Attaches the metadata of every matching rule to the nodes of `tree`.
+    pub fn apply(&self, tree: &mut SizeTree) {
+        if self.rules.is_empty() {
+            return;
+        }
+        // Nodes are visited with the path of the directory or file they are in.
+        let mut stack = vec![(tree, String::new())];
+        while let Some((node, path)) = stack.pop() {
+            for child in &mut node.children {
+                let function = child.name.strip_prefix("@function: ");
+                let child_path = match function {
+                    // Groups, crates and placeholders like `<no mapping info>` aren't part of paths.
+                    None if !child.name.starts_with(['@', '<'])
+                        && !child.name.starts_with(group::CRATE_PREFIX) =>
+                    {
+                        match path.is_empty() {
+                            true => child.name.trim_start_matches('/').to_string(),
+                            false => format!("{path}/{}", child.name),
+                        }
+                    }
+                    _ => path.clone(),
+                };
+                for rule in &self.rules {
+                    let matches = match (function, &rule.symbol) {
+                        (Some(name), Some(symbol)) => {
+                            symbol.is_match(name)
+                                && rule.file.as_ref().is_none_or(|file| file.is_match(&path))
+                        }
+                        (None, None) if child_path != path => rule
+                            .file
+                            .as_ref()
+                            .is_some_and(|file| file.is_match(&child_path)),
+                        _ => false,
+                    };
+                    if matches {
+                        child.annotations.extend(rule.metadata.clone());
+                    }
+                }
+                stack.push((child, child_path));
+            }
+        }
+    }
+}
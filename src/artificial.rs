@@ -0,0 +1,73 @@
+//! Finds code the compiler made up rather than translated from the source, like thunks, implicit
+//! constructors and destructors, or drop glue. DWARF marks these functions with
+//! `DW_AT_artificial`.
+
+use std::collections::HashSet;
+
+use addr2line::gimli;
+
+use crate::dwarf::{self, Reader};
+
+/// Frame that compiler-generated code is grouped under.
+pub const FRAME: &str = "@compiler-generated";
+
+/// Returns the coalesced address ranges of all compiler-generated functions.
+///
+/// A function is compiler-generated if its own entry is marked `DW_AT_artificial`, or the
+/// declaration it completes (`DW_AT_specification`) or instantiates (`DW_AT_abstract_origin`) is.
+/// Declarations are only followed within their unit.
+pub fn ranges(dwarf: &gimli::Dwarf<Reader>) -> anyhow::Result<Vec<(u64, u64)>> {
+    let mut ranges = vec![];
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        let unit = dwarf.unit(header)?;
+        let tombstone = !0u64 >> (64 - 8 * u32::from(unit.encoding().address_size));
+
+        let mut artificial = HashSet::new();
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs()? {
+            if entry.tag() == gimli::DW_TAG_subprogram && is_artificial(entry)? {
+                artificial.insert(entry.offset());
+            }
+        }
+        if artificial.is_empty() {
+            continue;
+        }
+
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs()? {
+            if entry.tag() != gimli::DW_TAG_subprogram {
+                continue;
+            }
+            let mut generated = artificial.contains(&entry.offset());
+            for name in [gimli::DW_AT_specification, gimli::DW_AT_abstract_origin] {
+                let origin = match entry.attr_value(name)? {
+                    Some(gimli::AttributeValue::UnitRef(offset)) => Some(offset),
+                    Some(gimli::AttributeValue::DebugInfoRef(offset)) => {
+                        offset.to_unit_offset(&unit.header)
+                    }
+                    _ => None,
+                };
+                generated |= origin.is_some_and(|offset| artificial.contains(&offset));
+            }
+            if !generated {
+                continue;
+            }
+            let mut die_ranges = dwarf.die_ranges(&unit, entry)?;
+            while let Some(range) = die_ranges.next()? {
+                if range.begin < tombstone && range.end > range.begin {
+                    ranges.push((range.begin, range.end));
+                }
+            }
+        }
+    }
+    dwarf::coalesce(&mut ranges);
+    Ok(ranges)
+}
+
+fn is_artificial(entry: &gimli::DebuggingInformationEntry<Reader>) -> anyhow::Result<bool> {
+    Ok(matches!(
+        entry.attr_value(gimli::DW_AT_artificial)?,
+        Some(gimli::AttributeValue::Flag(true))
+    ))
+}
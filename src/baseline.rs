@@ -0,0 +1,152 @@
+//! Baselines of the file and function sizes of a module, committed next to the code so that CI
+//! can tell which files and functions grew since.
+
+use std::{collections::BTreeMap, io::Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diff,
+    messages::{Lang, Message},
+    Contributors,
+};
+
+/// Version of the snapshot file format, bumped when it changes incompatibly.
+const FORMAT: u32 = 1;
+
+/// Sizes of a module, its files and its functions, as written by `wasmphobia snapshot`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub format: u32,
+    /// The metric the sizes are in, as passed to `--metric`.
+    pub metric: String,
+    pub total: u64,
+    /// Sections and source files, with their path joined by `/`, like in [`diff::SizeDiff`].
+    pub files: BTreeMap<String, u64>,
+    /// Functions without Rust crate disambiguators, like in [`diff::SizeDiff`].
+    pub functions: BTreeMap<String, u64>,
+}
+
+impl Snapshot {
+    /// `contributors` must have been produced with file grouping, down to functions.
+    pub fn from_contributors(contributors: &Contributors, metric: &str) -> Snapshot {
+        Snapshot {
+            format: FORMAT,
+            metric: metric.to_string(),
+            total: contributors.values().sum(),
+            files: diff::by(contributors, diff::file),
+            functions: diff::by(contributors, diff::function),
+        }
+    }
+
+    pub fn parse(json: &str) -> anyhow::Result<Snapshot> {
+        let snapshot: Snapshot = serde_json::from_str(json)?;
+        anyhow::ensure!(
+            snapshot.format == FORMAT,
+            "Unsupported snapshot format {}, expected {FORMAT}",
+            snapshot.format
+        );
+        Ok(snapshot)
+    }
+}
+
+/// The whole module (`<total>`), a file or a function that grew more than allowed since the
+/// baseline. Files and functions that are new have an old size of 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Growth {
+    pub kind: &'static str,
+    pub name: String,
+    pub old: u64,
+    pub new: u64,
+}
+
+impl Growth {
+    pub fn delta(&self) -> u64 {
+        self.new - self.old
+    }
+
+    /// Growth in percent of the old size, `None` for new files and functions.
+    pub fn percent(&self) -> Option<f64> {
+        (self.old > 0).then(|| self.delta() as f64 * 100.0 / self.old as f64)
+    }
+}
+
+/// Returns what grew by more than `max_percent` of its baseline size and by at least `min_bytes`,
+/// the whole module first and then the largest growth first. The total is checked against
+/// `max_percent` alone.
+pub fn check(
+    baseline: &Snapshot,
+    current: &Snapshot,
+    max_percent: f64,
+    min_bytes: u64,
+) -> Vec<Growth> {
+    let exceeds = |old: u64, new: u64| new as f64 > old as f64 * (1.0 + max_percent / 100.0);
+    let mut grown = vec![];
+    if exceeds(baseline.total, current.total) {
+        grown.push(Growth {
+            kind: "total",
+            name: "<total>".to_string(),
+            old: baseline.total,
+            new: current.total,
+        });
+    }
+    let mut items = vec![];
+    for (kind, old, new) in [
+        ("file", &baseline.files, &current.files),
+        ("function", &baseline.functions, &current.functions),
+    ] {
+        for (name, &size) in new {
+            let old = old.get(name).copied().unwrap_or_default();
+            if size >= old + min_bytes && exceeds(old, size) {
+                items.push(Growth {
+                    kind,
+                    name: name.clone(),
+                    old,
+                    new: size,
+                });
+            }
+        }
+    }
+    items.sort_by(|a, b| b.delta().cmp(&a.delta()).then_with(|| a.name.cmp(&b.name)));
+    grown.extend(items);
+    grown
+}
+
+pub fn write_check(
+    grown: &[Growth],
+    max_percent: f64,
+    lang: Lang,
+    mut output: impl Write,
+) -> anyhow::Result<()> {
+    if grown.is_empty() {
+        writeln!(output, "{}", Message::NoGrowth { max_percent }.text(lang))?;
+        return Ok(());
+    }
+    let message = Message::Grown {
+        count: grown.len(),
+        max_percent,
+    };
+    writeln!(output, "{}", message.text(lang))?;
+    writeln!(
+        output,
+        "  {:>10} {:>10} {:>10} {:>8}  {:<8}  name",
+        "old", "new", "delta", "%", "kind"
+    )?;
+    for growth in grown {
+        let percent = match growth.percent() {
+            Some(percent) => format!("{percent:+.1}%"),
+            None => "new".to_string(),
+        };
+        writeln!(
+            output,
+            "  {:>10} {:>10} {:>+10} {:>8}  {:<8}  {}",
+            growth.old,
+            growth.new,
+            growth.delta(),
+            percent,
+            growth.kind,
+            growth.name
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::Context;
+
+use crate::{analyze, escape_frame, provenance::Provenance, Analysis, Contributors, Options};
+
+/// Analysis of one file of a batch.
+pub struct BatchEntry {
+    pub path: PathBuf,
+    /// Unique name of the entry within the batch (the file name, disambiguated if necessary).
+    pub name: String,
+    pub size: u64,
+    pub provenance: Provenance,
+    pub analysis: Analysis,
+}
+
+/// Analyzes all `paths`, using as many threads as there are CPUs. Falls back to analyzing one
+/// file after another where threads aren't available (e.g. on WASI).
+pub fn analyze_all(paths: &[PathBuf], options: &Options) -> anyhow::Result<Vec<BatchEntry>> {
+    let threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(paths.len());
+    let queue = Mutex::new(paths.iter().enumerate());
+    let results = Mutex::new(Vec::with_capacity(paths.len()));
+    let work = || loop {
+        let Some((i, path)) = queue.lock().unwrap().next() else {
+            return;
+        };
+        let result = analyze_file(path, options);
+        results.lock().unwrap().push((i, result));
+    };
+    std::thread::scope(|scope| {
+        for _ in 1..threads {
+            // If spawning fails, the remaining files are analyzed on the current thread.
+            let _ = std::thread::Builder::new().spawn_scoped(scope, work);
+        }
+        work();
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(i, _)| *i);
+    let mut entries = Vec::with_capacity(results.len());
+    for (((_, result), path), name) in results.into_iter().zip(paths).zip(names(paths)) {
+        let (provenance, analysis) = result?;
+        entries.push(BatchEntry {
+            path: path.clone(),
+            name,
+            size: provenance.size,
+            provenance,
+            analysis,
+        });
+    }
+    Ok(entries)
+}
+
+/// The names of the entries for `paths`: their file names, disambiguated if necessary.
+pub fn names(paths: &[PathBuf]) -> Vec<String> {
+    let mut names = std::collections::HashSet::new();
+    paths
+        .iter()
+        .map(|path| {
+            let base = path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+            let mut name = base.clone();
+            let mut n = 2;
+            while !names.insert(name.clone()) {
+                name = format!("{base}-{n}");
+                n += 1;
+            }
+            name
+        })
+        .collect()
+}
+
+fn analyze_file(path: &Path, options: &Options) -> anyhow::Result<(Provenance, Analysis)> {
+    let data = std::fs::read(path).with_context(|| format!("Reading {}", path.display()))?;
+    let analysis =
+        analyze(&data, options).with_context(|| format!("Analyzing {}", path.display()))?;
+    Ok((Provenance::new(Some(path), &data), analysis))
+}
+
+/// Combines the contributors of all entries, with each entry's name as an additional root frame.
+pub fn merge(entries: &[BatchEntry]) -> Contributors {
+    let mut merged = Contributors::new();
+    for entry in entries {
+        let name = escape_frame(&entry.name);
+        for (key, &size) in &entry.analysis.contributors {
+            *merged.entry(format!("{name};{key}")).or_default() += size;
+        }
+    }
+    merged
+}
@@ -0,0 +1,233 @@
+use std::{collections::BTreeMap, io::Write};
+
+use wasmparser::Operator;
+
+use crate::{
+    demangle::Demangler,
+    dwarf::{DwarfSections, Reader},
+    explain::strip_disambiguators,
+    group,
+    messages::{Lang, Message},
+    wasm,
+};
+
+/// Kinds of runtime checks whose panics are recognizable by the function they call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Check {
+    /// Arithmetic overflow checks, compiled in with `overflow-checks`.
+    Overflow,
+    /// Debug assertions: Rust's unsafe precondition checks (`debug-assertions`) and C's `assert`
+    /// (without `NDEBUG`).
+    DebugAssertion,
+    /// Bounds checks of slice indexing. They stay in release builds.
+    Bounds,
+}
+
+/// Operations whose overflow is only checked with `overflow-checks`.
+const OVERFLOW_OPERATIONS: &[&str] = &["add", "sub", "mul", "neg", "shl", "shr", "pow"];
+
+impl Check {
+    /// The kind of check a call to the (demangled) function `name` fails.
+    fn for_callee(name: &str) -> Option<Check> {
+        let name = strip_disambiguators(name);
+        let mut path = group::namespace_path(&name);
+        let last = path.pop()?;
+        // `core::panicking::panic_const::panic_const_add_overflow`, or
+        // `core::num::imp::overflow_panic::add` in newer toolchains.
+        let op = match path.last().map(String::as_str) {
+            Some("overflow_panic") => Some(last.as_str()),
+            _ => last
+                .strip_prefix("panic_const_")
+                .and_then(|op| op.strip_suffix("_overflow")),
+        };
+        if op.is_some_and(|op| OVERFLOW_OPERATIONS.contains(&op)) {
+            return Some(Check::Overflow);
+        }
+        match last.as_str() {
+            "precondition_check" | "__assert_fail" | "__assert2" | "__assert" => {
+                Some(Check::DebugAssertion)
+            }
+            "panic_bounds_check"
+            | "slice_start_index_len_fail"
+            | "slice_end_index_len_fail"
+            | "slice_index_order_fail" => Some(Check::Bounds),
+            _ => None,
+        }
+    }
+}
+
+/// Code spent on runtime checks by one crate.
+#[derive(Clone, Debug, Default)]
+pub struct CrateChecks {
+    /// Number of call sites and estimated bytes per kind of check.
+    pub checks: BTreeMap<Check, (usize, u64)>,
+}
+
+impl CrateChecks {
+    pub fn size(&self) -> u64 {
+        self.checks.values().map(|&(_, size)| size).sum()
+    }
+}
+
+/// Estimates the code spent on runtime checks, per crate.
+///
+/// Every call to a panic function of a check is a call site. Its bytes are estimated as the
+/// branch that leads to it, the instructions setting up the call's arguments (like the address
+/// of the panic location), the call itself and the `unreachable` after it; the comparison before
+/// the branch is often shared with other code and isn't counted. Call sites are attributed to the
+/// crate of the source file of the function they are in, according to DWARF, or to the first
+/// component of the function's name.
+pub fn checks(module: &[u8]) -> anyhow::Result<BTreeMap<String, CrateChecks>> {
+    let wasm_file = object::wasm::WasmFile::parse(module)?;
+    let sections = DwarfSections::load(&wasm_file)?;
+    let context = addr2line::Context::from_dwarf(sections.dwarf())?;
+    let mut demangler = Demangler::default();
+
+    let names = wasm::function_names(module)?;
+    let callees: BTreeMap<u32, Check> = names
+        .iter()
+        .filter_map(|(&index, name)| Some((index, Check::for_callee(&demangler.demangle(name))?)))
+        .collect();
+    let mut crates: BTreeMap<String, CrateChecks> = BTreeMap::new();
+    if callees.is_empty() {
+        return Ok(crates);
+    }
+    for function in wasm::functions(module)? {
+        let mut reader = function.body.get_operators_reader()?;
+        // Start of the instructions since the last branch or block boundary.
+        let mut since_branch = reader.original_position();
+        let mut branch_size = 0;
+        // A call site whose trailing `unreachable` may still follow.
+        let mut pending: Option<(Check, u64, usize)> = None;
+        while !reader.eof() {
+            let start = reader.original_position();
+            let op = reader.read()?;
+            let end = reader.original_position();
+            if let Some((check, size, position)) = pending.take() {
+                let size = match op {
+                    Operator::Unreachable => size + (end - start) as u64,
+                    _ => size,
+                };
+                let name = crate_for(&context, &mut demangler, &names, &function, position)?;
+                let entry = crates.entry(name).or_default();
+                let (count, total) = entry.checks.entry(check).or_default();
+                *count += 1;
+                *total += size;
+            }
+            match op {
+                Operator::Call { function_index } => {
+                    if let Some(&check) = callees.get(&function_index) {
+                        let size = (branch_size + end - since_branch) as u64;
+                        pending = Some((check, size, start));
+                    }
+                }
+                Operator::BrIf { .. } | Operator::If { .. } => {
+                    since_branch = end;
+                    branch_size = end - start;
+                    continue;
+                }
+                Operator::Block { .. }
+                | Operator::Loop { .. }
+                | Operator::Else
+                | Operator::End
+                | Operator::Br { .. }
+                | Operator::BrTable { .. }
+                | Operator::Return
+                | Operator::Unreachable => {}
+                _ => continue,
+            }
+            since_branch = end;
+            branch_size = 0;
+        }
+    }
+    Ok(crates)
+}
+
+/// Crate of the code at file offset `position` in `function`.
+fn crate_for(
+    context: &addr2line::Context<Reader>,
+    demangler: &mut Demangler,
+    names: &std::collections::HashMap<u32, &str>,
+    function: &wasm::Function,
+    position: usize,
+) -> anyhow::Result<String> {
+    let address = function.code_offset + (position as u64 - function.file_offset);
+    // The outermost frame is the function the code lives in, not one inlined into it.
+    let mut frames = context.find_frames(address).skip_all_loads()?;
+    let mut file = None;
+    while let Some(frame) = frames.next()? {
+        file = frame.location.and_then(|location| location.file);
+    }
+    if let Some(name) = file.and_then(group::crate_for_path) {
+        return Ok(name);
+    }
+    let name = names
+        .get(&function.index)
+        .and_then(|name| group::crate_for_symbol(&demangler.demangle(name)));
+    Ok(name.unwrap_or_else(|| "<unknown crate>".to_string()))
+}
+
+pub fn write_report(module: &[u8], lang: Lang, mut output: impl Write) -> anyhow::Result<()> {
+    writeln!(output, "== checks ==")?;
+    let crates = checks(module)?;
+    if crates.is_empty() {
+        writeln!(output, "{}", Message::NoChecks.text(lang))?;
+        return Ok(());
+    }
+    let mut totals: BTreeMap<Check, (usize, u64)> = BTreeMap::new();
+    for crate_checks in crates.values() {
+        for (&check, &(count, size)) in &crate_checks.checks {
+            let total = totals.entry(check).or_default();
+            total.0 += count;
+            total.1 += size;
+        }
+    }
+    let mut crates: Vec<_> = crates.into_iter().collect();
+    crates.sort_by(|(a_name, a), (b_name, b)| b.size().cmp(&a.size()).then(a_name.cmp(b_name)));
+    writeln!(
+        output,
+        "  {:>10} {:>15} {:>15} {:>15}  crate",
+        "bytes", "overflow", "debug-assert", "bounds"
+    )?;
+    let column = |checks: &BTreeMap<Check, (usize, u64)>, check| {
+        let (count, size) = checks.get(&check).copied().unwrap_or_default();
+        format!("{size} ({count}x)")
+    };
+    for (name, crate_checks) in &crates {
+        writeln!(
+            output,
+            "  {:>10} {:>15} {:>15} {:>15}  {name}",
+            crate_checks.size(),
+            column(&crate_checks.checks, Check::Overflow),
+            column(&crate_checks.checks, Check::DebugAssertion),
+            column(&crate_checks.checks, Check::Bounds),
+        )?;
+    }
+    let size = |check| totals.get(&check).map_or(0, |&(_, size)| size);
+    let advice = [
+        (
+            Check::Overflow,
+            Message::OverflowChecks {
+                bytes: size(Check::Overflow),
+            },
+        ),
+        (
+            Check::DebugAssertion,
+            Message::DebugAssertions {
+                bytes: size(Check::DebugAssertion),
+            },
+        ),
+        (
+            Check::Bounds,
+            Message::BoundsChecks {
+                bytes: size(Check::Bounds),
+            },
+        ),
+    ];
+    for (check, message) in advice {
+        if totals.contains_key(&check) {
+            writeln!(output, "{}", message.text(lang))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,96 @@
+//! Estimates the compressed size of every contributor, i.e. how many bytes of the module are
+//! actually transferred when it is served with gzip.
+
+use std::{collections::HashMap, io::Write, ops::Range};
+
+use flate2::{
+    write::{DeflateEncoder, GzEncoder},
+    Compression,
+};
+
+use crate::Contributors;
+
+/// Size of `data` compressed with gzip at the best compression level, as a server would
+/// precompress a static file.
+pub fn gzip_size(data: &[u8]) -> anyhow::Result<u64> {
+    let mut encoder = GzEncoder::new(vec![], Compression::best());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?.len() as u64)
+}
+
+/// Size of `data` compressed like [`gzip_size`], but without gzip's header and trailer, which would
+/// dwarf small sections.
+fn deflate_size(data: &[u8]) -> anyhow::Result<u64> {
+    let mut encoder = DeflateEncoder::new(vec![], Compression::best());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?.len() as u64)
+}
+
+/// How far to trust the estimate of every contributor: the lower and the higher of two estimates,
+/// one by the compression ratio of its section (what [`estimate`] returns) and one by the ratio
+/// of the whole module. Where they differ a lot, so does what the contributor compresses to from
+/// the average of its section, and small differences in its estimate mean little.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Uncertainty {
+    pub low: Contributors,
+    pub high: Contributors,
+}
+
+/// Scales the bytes of `contributors` down to their estimated share of the gzip-compressed
+/// module, and returns the range of the estimates for every contributor as well.
+///
+/// Every section (by name, given its file ranges in `sections`) is compressed on its own, which
+/// gives its compression ratio: code, data and names compress very differently. The ratios are
+/// then scaled so the sections add up to the size of all of them compressed at once, as
+/// compressing them together finds more repetition. Within a section, every contributor gets the
+/// section's ratio. Contributors outside of any section get the overall ratio. Sections left out
+/// of `sections`, like stripped debug info, don't count towards the compressed size.
+pub fn estimate(
+    module: &[u8],
+    sections: &[(String, Range<u64>)],
+    contributors: &Contributors,
+) -> anyhow::Result<(Contributors, Uncertainty)> {
+    let mut data: HashMap<&str, Vec<u8>> = HashMap::new();
+    let mut all = vec![];
+    for (name, range) in sections {
+        let bytes = &module[range.start as usize..range.end as usize];
+        data.entry(name).or_default().extend_from_slice(bytes);
+        all.extend_from_slice(bytes);
+    }
+    let mut ratios = HashMap::new();
+    let mut separately = 0;
+    for (name, bytes) in &data {
+        let compressed = deflate_size(bytes)?;
+        separately += compressed;
+        ratios.insert(*name, compressed as f64 / bytes.len().max(1) as f64);
+    }
+    let together = gzip_size(&all)?;
+    let scale = together as f64 / separately.max(1) as f64;
+    let overall = together as f64 / all.len().max(1) as f64;
+
+    let mut estimated = Contributors::new();
+    let mut uncertainty = Uncertainty::default();
+    for (key, &size) in contributors {
+        let section = key
+            .split(';')
+            .next()
+            .and_then(|frame| frame.strip_prefix("@section: "));
+        let ratio = match section.and_then(|name| ratios.get(name)) {
+            Some(ratio) => ratio * scale,
+            None => overall,
+        };
+        let by_section = (size as f64 * ratio).round() as u64;
+        if by_section == 0 {
+            continue;
+        }
+        let by_module = (size as f64 * overall).round() as u64;
+        estimated.insert(key.clone(), by_section);
+        uncertainty
+            .low
+            .insert(key.clone(), by_section.min(by_module));
+        uncertainty
+            .high
+            .insert(key.clone(), by_section.max(by_module));
+    }
+    Ok((estimated, uncertainty))
+}
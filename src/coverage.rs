@@ -0,0 +1,138 @@
+use std::io::Write;
+
+use addr2line::gimli;
+
+use crate::{
+    dwarf::{self, Reader, Segments},
+    messages::{Lang, Message},
+};
+
+/// Units where less than this fraction of the address ranges could be attributed are flagged.
+const POOR_COVERAGE: f64 = 0.5;
+
+/// How much of a compilation unit's address ranges (its "envelope") could be attributed to
+/// source locations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnitCoverage {
+    pub name: String,
+    /// Total size of the unit's `DW_AT_low_pc`/`DW_AT_high_pc`/`DW_AT_ranges` ranges.
+    pub envelope: u64,
+    /// Bytes within the envelope that the line tables attribute to a source location.
+    pub attributed: u64,
+}
+
+impl UnitCoverage {
+    pub fn coverage(&self) -> f64 {
+        if self.envelope == 0 {
+            return 1.0;
+        }
+        self.attributed as f64 / self.envelope as f64
+    }
+
+    pub fn is_poor(&self) -> bool {
+        self.coverage() < POOR_COVERAGE
+    }
+}
+
+/// Maps addresses to the compilation unit whose envelope contains them.
+pub(crate) struct Envelopes {
+    pub units: Vec<UnitCoverage>,
+    /// `(segment, begin, end, unit index)`, sorted by segment and `begin`.
+    ranges: Vec<(u64, u64, u64, usize)>,
+    /// Problems worth telling the user about, e.g. units with too many ranges.
+    pub warnings: Vec<Message>,
+}
+
+impl Envelopes {
+    pub fn load(dwarf: &gimli::Dwarf<Reader>, segments: &Segments) -> anyhow::Result<Envelopes> {
+        let mut units = vec![];
+        let mut ranges = vec![];
+        let mut warnings = vec![];
+        let mut headers = dwarf.units();
+        while let Some(header) = headers.next()? {
+            let unit = dwarf.unit(header)?;
+            // Linkers mark ranges of code that was removed as dead with an all-ones address.
+            let segment = segments.unit_segment(&unit);
+            let tombstone = !0u64 >> (64 - 8 * u32::from(unit.encoding().address_size));
+            let name = dwarf::unit_name(&unit);
+            let mut unit_ranges = dwarf.unit_ranges(&unit)?;
+            let mut own = vec![];
+            while let Some(range) = unit_ranges.next()? {
+                if own.len() == dwarf::MAX_RANGES_PER_UNIT {
+                    warnings.push(Message::TooManyRanges {
+                        unit: name.clone(),
+                        max: dwarf::MAX_RANGES_PER_UNIT,
+                    });
+                    break;
+                }
+                if range.begin < tombstone && range.end > range.begin {
+                    own.push((range.begin, range.end));
+                }
+            }
+            // LTO'd units can list thousands of ranges, many of them adjacent.
+            dwarf::coalesce(&mut own);
+            let envelope = own.iter().map(|(begin, end)| end - begin).sum();
+            ranges.extend(
+                own.into_iter()
+                    .map(|(begin, end)| (segment, begin, end, units.len())),
+            );
+            units.push(UnitCoverage {
+                name,
+                envelope,
+                attributed: 0,
+            });
+        }
+        ranges.sort_unstable();
+        Ok(Envelopes {
+            units,
+            ranges,
+            warnings,
+        })
+    }
+
+    /// Credits `size` bytes at `address` in `segment` to the unit containing that address.
+    pub fn attribute(&mut self, segment: u64, address: u64, size: u64) {
+        let index = self
+            .ranges
+            .partition_point(|&(s, begin, _, _)| (s, begin) <= (segment, address));
+        let Some(&(s, _, end, unit)) = index.checked_sub(1).map(|i| &self.ranges[i]) else {
+            return;
+        };
+        if s == segment && address < end {
+            self.units[unit].attributed += size.min(end - address);
+        }
+    }
+}
+
+pub fn write_report(
+    units: &[UnitCoverage],
+    lang: Lang,
+    mut output: impl Write,
+) -> anyhow::Result<()> {
+    writeln!(output, "== coverage ==")?;
+    let envelope: u64 = units.iter().map(|u| u.envelope).sum();
+    let attributed: u64 = units.iter().map(|u| u.attributed).sum();
+    writeln!(
+        output,
+        "{attributed} of {envelope} bytes in {} compilation units attributed to source locations",
+        units.len()
+    )?;
+    let mut poor: Vec<_> = units.iter().filter(|u| u.is_poor()).collect();
+    if poor.is_empty() {
+        return Ok(());
+    }
+    poor.sort_by_key(|u| std::cmp::Reverse(u.envelope - u.attributed));
+    let percent = POOR_COVERAGE * 100.0;
+    writeln!(output, "{}", Message::PoorCoverage { percent }.text(lang))?;
+    for unit in poor {
+        writeln!(
+            output,
+            "  {:>6.1}%  {:>10} of {:>10} bytes  {}",
+            unit.coverage() * 100.0,
+            unit.attributed,
+            unit.envelope,
+            unit.name
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use std::{fmt, io::Write, str::FromStr, sync::Arc};
+
+use clap::ValueEnum;
+
+use crate::messages::{Lang, Message};
+
+/// Breaks the contents of a custom section down for the report.
+///
+/// Implement this to analyze proprietary custom sections in full; the built-in [`Content`] parsers
+/// only look at the section as a whole.
+pub trait CustomSectionParser: fmt::Debug + Send + Sync {
+    fn parse(&self, data: &[u8]) -> anyhow::Result<Parsed>;
+}
+
+/// What a [`CustomSectionParser`] found in a custom section.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Parsed {
+    /// Number of items in the section, in whatever unit makes sense for its content.
+    pub count: usize,
+    /// A short excerpt of the content, for the report.
+    pub sample: String,
+    /// Named parts of the section and their sizes, shown below the section in the breakdown.
+    /// Bytes not covered by any part are shown as unmapped.
+    pub parts: Vec<(String, u64)>,
+}
+
+/// Built-in ways to interpret a custom section.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Content {
+    /// Binary data: one item per section, sampled as hex.
+    Opaque,
+    /// UTF-8 text: one item per line.
+    Utf8,
+    /// A JSON document: one item per top-level object member or array element.
+    Json,
+}
+
+/// Maximum number of characters of a sample.
+const SAMPLE_LENGTH: usize = 80;
+
+impl CustomSectionParser for Content {
+    fn parse(&self, data: &[u8]) -> anyhow::Result<Parsed> {
+        let (count, sample) = match self {
+            Content::Opaque => {
+                let hex: Vec<_> = data
+                    .iter()
+                    .take(SAMPLE_LENGTH / 2)
+                    .map(|byte| format!("{byte:02x}"))
+                    .collect();
+                (1, hex.concat())
+            }
+            Content::Utf8 => {
+                let text = std::str::from_utf8(data)?;
+                (text.lines().count(), sample(text))
+            }
+            Content::Json => {
+                let value: serde_json::Value = serde_json::from_slice(data)?;
+                let count = match &value {
+                    serde_json::Value::Object(members) => members.len(),
+                    serde_json::Value::Array(elements) => elements.len(),
+                    _ => 1,
+                };
+                (count, sample(&value.to_string()))
+            }
+        };
+        let name = self.to_possible_value().expect("no variant is skipped");
+        Ok(Parsed {
+            count,
+            sample,
+            parts: vec![(format!("<{}>", name.get_name()), data.len() as u64)],
+        })
+    }
+}
+
+fn sample(text: &str) -> String {
+    let escaped: String = text
+        .chars()
+        .map(|c| match c.is_control() {
+            true => c.escape_default().to_string(),
+            false => c.to_string(),
+        })
+        .collect();
+    match escaped.char_indices().nth(SAMPLE_LENGTH) {
+        Some((end, _)) => format!("{}...", &escaped[..end]),
+        None => escaped,
+    }
+}
+
+/// How to analyze the custom sections with a given name, e.g. from `--custom-section name=json`.
+#[derive(Clone, Debug)]
+pub struct CustomSection {
+    pub name: String,
+    pub parser: Arc<dyn CustomSectionParser>,
+}
+
+impl FromStr for CustomSection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, content) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("expected <name>=<content>, got {s:?}"))?;
+        let content = Content::from_str(content, true)?;
+        Ok(CustomSection {
+            name: name.to_string(),
+            parser: Arc::new(content),
+        })
+    }
+}
+
+/// What was found in all custom sections with one name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub name: String,
+    /// Number of custom sections with this name.
+    pub sections: usize,
+    pub size: u64,
+    pub count: usize,
+    /// The sample of the first section.
+    pub sample: String,
+}
+
+impl Summary {
+    pub fn add(&mut self, size: u64, parsed: &Parsed) {
+        if self.sections == 0 {
+            self.sample = parsed.sample.clone();
+        }
+        self.sections += 1;
+        self.size += size;
+        self.count += parsed.count;
+    }
+}
+
+pub fn write_report(
+    summaries: &[Summary],
+    lang: Lang,
+    mut output: impl Write,
+) -> anyhow::Result<()> {
+    writeln!(output, "== custom-sections ==")?;
+    if summaries.is_empty() {
+        writeln!(output, "{}", Message::NoCustomSections.text(lang))?;
+        return Ok(());
+    }
+    for summary in summaries {
+        writeln!(
+            output,
+            "  {:>10} bytes  {} ({} sections, {} items)",
+            summary.size, summary.name, summary.sections, summary.count
+        )?;
+        writeln!(output, "                {}", summary.sample)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,473 @@
+//! Attributes the static data of the data segments, like globals, lookup tables and string
+//! literals. DWARF places variables with a fixed address (`DW_TAG_variable` with a `DW_OP_addr`
+//! location) in linear memory, and active segments say where their bytes are loaded. What no
+//! variable covers is searched for strings, which make up much of the rest, like the panic
+//! messages of Rust or the string literals of C.
+
+use std::{io::Write, ops::Range};
+
+use addr2line::gimli;
+use serde::Serialize;
+
+use crate::{
+    dwarf::{self, Reader},
+    wasm::{self, DataSegment},
+};
+
+/// Frame below the data section that the attributed data is grouped under.
+pub const FRAME: &str = "@data";
+/// Frame below [`FRAME`] for the strings found in data no variable covers.
+pub const STRINGS_FRAME: &str = "@strings";
+/// Prefix of the frames of variables, like `@function: ` for functions.
+pub const VARIABLE_PREFIX: &str = "@variable: ";
+
+/// Shorter runs of text are too likely to be something else, like a table of small numbers.
+const MIN_STRING_LENGTH: usize = 8;
+/// How many typedefs, qualifiers and nested arrays are followed to find a type's size.
+const MAX_TYPE_DEPTH: u32 = 16;
+/// How many characters of a string its preview shows.
+const PREVIEW_CHARS: usize = 60;
+
+/// A variable in the data segments.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Variable {
+    /// The linkage name if there is one, else the plain name.
+    pub name: String,
+    /// The file that declares the variable.
+    pub file: Option<String>,
+    pub file_range: Range<u64>,
+}
+
+/// Returns the variables of `dwarf` that lie in an active data segment of `module`, sorted by
+/// position and without overlaps. Variables of unknown size are left out.
+pub fn variables(module: &[u8], dwarf: &gimli::Dwarf<Reader>) -> anyhow::Result<Vec<Variable>> {
+    let segments = wasm::data_segments(module)?;
+    let mut variables = vec![];
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        let unit = dwarf.unit(header)?;
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs()? {
+            if entry.tag() != gimli::DW_TAG_variable {
+                continue;
+            }
+            let Some(address) = address(dwarf, &unit, entry)? else {
+                continue;
+            };
+            let Some(size) = type_size(&unit, entry, 0)?.filter(|&size| size > 0) else {
+                continue;
+            };
+            let Some(file_range) = file_range(&segments, address, size) else {
+                continue;
+            };
+            // Definitions of declared variables (like C++ static members) may only have their
+            // name and file in the declaration.
+            let declaration = match reference(&unit, entry, gimli::DW_AT_specification)? {
+                Some(offset) => Some(unit.entry(offset)?),
+                None => None,
+            };
+            let attr = |name| -> anyhow::Result<_> {
+                match entry.attr_value(name)? {
+                    Some(value) => Ok(Some(value)),
+                    None => match &declaration {
+                        Some(declaration) => Ok(declaration.attr_value(name)?),
+                        None => Ok(None),
+                    },
+                }
+            };
+            let name = [gimli::DW_AT_linkage_name, gimli::DW_AT_name]
+                .into_iter()
+                .find_map(|name| {
+                    let value = attr(name).ok()??;
+                    dwarf::attr_string(dwarf, &unit, value)
+                });
+            let Some(name) = name else {
+                continue;
+            };
+            let file = match attr(gimli::DW_AT_decl_file)? {
+                Some(gimli::AttributeValue::FileIndex(index)) => {
+                    dwarf::file_path(dwarf, &unit, index)
+                }
+                _ => None,
+            };
+            variables.push(Variable {
+                name,
+                file,
+                file_range,
+            });
+        }
+    }
+    variables.sort_by(|a, b| {
+        (a.file_range.start, b.file_range.end).cmp(&(b.file_range.start, a.file_range.end))
+    });
+    // The larger of overlapping variables (like a struct and a static of one of its fields) keeps
+    // the bytes.
+    let mut end = 0;
+    variables.retain_mut(|variable| {
+        variable.file_range.start = variable.file_range.start.max(end);
+        end = end.max(variable.file_range.end);
+        variable.file_range.start < variable.file_range.end
+    });
+    Ok(variables)
+}
+
+/// Returns the file ranges of the strings in the active and passive data segments of `module`:
+/// runs of at least [`MIN_STRING_LENGTH`] bytes of UTF-8 text with a letter in them, with a
+/// terminating NUL if there is one.
+pub fn strings(module: &[u8]) -> anyhow::Result<Vec<Range<u64>>> {
+    let mut strings = vec![];
+    for segment in wasm::data_segments(module)? {
+        let data = segment.data;
+        let mut start = 0;
+        while start < data.len() {
+            let length = data[start..]
+                .iter()
+                .take_while(|&&byte| is_text(byte))
+                .count();
+            if length == 0 {
+                start += 1;
+                continue;
+            }
+            let run = &data[start..start + length];
+            let end = start + length;
+            if length >= MIN_STRING_LENGTH
+                && std::str::from_utf8(run).is_ok()
+                && run.iter().any(u8::is_ascii_alphabetic)
+            {
+                let terminated = data.get(end) == Some(&0);
+                strings.push(
+                    segment.file_offset + start as u64
+                        ..segment.file_offset + (end + usize::from(terminated)) as u64,
+                );
+            }
+            start = end;
+        }
+    }
+    Ok(strings)
+}
+
+/// A string in the data segments, as listed by `wasmphobia strings`.
+#[derive(Clone, Debug, Serialize)]
+pub struct DataString {
+    pub file_offset: u64,
+    /// Address in linear memory, for strings in active segments.
+    pub address: Option<u64>,
+    /// Bytes of the string, with its terminating NUL if there is one.
+    pub size: u64,
+    /// The start of the string, quoted and escaped.
+    pub preview: String,
+}
+
+/// The strings of the data segments, largest first. Literals without a terminating NUL, like
+/// Rust's, are often laid out back to back and found as one string.
+#[derive(Clone, Debug, Serialize)]
+pub struct LargestStrings {
+    /// Number of strings and their bytes in total, including the ones not listed.
+    pub count: usize,
+    pub bytes: u64,
+    pub strings: Vec<DataString>,
+}
+
+/// Returns the `limit` largest strings of the data segments of `module`.
+pub fn largest_strings(module: &[u8], limit: usize) -> anyhow::Result<LargestStrings> {
+    let segments = wasm::data_segments(module)?;
+    let mut ranges = strings(module)?;
+    let count = ranges.len();
+    let bytes = ranges.iter().map(|range| range.end - range.start).sum();
+    ranges.sort_by(|a, b| {
+        (b.end - b.start)
+            .cmp(&(a.end - a.start))
+            .then(a.start.cmp(&b.start))
+    });
+    ranges.truncate(limit);
+    let strings = ranges
+        .into_iter()
+        .map(|range| {
+            let address = segments.iter().find_map(|segment| {
+                let offset = range.start.checked_sub(segment.file_offset)?;
+                (offset < segment.data.len() as u64).then_some(segment.memory_offset? + offset)
+            });
+            let text = &module[range.start as usize..range.end as usize];
+            let text = String::from_utf8_lossy(text.strip_suffix(&[0]).unwrap_or(text));
+            let mut preview: String = text.chars().take(PREVIEW_CHARS).collect();
+            if preview.len() < text.len() {
+                preview.push('…');
+            }
+            DataString {
+                file_offset: range.start,
+                address,
+                size: range.end - range.start,
+                preview: format!("{preview:?}"),
+            }
+        })
+        .collect();
+    Ok(LargestStrings {
+        count,
+        bytes,
+        strings,
+    })
+}
+
+pub fn write_text(largest: &LargestStrings, mut output: impl Write) -> anyhow::Result<()> {
+    writeln!(
+        output,
+        "{} strings in the data segments take {} bytes, the largest:",
+        largest.count, largest.bytes
+    )?;
+    writeln!(
+        output,
+        "  {:>8}  {:>10}  {:>10}  preview",
+        "bytes", "offset", "address"
+    )?;
+    for string in &largest.strings {
+        let address = match string.address {
+            Some(address) => format!("{address:#x}"),
+            None => "-".to_string(),
+        };
+        writeln!(
+            output,
+            "  {:>8}  {:>#10x}  {address:>10}  {}",
+            string.size, string.file_offset, string.preview
+        )?;
+    }
+    Ok(())
+}
+
+/// Whether `byte` can be part of UTF-8 text: printable ASCII, whitespace or part of a
+/// multi-byte character.
+fn is_text(byte: u8) -> bool {
+    matches!(byte, b'\t' | b'\n' | b'\r' | 0x20..=0x7e | 0x80..)
+}
+
+/// The memory address of a variable whose location is a plain `DW_OP_addr` or `DW_OP_addrx`.
+fn address(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    entry: &gimli::DebuggingInformationEntry<Reader>,
+) -> anyhow::Result<Option<u64>> {
+    let Some(gimli::AttributeValue::Exprloc(expression)) =
+        entry.attr_value(gimli::DW_AT_location)?
+    else {
+        return Ok(None);
+    };
+    let mut operations = expression.operations(unit.encoding());
+    let address = match operations.next()? {
+        Some(gimli::Operation::Address { address }) => address,
+        Some(gimli::Operation::AddressIndex { index }) => dwarf.address(unit, index)?,
+        _ => return Ok(None),
+    };
+    // Anything after the address, like an offset to `__memory_base`, makes it relative.
+    if operations.next()?.is_some() {
+        return Ok(None);
+    }
+    Ok(Some(address))
+}
+
+/// The position in the file of `size` bytes at `address` in linear memory, if they are loaded
+/// from an active segment.
+fn file_range(segments: &[DataSegment], address: u64, size: u64) -> Option<Range<u64>> {
+    segments.iter().find_map(|segment| {
+        let memory = segment.memory_offset?;
+        let offset = address.checked_sub(memory)?;
+        let length = segment.data.len() as u64;
+        (offset < length).then(|| {
+            segment.file_offset + offset..segment.file_offset + (offset + size).min(length)
+        })
+    })
+}
+
+/// The size of the type of `entry`, following typedefs and qualifiers and multiplying arrays
+/// out.
+fn type_size(
+    unit: &gimli::Unit<Reader>,
+    entry: &gimli::DebuggingInformationEntry<Reader>,
+    depth: u32,
+) -> anyhow::Result<Option<u64>> {
+    if depth > MAX_TYPE_DEPTH {
+        return Ok(None);
+    }
+    let Some(offset) = reference(unit, entry, gimli::DW_AT_type)? else {
+        return Ok(None);
+    };
+    let ty = unit.entry(offset)?;
+    if let Some(size) = ty
+        .attr_value(gimli::DW_AT_byte_size)?
+        .and_then(|value| value.udata_value())
+    {
+        return Ok(Some(size));
+    }
+    match ty.tag() {
+        gimli::DW_TAG_typedef
+        | gimli::DW_TAG_const_type
+        | gimli::DW_TAG_volatile_type
+        | gimli::DW_TAG_restrict_type
+        | gimli::DW_TAG_atomic_type => type_size(unit, &ty, depth + 1),
+        gimli::DW_TAG_pointer_type
+        | gimli::DW_TAG_reference_type
+        | gimli::DW_TAG_rvalue_reference_type => Ok(Some(u64::from(unit.encoding().address_size))),
+        gimli::DW_TAG_array_type => {
+            let Some(element) = type_size(unit, &ty, depth + 1)? else {
+                return Ok(None);
+            };
+            let mut count = 1u64;
+            let mut tree = unit.entries_tree(Some(ty.offset()))?;
+            let mut children = tree.root()?.children();
+            while let Some(child) = children.next()? {
+                let child = child.entry();
+                if child.tag() != gimli::DW_TAG_subrange_type {
+                    continue;
+                }
+                let udata = |name| -> anyhow::Result<Option<u64>> {
+                    Ok(child
+                        .attr_value(name)?
+                        .and_then(|value| value.udata_value()))
+                };
+                let length = match (udata(gimli::DW_AT_count)?, udata(gimli::DW_AT_upper_bound)?) {
+                    (Some(count), _) => Some(count),
+                    (None, Some(upper)) => {
+                        let lower = udata(gimli::DW_AT_lower_bound)?.unwrap_or_default();
+                        upper.checked_add(1).map(|end| end.saturating_sub(lower))
+                    }
+                    // Arrays of unknown length, like a C `extern int a[];`.
+                    (None, None) => return Ok(None),
+                };
+                // Sizes that don't fit are as good as unknown.
+                let Some(product) = length.and_then(|length| count.checked_mul(length)) else {
+                    return Ok(None);
+                };
+                count = product;
+            }
+            Ok(element.checked_mul(count))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// The entry in the same unit that attribute `name` of `entry` refers to.
+fn reference(
+    unit: &gimli::Unit<Reader>,
+    entry: &gimli::DebuggingInformationEntry<Reader>,
+    name: gimli::DwAt,
+) -> anyhow::Result<Option<gimli::UnitOffset>> {
+    Ok(match entry.attr_value(name)? {
+        Some(gimli::AttributeValue::UnitRef(offset)) => Some(offset),
+        Some(gimli::AttributeValue::DebugInfoRef(offset)) => offset.to_unit_offset(&unit.header),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use ::gimli::write::{AttributeValue, DwarfUnit, EndianVec, Sections, UnitEntryId};
+
+    use super::*;
+
+    /// Bounds of one dimension of an array, as `DW_AT_count` or `DW_AT_upper_bound`.
+    enum Bound {
+        Count(u64),
+        Upper(u64),
+    }
+
+    /// The size [`type_size`] finds for a variable that is an array of 4-byte integers with the
+    /// dimensions `bounds`.
+    fn array_size(bounds: &[Bound]) -> Option<u64> {
+        let encoding = ::gimli::Encoding {
+            format: ::gimli::Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        let mut unit = DwarfUnit::new(encoding);
+        let root = unit.unit.root();
+        let mut add = |parent: UnitEntryId, tag, attrs: Vec<(_, AttributeValue)>| {
+            let id = unit.unit.add(parent, tag);
+            for (name, value) in attrs {
+                unit.unit.get_mut(id).set(name, value);
+            }
+            id
+        };
+        let int = add(
+            root,
+            ::gimli::DW_TAG_base_type,
+            vec![(::gimli::DW_AT_byte_size, AttributeValue::Udata(4))],
+        );
+        let array = add(
+            root,
+            ::gimli::DW_TAG_array_type,
+            vec![(::gimli::DW_AT_type, AttributeValue::UnitRef(int))],
+        );
+        for bound in bounds {
+            let attr = match *bound {
+                Bound::Count(count) => (::gimli::DW_AT_count, AttributeValue::Udata(count)),
+                Bound::Upper(upper) => (::gimli::DW_AT_upper_bound, AttributeValue::Udata(upper)),
+            };
+            add(array, ::gimli::DW_TAG_subrange_type, vec![attr]);
+        }
+        add(
+            root,
+            ::gimli::DW_TAG_variable,
+            vec![(::gimli::DW_AT_type, AttributeValue::UnitRef(array))],
+        );
+        let mut sections = Sections::new(EndianVec::new(::gimli::LittleEndian));
+        unit.write(&mut sections).expect("writing DWARF");
+
+        let dwarf = gimli::Dwarf::load(|id| -> Result<Reader, gimli::Error> {
+            let data = sections
+                .get(id)
+                .map(|section| section.slice().to_vec())
+                .unwrap_or_default();
+            Ok(Reader::new(Rc::from(data), gimli::RunTimeEndian::Little))
+        })
+        .expect("loading DWARF");
+        let header = dwarf.units().next().unwrap().expect("one unit");
+        let unit = dwarf.unit(header).unwrap();
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs().unwrap() {
+            if entry.tag() == gimli::DW_TAG_variable {
+                return type_size(&unit, entry, 0).unwrap();
+            }
+        }
+        panic!("no variable");
+    }
+
+    #[test]
+    fn multiplies_arrays_out() {
+        assert_eq!(array_size(&[Bound::Count(3)]), Some(12));
+        assert_eq!(array_size(&[Bound::Upper(2), Bound::Count(4)]), Some(48));
+    }
+
+    #[test]
+    fn overflowing_sizes_are_unknown() {
+        assert_eq!(array_size(&[Bound::Upper(u64::MAX)]), None);
+        assert_eq!(
+            array_size(&[Bound::Count(1 << 40), Bound::Count(1 << 40)]),
+            None
+        );
+        assert_eq!(array_size(&[Bound::Count(1 << 62)]), None);
+    }
+
+    /// A module with a single passive data segment holding `data`.
+    fn module_with_data(data: &[u8]) -> Vec<u8> {
+        let mut segment = vec![1, data.len() as u8];
+        segment.extend_from_slice(data);
+        let mut module = b"\0asm\x01\0\0\0".to_vec();
+        module.extend([11, segment.len() as u8 + 1, 1]);
+        module.extend(segment);
+        module
+    }
+
+    #[test]
+    fn finds_strings() {
+        let module = module_with_data(b"\x01\x02called `unwrap()`\0\x07short\0");
+        let start = module.windows(6).position(|w| w == b"called").unwrap() as u64;
+        // With the terminating NUL, but without the string that is too short.
+        assert_eq!(strings(&module).unwrap(), vec![start..start + 18]);
+    }
+
+    #[test]
+    fn strings_need_letters() {
+        let module = module_with_data(b"0123456789, 42, 43");
+        assert!(strings(&module).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{BufRead, BufReader, Write},
+    num::NonZeroUsize,
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    rc::Rc,
+};
+
+use anyhow::Context;
+use lru::LruCache;
+
+use crate::tinygo;
+
+/// Demangles Rust, C++ and Go symbol names, memoizing the results.
+///
+/// The same symbols show up over and over again (every inlined call site produces a frame), so
+/// demangled names are interned and shared. The cache is either unbounded or an LRU cache with a
+/// fixed number of entries.
+///
+/// Names none of the built-in demanglers understand can be passed on to an external demangler, see
+/// [`Demangler::with_command`].
+pub struct Demangler {
+    cache: Cache,
+    external: Option<External>,
+}
+
+enum Cache {
+    Unbounded(HashMap<String, Rc<str>>),
+    Lru(LruCache<String, Rc<str>>),
+}
+
+impl Demangler {
+    /// Creates a demangler whose cache holds at most `capacity` names, or any number of names if
+    /// `capacity` is 0.
+    pub fn new(capacity: usize) -> Demangler {
+        let cache = match NonZeroUsize::new(capacity) {
+            Some(capacity) => Cache::Lru(LruCache::new(capacity)),
+            None => Cache::Unbounded(HashMap::new()),
+        };
+        Demangler {
+            cache,
+            external: None,
+        }
+    }
+
+    /// Passes the names the built-in demanglers leave as they are to `command`, a program (plus
+    /// arguments, separated by whitespace) that reads one name per line from stdin and writes each
+    /// one demangled, or as it is, on its own line to stdout, like `c++filt`. The program is
+    /// started right away and kept running. If it fails later on, the names stay as they are.
+    pub fn with_command(mut self, command: &str) -> anyhow::Result<Demangler> {
+        self.external = Some(External::spawn(command)?);
+        Ok(self)
+    }
+
+    pub fn demangle(&mut self, name: &str) -> Rc<str> {
+        if let Some(demangled) = self.cached(name) {
+            return demangled;
+        }
+        let mut demangled = demangle_uncached(name);
+        if demangled == name {
+            if let Some(external) = self.external(&[name]) {
+                demangled = external.into_iter().next().unwrap_or(demangled);
+            }
+        }
+        self.insert(name, demangled.into())
+    }
+
+    /// Demangles many names at once. Duplicates within the batch are only demangled once, and the
+    /// names that need the external demangler are sent to it together.
+    pub fn demangle_batch<'a>(&mut self, names: impl IntoIterator<Item = &'a str>) -> Vec<Rc<str>> {
+        let names: Vec<_> = names.into_iter().collect();
+        if self.external.is_some() {
+            let mut seen = HashSet::new();
+            let unknown: Vec<_> = names
+                .iter()
+                .copied()
+                .filter(|&name| seen.insert(name) && self.cached(name).is_none())
+                .filter(|&name| demangle_uncached(name) == name)
+                .collect();
+            if let Some(demangled) = self.external(&unknown) {
+                for (name, demangled) in unknown.into_iter().zip(demangled) {
+                    self.insert(name, demangled.into());
+                }
+            }
+        }
+        names.into_iter().map(|name| self.demangle(name)).collect()
+    }
+
+    fn cached(&mut self, name: &str) -> Option<Rc<str>> {
+        match &mut self.cache {
+            Cache::Unbounded(map) => map.get(name).cloned(),
+            Cache::Lru(lru) => lru.get(name).cloned(),
+        }
+    }
+
+    fn insert(&mut self, name: &str, demangled: Rc<str>) -> Rc<str> {
+        match &mut self.cache {
+            Cache::Unbounded(map) => {
+                map.insert(name.to_string(), demangled.clone());
+            }
+            Cache::Lru(lru) => {
+                lru.put(name.to_string(), demangled.clone());
+            }
+        }
+        demangled
+    }
+
+    /// Demangles `names` with the external demangler, if there is one. Names it gives back empty
+    /// stay as they are. A demangler that fails is not asked again.
+    fn external(&mut self, names: &[&str]) -> Option<Vec<String>> {
+        if names.is_empty() {
+            return None;
+        }
+        let demangled = self.external.as_mut()?.demangle(names);
+        if demangled.is_none() {
+            self.external = None;
+        }
+        let demangled = demangled?
+            .into_iter()
+            .zip(names)
+            .map(|(demangled, name)| match demangled.is_empty() {
+                true => name.to_string(),
+                false => demangled,
+            })
+            .collect();
+        Some(demangled)
+    }
+}
+
+impl Default for Demangler {
+    fn default() -> Self {
+        Demangler::new(0)
+    }
+}
+
+/// A running external demangler.
+struct External {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl External {
+    fn spawn(command: &str) -> anyhow::Result<External> {
+        let mut words = command.split_whitespace();
+        let program = words.next().context("The demangler command is empty")?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Starting the demangler {program:?}"))?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            anyhow::bail!("The demangler's stdin or stdout is not available");
+        };
+        Ok(External {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+        })
+    }
+
+    /// Sends all `names` and reads back a line for each, or `None` if the demangler failed. The
+    /// names are written from another thread, so that neither side blocks on a full pipe.
+    fn demangle(&mut self, names: &[&str]) -> Option<Vec<String>> {
+        let (stdin, stdout) = (&mut self.stdin, &mut self.stdout);
+        std::thread::scope(|scope| {
+            let writer = scope.spawn(move || {
+                for name in names {
+                    writeln!(stdin, "{}", name.replace('\n', " "))?;
+                }
+                stdin.flush()
+            });
+            let mut demangled = Vec::with_capacity(names.len());
+            for _ in names {
+                let mut line = String::new();
+                match stdout.read_line(&mut line) {
+                    Ok(0) | Err(_) => return None,
+                    Ok(_) => demangled.push(line.trim_end_matches(['\r', '\n']).to_string()),
+                }
+            }
+            writer.join().ok()?.ok()?;
+            Some(demangled)
+        })
+    }
+}
+
+impl Drop for External {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn demangle_uncached(name: &str) -> String {
+    if let Some(demangled) = tinygo::demangle(name) {
+        return demangled;
+    }
+    let mut name = name.to_string();
+    if let Ok(demangled) = rustc_demangle::try_demangle(&name) {
+        name = demangled.to_string();
+    }
+    if let Ok(demangled) = cpp_demangle::Symbol::new(name.clone()) {
+        name = demangled.to_string();
+    }
+    name
+}
@@ -0,0 +1,52 @@
+use addr2line::gimli;
+
+use crate::{
+    dwarf::{self, Reader},
+    escape_frame,
+    glob::PathFilter,
+    group::PathNormalizer,
+    Contributors,
+};
+
+/// Counts the debug info entries (DIEs) per source file.
+///
+/// DIEs are attributed to their `DW_AT_decl_file`; entries without one inherit the file of their
+/// parent, and top-level entries fall back to the compilation unit's name. File paths are passed
+/// through `paths`, and entries of files `filter` doesn't keep are not counted.
+pub fn die_counts(
+    dwarf: &gimli::Dwarf<Reader>,
+    paths: &mut PathNormalizer,
+    filter: &PathFilter,
+) -> anyhow::Result<Contributors> {
+    let mut contributors = Contributors::new();
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        let unit = dwarf.unit(header)?;
+        let unit_file = dwarf::unit_name(&unit);
+        // File of each ancestor of the current entry.
+        let mut files: Vec<String> = vec![];
+        let mut entries = unit.entries();
+        while let Some((delta, entry)) = entries.next_dfs()? {
+            let depth = (files.len() as isize + delta - 1).max(0) as usize;
+            files.truncate(depth);
+            let file = match entry.attr_value(gimli::DW_AT_decl_file)? {
+                Some(gimli::AttributeValue::FileIndex(index)) => {
+                    dwarf::file_path(dwarf, &unit, index)
+                }
+                _ => None,
+            }
+            .map(|file| paths.normalize(&file))
+            .or_else(|| files.last().cloned())
+            .unwrap_or_else(|| paths.normalize(&unit_file));
+            if filter.keeps(Some(&file)) {
+                let key = format!(
+                    "@section: .debug_info;{}",
+                    escape_frame(file.trim_start_matches('/')).replace('/', ";")
+                );
+                *contributors.entry(key).or_default() += 1;
+            }
+            files.push(file);
+        }
+    }
+    Ok(contributors)
+}
@@ -0,0 +1,312 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Write,
+};
+
+use serde::Serialize;
+
+use crate::{
+    demangle::Demangler, explain::strip_disambiguators, ratchet::CrateSizes, wasm, Contributors,
+};
+
+/// Size of one crate, file or function in two builds.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Change {
+    pub name: String,
+    pub old: u64,
+    pub new: u64,
+    /// Estimated sizes after gzip compression, see [`crate::Metric::Gzip`].
+    pub old_gzip: u64,
+    pub new_gzip: u64,
+    /// Number of instructions. Only counted for functions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_instructions: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_instructions: Option<u64>,
+}
+
+impl Change {
+    pub fn delta(&self) -> i64 {
+        self.new as i64 - self.old as i64
+    }
+
+    pub fn gzip_delta(&self) -> i64 {
+        self.new_gzip as i64 - self.old_gzip as i64
+    }
+
+    pub fn instructions_delta(&self) -> Option<i64> {
+        Some(self.new_instructions? as i64 - self.old_instructions? as i64)
+    }
+
+    /// Whether the raw and the compressed size went in opposite directions, so whether the
+    /// change pays off depends on the module being served compressed. A compressed size off by a
+    /// single byte is rounding, not a change.
+    pub fn diverges(&self) -> bool {
+        self.gzip_delta().abs() > 1 && self.delta().signum() * self.gzip_delta().signum() < 0
+    }
+}
+
+/// Differences between the breakdowns of two builds of a module. Only crates, files and
+/// functions whose size changed are listed, the largest changes first.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SizeDiff {
+    pub old_total: u64,
+    pub new_total: u64,
+    pub old_gzip_total: u64,
+    pub new_gzip_total: u64,
+    pub crates: Vec<Change>,
+    /// Sections and source files, with their path joined by `/`.
+    pub files: Vec<Change>,
+    /// Functions the code physically lives in, i.e. inlined code counts for its caller. Rust crate
+    /// disambiguators are left out, as they change between builds.
+    pub functions: Vec<Change>,
+}
+
+impl SizeDiff {
+    pub fn delta(&self) -> i64 {
+        self.new_total as i64 - self.old_total as i64
+    }
+
+    pub fn gzip_delta(&self) -> i64 {
+        self.new_gzip_total as i64 - self.old_gzip_total as i64
+    }
+
+    /// The changes whose raw and compressed sizes went in opposite directions, with the kind of
+    /// change (`crate`, `file` or `function`).
+    pub fn divergent(&self) -> impl Iterator<Item = (&'static str, &Change)> {
+        let kinds = [
+            ("crate", &self.crates),
+            ("file", &self.files),
+            ("function", &self.functions),
+        ];
+        kinds.into_iter().flat_map(|(kind, changes)| {
+            changes
+                .iter()
+                .filter(|change| change.diverges())
+                .map(move |change| (kind, change))
+        })
+    }
+}
+
+/// The breakdowns of one build, in every metric that is compared.
+#[derive(Clone, Copy, Debug)]
+pub struct Build<'a> {
+    /// Bytes, produced with file grouping.
+    pub bytes: &'a Contributors,
+    /// Like `bytes`, but with [`crate::Metric::Gzip`].
+    pub gzip: &'a Contributors,
+    /// Number of instructions per function, see [`instruction_counts`].
+    pub instructions: &'a BTreeMap<String, u64>,
+}
+
+/// Sizes of crates, files or functions of one build.
+struct Sizes<'a> {
+    bytes: BTreeMap<String, u64>,
+    gzip: BTreeMap<String, u64>,
+    instructions: Option<&'a BTreeMap<String, u64>>,
+}
+
+/// Compares an old and a new build in every metric.
+pub fn diff(old: &Build, new: &Build) -> SizeDiff {
+    let (old_crates, new_crates) = (
+        CrateSizes::from_contributors(old.bytes),
+        CrateSizes::from_contributors(new.bytes),
+    );
+    let (old_gzip_crates, new_gzip_crates) = (
+        CrateSizes::from_contributors(old.gzip),
+        CrateSizes::from_contributors(new.gzip),
+    );
+    SizeDiff {
+        old_total: old_crates.total,
+        new_total: new_crates.total,
+        old_gzip_total: old_gzip_crates.total,
+        new_gzip_total: new_gzip_crates.total,
+        crates: changes(
+            &Sizes {
+                bytes: old_crates.crates,
+                gzip: old_gzip_crates.crates,
+                instructions: None,
+            },
+            &Sizes {
+                bytes: new_crates.crates,
+                gzip: new_gzip_crates.crates,
+                instructions: None,
+            },
+        ),
+        files: changes(&sizes(old, file, false), &sizes(new, file, false)),
+        functions: changes(&sizes(old, function, true), &sizes(new, function, true)),
+    }
+}
+
+fn sizes<'a>(
+    build: &Build<'a>,
+    name_of: fn(&str) -> Option<String>,
+    instructions: bool,
+) -> Sizes<'a> {
+    Sizes {
+        bytes: by(build.bytes, name_of),
+        gzip: by(build.gzip, name_of),
+        instructions: instructions.then_some(build.instructions),
+    }
+}
+
+/// Number of instructions of every named function in `module`, by its name with the crate
+/// disambiguators left out, like in [`SizeDiff::functions`]. Functions of the same name are
+/// counted together.
+pub fn instruction_counts(
+    module: &[u8],
+    raw_symbols: bool,
+) -> anyhow::Result<BTreeMap<String, u64>> {
+    let names = wasm::function_names(module)?;
+    let mut demangler = Demangler::default();
+    let mut counts = BTreeMap::new();
+    for function in wasm::functions(module)? {
+        let Some(name) = names.get(&function.index) else {
+            continue;
+        };
+        let name = match raw_symbols {
+            true => name.to_string(),
+            false => demangler.demangle(name).to_string(),
+        };
+        let mut reader = function.body.get_operators_reader()?;
+        let mut count = 0;
+        while !reader.eof() {
+            reader.read()?;
+            count += 1;
+        }
+        *counts.entry(strip_disambiguators(&name)).or_default() += count;
+    }
+    Ok(counts)
+}
+
+/// Sums up `contributors` by the name `name_of` gives their keys, skipping keys it has no name for.
+pub(crate) fn by(
+    contributors: &Contributors,
+    name_of: fn(&str) -> Option<String>,
+) -> BTreeMap<String, u64> {
+    let mut sizes = BTreeMap::new();
+    for (key, &size) in contributors {
+        if let Some(name) = name_of(key) {
+            *sizes.entry(name).or_default() += size;
+        }
+    }
+    sizes
+}
+
+pub(crate) fn file(key: &str) -> Option<String> {
+    let frames: Vec<_> = key
+        .split(';')
+        .take_while(|frame| !frame.starts_with("@function: "))
+        .collect();
+    Some(frames.join("/"))
+}
+
+pub(crate) fn function(key: &str) -> Option<String> {
+    let name = key.rsplit(';').next()?.strip_prefix("@function: ")?;
+    Some(strip_disambiguators(name))
+}
+
+fn changes(old: &Sizes, new: &Sizes) -> Vec<Change> {
+    let names: BTreeSet<_> = old.bytes.keys().chain(new.bytes.keys()).collect();
+    let size = |sizes: &BTreeMap<String, u64>, name| sizes.get(name).copied().unwrap_or_default();
+    // Functions without code in a build have no instructions there.
+    let instructions = |sizes: &Sizes, name| {
+        let count = sizes.instructions?.get(name).copied();
+        Some(count.unwrap_or_default())
+    };
+    let mut changes: Vec<_> = names
+        .into_iter()
+        .map(|name| Change {
+            name: name.clone(),
+            old: size(&old.bytes, name),
+            new: size(&new.bytes, name),
+            old_gzip: size(&old.gzip, name),
+            new_gzip: size(&new.gzip, name),
+            old_instructions: instructions(old, name),
+            new_instructions: instructions(new, name),
+        })
+        .filter(|change| {
+            change.delta() != 0
+                || change.gzip_delta() != 0
+                || change.instructions_delta().is_some_and(|delta| delta != 0)
+        })
+        .collect();
+    changes.sort_by(|a, b| {
+        b.delta()
+            .unsigned_abs()
+            .cmp(&a.delta().unsigned_abs())
+            .then_with(|| {
+                b.gzip_delta()
+                    .unsigned_abs()
+                    .cmp(&a.gzip_delta().unsigned_abs())
+            })
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    changes
+}
+
+/// Writes the `limit` largest changes of every kind, marking those whose raw and compressed sizes
+/// went in opposite directions with `!`, and then these again on their own.
+pub fn write_text(diff: &SizeDiff, limit: usize, mut output: impl Write) -> anyhow::Result<()> {
+    writeln!(
+        output,
+        "Total: {} -> {} bytes ({:+}), gzip {} -> {} bytes ({:+})",
+        diff.old_total,
+        diff.new_total,
+        diff.delta(),
+        diff.old_gzip_total,
+        diff.new_gzip_total,
+        diff.gzip_delta()
+    )?;
+    for (title, changes) in [
+        ("crates", &diff.crates),
+        ("files", &diff.files),
+        ("functions", &diff.functions),
+    ] {
+        writeln!(output, "== {title} ==")?;
+        if changes.is_empty() {
+            writeln!(output, "  no changes")?;
+            continue;
+        }
+        writeln!(
+            output,
+            "  {:>10} {:>10} {:>12}  {:>10} -> {:<10}  name",
+            "bytes", "gzip", "instructions", "old", "new"
+        )?;
+        for change in changes.iter().take(limit) {
+            write_change(&mut output, change, &change.name)?;
+        }
+        if changes.len() > limit {
+            writeln!(output, "  ... and {} more", changes.len() - limit)?;
+        }
+    }
+    let divergent: Vec<_> = diff.divergent().collect();
+    if !divergent.is_empty() {
+        writeln!(output, "== raw and compressed size disagree ==")?;
+        for (kind, change) in divergent.iter().take(limit) {
+            write_change(&mut output, change, &format!("{kind} {}", change.name))?;
+        }
+        if divergent.len() > limit {
+            writeln!(output, "  ... and {} more", divergent.len() - limit)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_change(mut output: impl Write, change: &Change, name: &str) -> anyhow::Result<()> {
+    let instructions = match change.instructions_delta() {
+        Some(delta) => format!("{delta:+}"),
+        None => String::new(),
+    };
+    writeln!(
+        output,
+        "{} {:>+10} {:>+10} {:>12}  {:>10} -> {:<10}  {name}",
+        if change.diverges() { "!" } else { " " },
+        change.delta(),
+        change.gzip_delta(),
+        instructions,
+        change.old,
+        change.new,
+    )?;
+    Ok(())
+}
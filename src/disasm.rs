@@ -0,0 +1,209 @@
+//! Hexdumps and a plain text disassembly of function bodies, so reports can show what the bytes of
+//! a function actually are.
+
+use std::{collections::HashMap, fmt::Write as _};
+
+use regex::Regex;
+use serde::Serialize;
+use wasmparser::{BlockType, Operator, ValType};
+
+use crate::{wasm, AddressRange};
+
+/// Bodies larger than this are only dumped up to here, to keep reports small.
+const MAX_DUMPED_BYTES: usize = 16 * 1024;
+
+/// The code of one function, ready to be shown next to its frame.
+#[derive(Clone, Debug, Serialize)]
+pub struct FunctionCode {
+    /// Name of the function as in the breakdown's `@function: ` frames.
+    pub name: String,
+    /// Offset of the body in the file.
+    pub file_offset: u64,
+    pub size: u64,
+    pub hexdump: String,
+    pub disassembly: String,
+    /// Whether the dumps stop before the end of the body.
+    pub truncated: bool,
+}
+
+/// Returns the code of the `count` largest functions of `address_map`, by the bytes attributed to
+/// them.
+pub fn largest_functions(
+    module: &[u8],
+    address_map: &[AddressRange],
+    count: usize,
+) -> anyhow::Result<Vec<FunctionCode>> {
+    if count == 0 {
+        return Ok(vec![]);
+    }
+    let mut sizes: HashMap<&str, (u64, u64)> = HashMap::new();
+    for range in address_map {
+        let Some(symbol) = &range.symbol else {
+            continue;
+        };
+        let (size, _) = sizes.entry(symbol).or_insert((0, range.start));
+        *size += range.end - range.start;
+    }
+    let mut largest: Vec<_> = sizes.into_iter().collect();
+    largest.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then_with(|| a.0.cmp(b.0)));
+
+    let functions = wasm::functions(module)?;
+    let names = wasm::function_names(module)?;
+    let mut code = vec![];
+    for (name, (_, address)) in largest {
+        if code.len() == count {
+            break;
+        }
+        let Some(function) = functions
+            .iter()
+            .find(|function| function.code_range().contains(&address))
+        else {
+            continue;
+        };
+        let start = function.file_offset as usize;
+        let body = &module[start..start + function.size as usize];
+        let dumped = &body[..body.len().min(MAX_DUMPED_BYTES)];
+        code.push(FunctionCode {
+            name: name.to_string(),
+            file_offset: function.file_offset,
+            size: function.size,
+            hexdump: hexdump(dumped, function.file_offset),
+            disassembly: disassemble(function, &names, MAX_DUMPED_BYTES)?,
+            truncated: dumped.len() < body.len(),
+        });
+    }
+    Ok(code)
+}
+
+/// Classic hexdump of `bytes`, 16 per line, with file offsets starting at `offset` and the
+/// printable ASCII characters on the right.
+pub fn hexdump(bytes: &[u8], offset: u64) -> String {
+    let mut dump = String::new();
+    for (i, line) in bytes.chunks(16).enumerate() {
+        let _ = write!(dump, "{:08x}  ", offset + i as u64 * 16);
+        for (j, byte) in line.iter().enumerate() {
+            let _ = write!(dump, "{byte:02x} ");
+            if j == 7 {
+                dump.push(' ');
+            }
+        }
+        let padding = (16 - line.len()) * 3 + usize::from(line.len() <= 8);
+        dump.extend(std::iter::repeat_n(' ', padding));
+        dump.push_str(" |");
+        dump.extend(line.iter().map(|&byte| match byte {
+            0x20..=0x7e => byte as char,
+            _ => '.',
+        }));
+        dump.push_str("|\n");
+    }
+    dump
+}
+
+/// Lists the instructions of `function`, one per line with its file offset, in a notation close
+/// to the text format: `local.get 0`, `i32.load offset=8`, `call $name`. Stops after the
+/// instruction that reaches `limit` bytes into the body.
+pub fn disassemble(
+    function: &wasm::Function,
+    names: &HashMap<u32, &str>,
+    limit: usize,
+) -> anyhow::Result<String> {
+    let immediate = Regex::new(r"(\w+): (-?\w+)")?;
+    let mut text = String::new();
+    let mut reader = function.body.get_operators_reader()?;
+    let mut depth = 1usize;
+    while !reader.eof() {
+        let offset = reader.original_position();
+        if offset - function.file_offset as usize >= limit {
+            break;
+        }
+        let op = reader.read()?;
+        if matches!(
+            op,
+            Operator::End | Operator::Else | Operator::Delegate { .. }
+        ) {
+            depth = depth.saturating_sub(1);
+        }
+        let debug = format!("{op:?}");
+        let (variant, fields) = debug.split_once(' ').unwrap_or((&debug, ""));
+        let mut line = mnemonic(variant);
+        match &op {
+            Operator::Call { function_index } | Operator::ReturnCall { function_index } => {
+                match names.get(function_index) {
+                    Some(name) => write!(line, " ${name}")?,
+                    None => write!(line, " {function_index}")?,
+                }
+            }
+            Operator::Block { blockty } | Operator::Loop { blockty } | Operator::If { blockty } => {
+                match blockty {
+                    BlockType::Empty => {}
+                    BlockType::Type(ty) => write!(line, " (result {})", value_type(*ty))?,
+                    BlockType::FuncType(index) => write!(line, " (type {index})")?,
+                }
+            }
+            Operator::BrTable { targets } => {
+                for target in targets.targets() {
+                    write!(line, " {}", target?)?;
+                }
+                write!(line, " {}", targets.default())?;
+            }
+            Operator::F32Const { value } => write!(line, " {}", f32::from_bits(value.bits()))?,
+            Operator::F64Const { value } => write!(line, " {}", f64::from_bits(value.bits()))?,
+            _ => {
+                for captures in immediate.captures_iter(fields) {
+                    match (&captures[1], &captures[2]) {
+                        // Names of nested structs, like `MemArg`.
+                        (_, value) if value.starts_with(|c: char| c.is_ascii_uppercase()) => {}
+                        // Memory arguments: only what differs from the defaults is written.
+                        ("max_align" | "align", _) | ("memory", "0") | ("offset", "0") => {}
+                        ("offset" | "memory", value) => write!(line, " {}={value}", &captures[1])?,
+                        (_, value) => write!(line, " {value}")?,
+                    }
+                }
+            }
+        }
+        writeln!(text, "{offset:08x}  {}{line}", "  ".repeat(depth))?;
+        if matches!(
+            op,
+            Operator::Block { .. }
+                | Operator::Loop { .. }
+                | Operator::If { .. }
+                | Operator::Else
+                | Operator::Try { .. }
+                | Operator::TryTable { .. }
+        ) {
+            depth += 1;
+        }
+    }
+    Ok(text)
+}
+
+/// Prefixes of operator names that the text format separates with a `.`, like `i32.add` or
+/// `local.get`.
+const NAMESPACES: &[&str] = &[
+    "i32", "i64", "f32", "f64", "v128", "i8x16", "i16x8", "i32x4", "i64x2", "f32x4", "f64x2",
+    "local", "global", "table", "memory", "ref", "data", "elem",
+];
+
+/// Text format name of a `wasmparser::Operator` variant, like `i32.load8_u` for `I32Load8U`.
+fn mnemonic(variant: &str) -> String {
+    let mut words: Vec<String> = vec![];
+    for c in variant.chars() {
+        match words.last_mut() {
+            Some(word) if !c.is_ascii_uppercase() => word.push(c),
+            _ => words.push(c.to_ascii_lowercase().to_string()),
+        }
+    }
+    match words.split_first() {
+        Some((first, rest)) if NAMESPACES.contains(&first.as_str()) && !rest.is_empty() => {
+            format!("{first}.{}", rest.join("_"))
+        }
+        _ => words.join("_"),
+    }
+}
+
+fn value_type(ty: ValType) -> String {
+    match ty {
+        ValType::Ref(ty) => format!("{ty}"),
+        ty => format!("{ty:?}").to_lowercase(),
+    }
+}
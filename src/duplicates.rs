@@ -0,0 +1,151 @@
+use std::{collections::HashMap, io::Write};
+
+use crate::{
+    messages::{Lang, Message},
+    wasm::DataSegment,
+};
+
+/// Size of the windows that get hashed. Duplicates shorter than this are not detected.
+const WINDOW: usize = 64;
+/// Duplicated runs shorter than this are not worth reporting.
+const MIN_REPORTED_SIZE: usize = 256;
+const BASE: u64 = 0x100000001b3;
+
+/// A run of bytes in a data segment that is an exact copy of an earlier run.
+pub struct DuplicateBlob {
+    pub size: u64,
+    /// File offset of the first occurrence.
+    pub original: u64,
+    /// File offset of the copy.
+    pub copy: u64,
+}
+
+/// Finds byte runs in the data segments that duplicate earlier data.
+///
+/// Every `WINDOW`-aligned window of every segment is indexed by its hash. Then all
+/// windows (at every offset) are looked up in that index, so copies are found
+/// regardless of their alignment. Matches are extended in both directions to
+/// cover the entire duplicated run.
+pub fn find_duplicates(segments: &[DataSegment]) -> Vec<DuplicateBlob> {
+    let mut index: HashMap<u64, (usize, usize)> = HashMap::new();
+    for (seg_idx, segment) in segments.iter().enumerate() {
+        for (chunk_idx, window) in segment.data.as_chunks::<WINDOW>().0.iter().enumerate() {
+            if is_uniform(window) {
+                continue;
+            }
+            index
+                .entry(hash(window))
+                .or_insert((seg_idx, chunk_idx * WINDOW));
+        }
+    }
+
+    let top_power = (1..WINDOW).fold(1u64, |acc, _| acc.wrapping_mul(BASE));
+    let mut duplicates = vec![];
+    for (seg_idx, segment) in segments.iter().enumerate() {
+        let data = segment.data;
+        if data.len() < WINDOW {
+            continue;
+        }
+        let mut pos = 0;
+        let mut last_end = 0;
+        let mut h = hash(&data[..WINDOW]);
+        while pos + WINDOW <= data.len() {
+            let mut advance = 1;
+            if let Some(&(orig_seg, orig_pos)) = index.get(&h) {
+                let original = segments[orig_seg].data;
+                let is_earlier = (orig_seg, orig_pos) < (seg_idx, pos);
+                // Copies must not overlap with their original.
+                let limit = if orig_seg == seg_idx {
+                    pos.saturating_sub(orig_pos)
+                } else {
+                    usize::MAX
+                };
+                if is_earlier
+                    && limit >= WINDOW
+                    && original[orig_pos..orig_pos + WINDOW] == data[pos..pos + WINDOW]
+                {
+                    let mut back = 0;
+                    while back < orig_pos
+                        && pos - back > last_end
+                        && original[orig_pos - back - 1] == data[pos - back - 1]
+                    {
+                        back += 1;
+                    }
+                    let mut len = WINDOW;
+                    while back + len < limit
+                        && orig_pos + len < original.len()
+                        && pos + len < data.len()
+                        && original[orig_pos + len] == data[pos + len]
+                    {
+                        len += 1;
+                    }
+                    let len = (back + len).min(limit);
+                    if len >= MIN_REPORTED_SIZE {
+                        duplicates.push(DuplicateBlob {
+                            size: len as u64,
+                            original: segments[orig_seg].file_offset + (orig_pos - back) as u64,
+                            copy: segment.file_offset + (pos - back) as u64,
+                        });
+                    }
+                    last_end = pos - back + len;
+                    advance = last_end.saturating_sub(pos).max(1);
+                }
+            }
+            let next = pos + advance;
+            if next + WINDOW > data.len() {
+                break;
+            }
+            h = if advance == 1 {
+                h.wrapping_sub((data[pos] as u64).wrapping_mul(top_power))
+                    .wrapping_mul(BASE)
+                    .wrapping_add(data[pos + WINDOW] as u64)
+            } else {
+                hash(&data[next..next + WINDOW])
+            };
+            pos = next;
+        }
+    }
+    duplicates.sort_by(|a, b| b.size.cmp(&a.size).then(a.copy.cmp(&b.copy)));
+    duplicates
+}
+
+pub fn write_report(
+    segments: &[DataSegment],
+    lang: Lang,
+    mut output: impl Write,
+) -> anyhow::Result<()> {
+    let duplicates = find_duplicates(segments);
+    let savings: u64 = duplicates.iter().map(|d| d.size).sum();
+    writeln!(output, "== duplicated-data ==")?;
+    if duplicates.is_empty() {
+        let message = Message::NoDuplicates {
+            min_size: MIN_REPORTED_SIZE,
+        };
+        writeln!(output, "{}", message.text(lang))?;
+        return Ok(());
+    }
+    let message = Message::Duplicates {
+        count: duplicates.len(),
+        savings,
+    };
+    writeln!(output, "{}", message.text(lang))?;
+    for duplicate in duplicates {
+        writeln!(
+            output,
+            "  {:>10} bytes at 0x{:08x} duplicate 0x{:08x}",
+            duplicate.size, duplicate.copy, duplicate.original
+        )?;
+    }
+    Ok(())
+}
+
+fn hash(window: &[u8]) -> u64 {
+    window.iter().fold(0u64, |acc, &b| {
+        acc.wrapping_mul(BASE).wrapping_add(b as u64)
+    })
+}
+
+/// Windows consisting of a single repeated byte (mostly zero padding) aren't interesting.
+fn is_uniform(window: &[u8]) -> bool {
+    window.iter().all(|&b| b == window[0])
+}
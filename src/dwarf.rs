@@ -0,0 +1,423 @@
+use std::{borrow::Cow, collections::HashMap, rc::Rc, sync::Arc};
+
+use addr2line::{
+    gimli::{self, EndianRcSlice, RunTimeEndian, SectionId},
+    LookupContinuation, LookupResult, SplitDwarfLoad,
+};
+use object::{Object, ObjectSection};
+
+pub type Reader = EndianRcSlice<RunTimeEndian>;
+
+/// The DWARF sections of a module, loaded once so several `gimli::Dwarf` instances (e.g. one for
+/// `addr2line` and one for walking units directly) can share the data.
+pub struct DwarfSections {
+    sections: HashMap<SectionId, Rc<[u8]>>,
+}
+
+impl DwarfSections {
+    pub fn load<'data>(file: &impl Object<'data>) -> anyhow::Result<DwarfSections> {
+        Self::load_named(file, |id| Some(id.name()))
+    }
+
+    /// Loads the sections of a split DWARF object (`.dwo`), like `.debug_info.dwo`.
+    pub fn load_dwo<'data>(file: &impl Object<'data>) -> anyhow::Result<DwarfSections> {
+        Self::load_named(file, SectionId::dwo_name)
+    }
+
+    fn load_named<'data>(
+        file: &impl Object<'data>,
+        name: impl Fn(SectionId) -> Option<&'static str>,
+    ) -> anyhow::Result<DwarfSections> {
+        let mut sections = HashMap::new();
+        let mut read = |id: SectionId| -> Result<Reader, gimli::Error> {
+            let data = name(id)
+                .and_then(|name| file.section_by_name(name))
+                .and_then(|section| section.uncompressed_data().ok())
+                .unwrap_or(Cow::Borrowed(&[]));
+            let data: Rc<[u8]> = Rc::from(&*data);
+            sections.insert(id, data.clone());
+            Ok(EndianRcSlice::new(data, RunTimeEndian::Little))
+        };
+        gimli::Dwarf::load(&mut read)?;
+        // The unit indexes of a DWARF package (`.dwp`), which `Dwarf` doesn't need.
+        read(SectionId::DebugCuIndex)?;
+        read(SectionId::DebugTuIndex)?;
+        Ok(DwarfSections { sections })
+    }
+
+    fn reader(&self, id: SectionId) -> Reader {
+        let data = match self.sections.get(&id) {
+            Some(data) => data.clone(),
+            None => Rc::from(&[][..]),
+        };
+        EndianRcSlice::new(data, RunTimeEndian::Little)
+    }
+
+    pub fn dwarf(&self) -> gimli::Dwarf<Reader> {
+        gimli::Dwarf::load(|id| -> Result<Reader, gimli::Error> { Ok(self.reader(id)) })
+            .expect("loading from memory can't fail")
+    }
+
+    /// The sections as a DWARF package, if they have a unit index (`.debug_cu_index`).
+    fn package(&self) -> anyhow::Result<Option<gimli::DwarfPackage<Reader>>> {
+        if self
+            .sections
+            .get(&SectionId::DebugCuIndex)
+            .is_none_or(|data| data.is_empty())
+        {
+            return Ok(None);
+        }
+        let empty = EndianRcSlice::new(Rc::from(&[][..]), RunTimeEndian::Little);
+        let package = gimli::DwarfPackage::load(
+            |id| -> Result<Reader, gimli::Error> { Ok(self.reader(id)) },
+            empty,
+        )?;
+        Ok(Some(package))
+    }
+
+    /// Whether there are any units.
+    pub fn has_units(&self) -> bool {
+        self.sections
+            .get(&SectionId::DebugInfo)
+            .is_some_and(|data| !data.is_empty())
+    }
+}
+
+/// Split DWARF objects (`.dwo`) and packages (`.dwp`) of skeleton units, which hold the functions
+/// and inlining of code built with `-gsplit-dwarf` while the module keeps only the line tables.
+#[derive(Default)]
+pub struct SplitDwarf {
+    objects: Vec<SplitObject>,
+    /// Packages, which find their units by DWO id in their unit index.
+    packages: Vec<gimli::DwarfPackage<Reader>>,
+}
+
+struct SplitObject {
+    /// File name, to match the `DW_AT_dwo_name` of skeleton units.
+    name: String,
+    /// DWO ids of the object's units.
+    ids: Vec<gimli::DwoId>,
+    sections: DwarfSections,
+}
+
+impl SplitDwarf {
+    /// Adds the split DWARF object or package read from the file at `path`.
+    pub fn add(&mut self, path: &str, sections: DwarfSections) -> anyhow::Result<()> {
+        if let Some(package) = sections.package()? {
+            self.packages.push(package);
+            return Ok(());
+        }
+        let dwarf = sections.dwarf();
+        let mut ids = vec![];
+        let mut headers = dwarf.units();
+        while let Some(header) = headers.next()? {
+            ids.extend(dwo_unit(&dwarf, header)?.map(|(id, _)| id));
+        }
+        self.objects.push(SplitObject {
+            name: file_name(path).to_string(),
+            ids,
+            sections,
+        });
+        Ok(())
+    }
+
+    /// Completes a lookup, loading the split DWARF it needs from the added objects. Units whose
+    /// object wasn't added are looked up without it, like addr2line's `skip_all_loads`.
+    pub fn resolve<L: LookupContinuation<Buf = Reader>>(
+        &self,
+        mut result: LookupResult<L>,
+    ) -> L::Output {
+        loop {
+            result = match result {
+                LookupResult::Output(output) => return output,
+                LookupResult::Load { load, continuation } => continuation.resume(self.find(&load)),
+            };
+        }
+    }
+
+    fn find(&self, load: &SplitDwarfLoad<Reader>) -> Option<Arc<gimli::Dwarf<Reader>>> {
+        let path = load
+            .path
+            .as_ref()
+            .and_then(|path| gimli::Reader::to_string_lossy(path).ok());
+        let dwarf = match self.object(load.dwo_id, path.as_deref()) {
+            Some(object) => {
+                let mut dwarf = object.sections.dwarf();
+                dwarf.make_dwo(&load.parent);
+                dwarf
+            }
+            None => self
+                .packages
+                .iter()
+                .find_map(|package| package.find_cu(load.dwo_id, &load.parent).ok()?)?,
+        };
+        // addr2line wants an `Arc`, although the readers share their data with an `Rc`.
+        #[allow(clippy::arc_with_non_send_sync)]
+        Some(Arc::new(dwarf))
+    }
+
+    /// The object with the unit `id`, or else the one named like the file at `path`.
+    fn object(&self, id: gimli::DwoId, path: Option<&str>) -> Option<&SplitObject> {
+        self.objects
+            .iter()
+            .find(|object| object.ids.contains(&id))
+            .or_else(|| {
+                let name = file_name(path?);
+                self.objects.iter().find(|object| object.name == name)
+            })
+    }
+
+    /// How many skeleton units of `dwarf` have their split DWARF in none of the added objects
+    /// and packages, so their functions can't be named.
+    pub fn missing_units(&self, dwarf: &gimli::Dwarf<Reader>) -> anyhow::Result<usize> {
+        let mut missing = 0;
+        let mut headers = dwarf.units();
+        while let Some(header) = headers.next()? {
+            let Some((id, path)) = dwo_unit(dwarf, header)? else {
+                continue;
+            };
+            let packaged = self
+                .packages
+                .iter()
+                .any(|package| package.cu_index.find(id.0).is_some());
+            if !packaged && self.object(id, path.as_deref()).is_none() {
+                missing += 1;
+            }
+        }
+        Ok(missing)
+    }
+}
+
+/// The DWO id of a skeleton or split unit, and the path of its `.dwo` from a skeleton unit.
+/// DWARF 5 has the id in the unit header, GNU's DWARF 4 extension in attributes.
+fn dwo_unit(
+    dwarf: &gimli::Dwarf<Reader>,
+    header: gimli::UnitHeader<Reader>,
+) -> anyhow::Result<Option<(gimli::DwoId, Option<String>)>> {
+    let header_id = match header.type_() {
+        gimli::UnitType::Skeleton(id) | gimli::UnitType::SplitCompilation(id) => Some(id),
+        _ => None,
+    };
+    let unit = dwarf.unit(header)?;
+    let mut entries = unit.entries();
+    let Some((_, root)) = entries.next_dfs()? else {
+        return Ok(None);
+    };
+    let id = match root.attr_value(gimli::DW_AT_GNU_dwo_id)? {
+        Some(gimli::AttributeValue::DwoId(id)) => Some(id),
+        _ => header_id,
+    };
+    let mut path = None;
+    for name in [gimli::DW_AT_dwo_name, gimli::DW_AT_GNU_dwo_name] {
+        if let Some(value) = root.attr_value(name)? {
+            path = path.or_else(|| attr_string(dwarf, &unit, value));
+        }
+    }
+    Ok(id.map(|id| (id, path)))
+}
+
+/// The last component of a path, on Unix or on Windows.
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// Name of a compilation unit, lossily converted to a string.
+pub fn unit_name(unit: &gimli::Unit<Reader>) -> String {
+    unit.name
+        .as_ref()
+        .and_then(|name| gimli::Reader::to_string_lossy(name).ok())
+        .map(|name| name.into_owned())
+        .unwrap_or_else(|| "<unknown unit>".to_string())
+}
+
+/// Resolves a `DW_AT_decl_file`-style file index to a path using the unit's line program header.
+/// Relative paths are joined with their include directory.
+pub fn file_path(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    index: u64,
+) -> Option<String> {
+    let header = unit.line_program.as_ref()?.header();
+    let file = header.file(index)?;
+    let name = attr_string(dwarf, unit, file.path_name())?;
+    if is_absolute(&name) {
+        return Some(name);
+    }
+    match file
+        .directory(header)
+        .and_then(|dir| attr_string(dwarf, unit, dir))
+    {
+        Some(dir) => Some(join_path(&dir, &name)),
+        None => Some(name),
+    }
+}
+
+/// Whether a path is absolute, on Unix or on Windows (`C:\...`, `\\server\share\...` or
+/// `\...`).
+pub fn is_absolute(path: &str) -> bool {
+    let drive = path.as_bytes().get(..3).is_some_and(|prefix| {
+        prefix[0].is_ascii_alphabetic() && prefix[1] == b':' && matches!(prefix[2], b'\\' | b'/')
+    });
+    path.starts_with(['/', '\\']) || drive
+}
+
+/// Joins a relative path to its directory, with the separator the directory uses. Absolute paths
+/// are returned as they are.
+pub fn join_path(dir: &str, name: &str) -> String {
+    if dir.is_empty() || is_absolute(name) {
+        return name.to_string();
+    }
+    let separator = match dir.contains('\\') && !dir.contains('/') {
+        true => '\\',
+        false => '/',
+    };
+    format!("{}{separator}{name}", dir.trim_end_matches(['/', '\\']))
+}
+
+/// Lossily converts a string attribute to a `String`, whatever its form.
+pub fn attr_string(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    value: gimli::AttributeValue<Reader>,
+) -> Option<String> {
+    let string = dwarf.attr_string(unit, value).ok()?;
+    gimli::Reader::to_string_lossy(&string)
+        .ok()
+        .map(|s| s.into_owned())
+}
+
+/// Units with more address ranges than this are cut off (with a warning) rather than stalling the
+/// analysis.
+pub const MAX_RANGES_PER_UNIT: usize = 1_000_000;
+
+/// Sorts `ranges` and merges overlapping and adjacent ones, so they can be binary searched.
+pub fn coalesce(ranges: &mut Vec<(u64, u64)>) {
+    ranges.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+    for &(begin, end) in ranges.iter() {
+        match merged.last_mut() {
+            Some(last) if begin <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((begin, end)),
+        }
+    }
+    *ranges = merged;
+}
+
+/// Whether `address` lies in one of the coalesced `ranges`.
+pub fn contains(ranges: &[(u64, u64)], address: u64) -> bool {
+    let index = ranges.partition_point(|&(begin, _)| begin <= address);
+    index.checked_sub(1).is_some_and(|i| address < ranges[i].1)
+}
+
+/// Returns the sorted addresses where the ranges of inlined calls begin and end. An inlined call
+/// can be split into several ranges scattered across its caller, and each range has its own
+/// boundaries.
+pub fn inlined_boundaries(dwarf: &gimli::Dwarf<Reader>) -> anyhow::Result<Vec<u64>> {
+    let mut boundaries = vec![];
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        let unit = dwarf.unit(header)?;
+        let tombstone = !0u64 >> (64 - 8 * u32::from(unit.encoding().address_size));
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs()? {
+            if entry.tag() != gimli::DW_TAG_inlined_subroutine {
+                continue;
+            }
+            // Calls inlined into code the linker removed can have nonsensical ranges, which are
+            // skipped like addr2line does.
+            let mut ranges = dwarf.die_ranges(&unit, entry)?;
+            while let Ok(Some(range)) = ranges.next() {
+                if range.begin < tombstone && range.end > range.begin {
+                    boundaries.extend([range.begin, range.end]);
+                }
+            }
+        }
+    }
+    boundaries.sort_unstable();
+    boundaries.dedup();
+    Ok(boundaries)
+}
+
+/// Segment selectors (address spaces) from `.debug_aranges`. Wasm has a single address space, but
+/// some producers still place ranges in non-zero segments, which then overlap the default one.
+#[derive(Debug, Default)]
+pub struct Segments {
+    /// Coalesced ranges of the default segment.
+    default: Vec<(u64, u64)>,
+    /// Disjoint `(begin, end, segment)` ranges of the other segments, sorted by `begin`. Where
+    /// segments overlap, the range that starts first wins.
+    other: Vec<(u64, u64, u64)>,
+    /// Segment of every unit whose aranges are all in the same non-zero segment.
+    units: HashMap<gimli::DebugInfoOffset, u64>,
+}
+
+impl Segments {
+    pub fn load(dwarf: &gimli::Dwarf<Reader>) -> anyhow::Result<Segments> {
+        let mut segments = Segments::default();
+        let mut by_segment: HashMap<u64, Vec<(u64, u64)>> = HashMap::new();
+        let mut headers = dwarf.debug_aranges.headers();
+        while let Some(header) = headers.next()? {
+            let mut unit_segments = vec![];
+            let mut entries = header.entries();
+            while let Some(entry) = entries.next()? {
+                let segment = entry.segment().unwrap_or(0);
+                let range = entry.range();
+                by_segment
+                    .entry(segment)
+                    .or_default()
+                    .push((range.begin, range.end));
+                unit_segments.push(segment);
+            }
+            unit_segments.dedup();
+            if let [segment] = unit_segments[..] {
+                if segment != 0 {
+                    segments.units.insert(header.debug_info_offset(), segment);
+                }
+            }
+        }
+        if segments.units.is_empty() {
+            // Only the default segment: nothing needs special handling.
+            return Ok(segments);
+        }
+        for (segment, mut ranges) in by_segment {
+            coalesce(&mut ranges);
+            if segment == 0 {
+                segments.default = ranges;
+            } else {
+                let ranges = ranges.into_iter().map(|(begin, end)| (begin, end, segment));
+                segments.other.extend(ranges);
+            }
+        }
+        segments.other.sort_unstable();
+        let mut disjoint: Vec<(u64, u64, u64)> = Vec::with_capacity(segments.other.len());
+        for (begin, end, segment) in segments.other.drain(..) {
+            let begin = disjoint.last().map_or(begin, |last| begin.max(last.1));
+            if begin < end {
+                disjoint.push((begin, end, segment));
+            }
+        }
+        segments.other = disjoint;
+        Ok(segments)
+    }
+
+    /// Segment of the unit, 0 for the default segment.
+    pub fn unit_segment(&self, unit: &gimli::Unit<Reader>) -> u64 {
+        unit.header
+            .offset()
+            .as_debug_info_offset()
+            .and_then(|offset| self.units.get(&offset).copied())
+            .unwrap_or(0)
+    }
+
+    /// The non-zero segment `address` lies in, if it isn't also covered by the default segment.
+    pub fn segment_of(&self, address: u64) -> Option<u64> {
+        if contains(&self.default, address) {
+            return None;
+        }
+        let index = self
+            .other
+            .partition_point(|&(begin, _, _)| begin <= address);
+        let &(_, end, segment) = self.other.get(index.checked_sub(1)?)?;
+        (address < end).then_some(segment)
+    }
+}
@@ -0,0 +1,204 @@
+use std::{collections::HashMap, io::Write, ops::Range};
+
+use wasmparser::{Name, NameSectionReader, Operator, Parser, Payload, TypeRef};
+
+use crate::{
+    demangle::Demangler,
+    messages::{Lang, Message},
+    wasm,
+};
+
+/// A tag defined in the tag section of a module using exception handling.
+#[derive(Clone, Debug)]
+pub struct Tag {
+    /// Index in the tag index space, i.e. counting imported tags.
+    pub index: u32,
+    pub name: Option<String>,
+    /// Position of the tag's entry in the file.
+    pub file_range: Range<u64>,
+}
+
+impl Tag {
+    /// Frame for the tag in the breakdown.
+    pub fn frame(&self) -> String {
+        match &self.name {
+            Some(name) => format!("@tag: {name}"),
+            None => format!("@tag: #{}", self.index),
+        }
+    }
+}
+
+/// Returns the tags defined in the module, named after the `name` section if possible.
+pub fn tags(module: &[u8]) -> anyhow::Result<Vec<Tag>> {
+    let mut tags = vec![];
+    let mut imported = 0;
+    let mut names = HashMap::new();
+    for payload in Parser::new(0).parse_all(module) {
+        match payload? {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    if let TypeRef::Tag(_) = import?.ty {
+                        imported += 1;
+                    }
+                }
+            }
+            Payload::TagSection(reader) => {
+                let end = reader.range().end as u64;
+                let mut entries = reader.into_iter_with_offsets().peekable();
+                while let Some(entry) = entries.next() {
+                    let (offset, _) = entry?;
+                    let next = match entries.peek() {
+                        Some(Ok((next, _))) => *next as u64,
+                        _ => end,
+                    };
+                    tags.push(Tag {
+                        index: imported + tags.len() as u32,
+                        name: None,
+                        file_range: offset as u64..next,
+                    });
+                }
+            }
+            Payload::CustomSection(section) if section.name() == "name" => {
+                for subsection in NameSectionReader::new(section.data(), section.data_offset()) {
+                    // Names are optional, a broken name section only loses them.
+                    let Ok(Name::Tag(map)) = subsection else {
+                        continue;
+                    };
+                    for naming in map.into_iter().flatten() {
+                        names.insert(naming.index, naming.name.to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    for tag in &mut tags {
+        tag.name = names.remove(&tag.index);
+    }
+    Ok(tags)
+}
+
+/// Exception handling code in one function.
+#[derive(Clone, Debug, Default)]
+pub struct Handlers {
+    pub index: u32,
+    pub name: Option<String>,
+    pub size: u64,
+    /// `try` and `try_table` blocks.
+    pub try_blocks: usize,
+    /// `catch` and `catch_all` handlers, and the catch clauses of `try_table`s.
+    pub catches: usize,
+    /// Estimated bytes spent on exception handling: the exception handling instructions
+    /// themselves, plus the landing pads (the code of `catch` and `catch_all` handlers). Landing
+    /// pads of `try_table` are ordinary blocks and can't be told apart, so they aren't counted.
+    pub overhead: u64,
+}
+
+/// Returns the functions that handle exceptions, the ones with the largest overhead first.
+pub fn handlers(module: &[u8]) -> anyhow::Result<Vec<Handlers>> {
+    let names = wasm::function_names(module)?;
+    let mut demangler = Demangler::default();
+    let mut functions = vec![];
+    for function in wasm::functions(module)? {
+        let mut handlers = Handlers {
+            index: function.index,
+            name: names
+                .get(&function.index)
+                .map(|name| demangler.demangle(name).to_string()),
+            size: function.size,
+            ..Default::default()
+        };
+        // For each open block, where its handler started if it is a `try` in a handler.
+        let mut blocks: Vec<Option<usize>> = vec![];
+        // Handler code nested in another handler is only counted once.
+        let mut in_handler = 0;
+        let mut reader = function.body.get_operators_reader()?;
+        while !reader.eof() {
+            let start = reader.original_position();
+            let op = reader.read()?;
+            let end = reader.original_position();
+            let instruction = (end - start) as u64;
+            // Instructions inside a handler are counted as part of the landing pad.
+            let outside = if in_handler == 0 { instruction } else { 0 };
+            match op {
+                Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. } => {
+                    blocks.push(None)
+                }
+                Operator::Try { .. } => {
+                    handlers.try_blocks += 1;
+                    handlers.overhead += outside;
+                    blocks.push(None);
+                }
+                Operator::TryTable { try_table } => {
+                    handlers.try_blocks += 1;
+                    handlers.catches += try_table.catches.len();
+                    handlers.overhead += outside;
+                    blocks.push(None);
+                }
+                Operator::Catch { .. } | Operator::CatchAll => {
+                    handlers.catches += 1;
+                    // Further handlers of the same `try` are already part of its landing pad.
+                    if let Some(block @ None) = blocks.last_mut() {
+                        *block = Some(start);
+                        in_handler += 1;
+                    }
+                }
+                Operator::Delegate { .. } => {
+                    handlers.overhead += outside;
+                    blocks.pop();
+                }
+                Operator::Throw { .. } | Operator::ThrowRef | Operator::Rethrow { .. } => {
+                    handlers.overhead += outside
+                }
+                Operator::End => {
+                    if let Some(Some(handler_start)) = blocks.pop() {
+                        in_handler -= 1;
+                        if in_handler == 0 {
+                            handlers.overhead += (end - handler_start) as u64;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if handlers.try_blocks > 0 || handlers.overhead > 0 {
+            functions.push(handlers);
+        }
+    }
+    functions.sort_by(|a, b| b.overhead.cmp(&a.overhead).then(a.index.cmp(&b.index)));
+    Ok(functions)
+}
+
+pub fn write_report(module: &[u8], lang: Lang, mut output: impl Write) -> anyhow::Result<()> {
+    writeln!(output, "== exception-handling ==")?;
+    let tags = tags(module)?;
+    let functions = handlers(module)?;
+    if tags.is_empty() && functions.is_empty() {
+        writeln!(output, "{}", Message::NoExceptionHandling.text(lang))?;
+        return Ok(());
+    }
+    let tag_bytes: u64 = tags
+        .iter()
+        .map(|tag| tag.file_range.end - tag.file_range.start)
+        .sum();
+    let overhead: u64 = functions.iter().map(|function| function.overhead).sum();
+    writeln!(
+        output,
+        "{} tags ({tag_bytes} bytes), {} functions with exception handling, estimated overhead: {overhead} bytes",
+        tags.len(),
+        functions.len()
+    )?;
+    for function in &functions {
+        writeln!(
+            output,
+            "  {:>10} bytes overhead  {:>4} try {:>4} catch  {:>10} bytes  #{:<6} {}",
+            function.overhead,
+            function.try_blocks,
+            function.catches,
+            function.size,
+            function.index,
+            function.name.as_deref().unwrap_or("<unnamed>")
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use std::{
+    io::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+use crate::Contributors;
+
+/// Progress and result events emitted during analysis.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    AnalysisStarted {
+        module_size: u64,
+    },
+    UnitAnalyzed {
+        name: String,
+        size: u64,
+        attributed: u64,
+    },
+    LocationsIndexed {
+        indexed: u64,
+        total: u64,
+    },
+    BytesAttributed {
+        attributed: u64,
+        total: u64,
+    },
+    Warning {
+        message: String,
+    },
+    PhaseFinished {
+        phase: Phase,
+        elapsed_ms: u64,
+    },
+    AnalysisFinished {
+        attributed: u64,
+        unattributed: u64,
+        elapsed_ms: u64,
+    },
+}
+
+/// The consecutive phases of the analysis of a module's code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Phase {
+    /// Parsing the module's sections and loading its debug info.
+    Load,
+    /// Reading the line tables and looking up the functions (and inlined functions) of every
+    /// location in the debug info entries, in address order.
+    Index,
+    /// Building the breakdown from the index, and attributing everything that isn't code.
+    Attribute,
+}
+
+impl Phase {
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Load => "load",
+            Phase::Index => "index",
+            Phase::Attribute => "attribute",
+        }
+    }
+}
+
+/// Receives events as the analysis progresses.
+pub trait Observer {
+    fn event(&mut self, event: Event);
+
+    /// Called regularly while code is indexed and attributed, with the breakdown so far. It lacks
+    /// the unattributed bytes and everything that is attributed after the code, and is empty
+    /// during the index.
+    fn checkpoint(&mut self, _contributors: &Contributors) {}
+
+    /// Called regularly while code is indexed and attributed. Returning a reason ends the analysis
+    /// of the code early: the code not attributed yet is shown as unmapped, and the analysis is
+    /// marked as truncated.
+    fn should_stop(&mut self) -> Option<StopReason> {
+        None
+    }
+}
+
+/// Why an [`Observer`] stopped the analysis early.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// The analysis used more than `limit` bytes of memory, and would likely run out of it soon,
+    /// e.g. in a browser tab.
+    Memory { limit: u64 },
+}
+
+impl<O: Observer> Observer for Option<O> {
+    fn event(&mut self, event: Event) {
+        if let Some(observer) = self {
+            observer.event(event);
+        }
+    }
+
+    fn checkpoint(&mut self, contributors: &Contributors) {
+        if let Some(observer) = self {
+            observer.checkpoint(contributors);
+        }
+    }
+
+    fn should_stop(&mut self) -> Option<StopReason> {
+        self.as_mut()?.should_stop()
+    }
+}
+
+/// Passes everything on to both observers.
+impl<A: Observer, B: Observer> Observer for (A, B) {
+    fn event(&mut self, event: Event) {
+        self.0.event(event.clone());
+        self.1.event(event);
+    }
+
+    fn checkpoint(&mut self, contributors: &Contributors) {
+        self.0.checkpoint(contributors);
+        self.1.checkpoint(contributors);
+    }
+
+    fn should_stop(&mut self) -> Option<StopReason> {
+        self.0.should_stop().or_else(|| self.1.should_stop())
+    }
+}
+
+/// Observer that ignores all events.
+pub struct Ignore;
+
+impl Observer for Ignore {
+    fn event(&mut self, _event: Event) {}
+}
+
+/// Writes every event as one JSON line with a millisecond timestamp (`ts`).
+///
+/// Writing never interrupts the analysis; the first I/O error is kept and returned by `finish`.
+pub struct NdjsonWriter<W: Write> {
+    output: W,
+    error: Option<std::io::Error>,
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    ts: u128,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    pub fn new(output: W) -> Self {
+        NdjsonWriter {
+            output,
+            error: None,
+        }
+    }
+
+    /// Returns the output, flushed, unless writing an event failed.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => self.output.flush().map(|_| self.output),
+        }
+    }
+}
+
+impl<W: Write> Observer for NdjsonWriter<W> {
+    fn event(&mut self, event: Event) {
+        if self.error.is_some() {
+            return;
+        }
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        let record = Record { ts, event: &event };
+        let result = serde_json::to_writer(&mut self.output, &record)
+            .map_err(std::io::Error::from)
+            .and_then(|_| writeln!(self.output))
+            .and_then(|_| self.output.flush());
+        if let Err(err) = result {
+            self.error = Some(err);
+        }
+    }
+}
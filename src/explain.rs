@@ -0,0 +1,292 @@
+use std::{collections::HashMap, io::Write};
+
+use addr2line::gimli::Reader as _;
+use serde::Serialize;
+use wasmparser::Operator;
+
+use crate::{
+    demangle::Demangler,
+    dwarf::{DwarfSections, Reader},
+    wasm::{self, DataSegment, Function},
+};
+
+/// Where the bytes of a single function come from.
+#[derive(Clone, Debug, Serialize)]
+pub struct Explanation {
+    pub name: String,
+    pub index: u32,
+    pub size: u64,
+    /// Bytes the line tables attribute to the function itself.
+    pub self_size: u64,
+    /// Functions inlined directly into this one, with the bytes attributed to them (including
+    /// whatever was inlined into them in turn).
+    pub inlined: Vec<Inlined>,
+    /// Bytes without line information (locals declarations, padding, stripped debug info).
+    pub unattributed: u64,
+    pub br_tables: BrTables,
+    /// Data segments that `i32.const` instructions in the function point into.
+    pub referenced_data: Vec<DataReference>,
+    pub complexity: Complexity,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Inlined {
+    pub name: String,
+    pub size: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct BrTables {
+    pub count: u64,
+    pub size: u64,
+}
+
+/// Rough complexity measures of a function body. Large values tend to come with bloated code.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Complexity {
+    /// Number of locals declared by the body, not counting parameters.
+    pub locals: u64,
+    /// Deepest nesting of blocks, loops, `if`s and `try`s.
+    pub max_nesting: u32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DataReference {
+    pub segment: usize,
+    pub address: u64,
+    pub segment_size: u64,
+    pub references: u64,
+}
+
+/// Explains every function whose name is `query`, or contains `query` if no name matches exactly.
+///
+/// Names are taken from the name section, falling back to DWARF. Rust crate disambiguators
+/// (`core[1a2b3c]::`) are ignored when matching.
+pub fn explain(module: &[u8], query: &str) -> anyhow::Result<Vec<Explanation>> {
+    let wasm_file = object::wasm::WasmFile::parse(module)?;
+    let sections = DwarfSections::load(&wasm_file)?;
+    let context = addr2line::Context::from_dwarf(sections.dwarf())?;
+    let mut demangler = Demangler::default();
+
+    let functions = wasm::functions(module)?;
+    let names = wasm::function_names(module)?;
+    let mut named: Vec<(&Function, String)> = vec![];
+    for function in &functions {
+        let name = match names.get(&function.index) {
+            Some(name) => demangler.demangle(name).to_string(),
+            None => match outermost_function(&context, &mut demangler, function.code_offset)? {
+                Some(name) => name,
+                None => continue,
+            },
+        };
+        named.push((function, name));
+    }
+
+    let exact: Vec<_> = named
+        .iter()
+        .filter(|(_, name)| name == query || strip_disambiguators(name) == query)
+        .collect();
+    let matches = if exact.is_empty() {
+        named
+            .iter()
+            .filter(|(_, name)| name.contains(query) || strip_disambiguators(name).contains(query))
+            .collect()
+    } else {
+        exact
+    };
+
+    let segments = wasm::data_segments(module)?;
+    matches
+        .into_iter()
+        .map(|(function, name)| {
+            explain_function(&context, &mut demangler, &segments, function, name)
+        })
+        .collect()
+}
+
+fn explain_function(
+    context: &addr2line::Context<Reader>,
+    demangler: &mut Demangler,
+    segments: &[DataSegment],
+    function: &Function,
+    name: &str,
+) -> anyhow::Result<Explanation> {
+    let range = function.code_range();
+    let mut self_size = 0;
+    let mut inlined: HashMap<String, u64> = HashMap::new();
+    for (start, size, _) in context.find_location_range(range.start, range.end)? {
+        let size = size.min(range.end - start);
+        let mut frames = context.find_frames(start).skip_all_loads()?;
+        let mut names = vec![];
+        while let Some(frame) = frames.next()? {
+            let name = match frame.function {
+                Some(function) => demangler
+                    .demangle(&function.name.to_string_lossy()?)
+                    .to_string(),
+                None => "<Unknown>".to_string(),
+            };
+            names.push(name);
+        }
+        // Frames are ordered from the innermost inlined function outwards.
+        match names.len() {
+            0 | 1 => self_size += size,
+            n => *inlined.entry(names[n - 2].clone()).or_default() += size,
+        }
+    }
+    let mut inlined: Vec<_> = inlined
+        .into_iter()
+        .map(|(name, size)| Inlined { name, size })
+        .collect();
+    inlined.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    let attributed = self_size + inlined.iter().map(|i| i.size).sum::<u64>();
+
+    let mut complexity = Complexity::default();
+    for locals in function.body.get_locals_reader()? {
+        complexity.locals += u64::from(locals?.0);
+    }
+    let mut nesting = 0;
+    let mut br_tables = BrTables::default();
+    let mut references: HashMap<usize, u64> = HashMap::new();
+    let mut operators = function.body.get_operators_reader()?;
+    let body_end = function.file_offset + function.size;
+    let mut previous: Option<(bool, usize)> = None;
+    while !operators.eof() {
+        let (operator, offset) = operators.read_with_offset()?;
+        if let Some((true, start)) = previous {
+            br_tables.size += (offset - start) as u64;
+        }
+        previous = Some((matches!(operator, Operator::BrTable { .. }), offset));
+        match operator {
+            Operator::Block { .. }
+            | Operator::Loop { .. }
+            | Operator::If { .. }
+            | Operator::Try { .. }
+            | Operator::TryTable { .. } => {
+                nesting += 1;
+                complexity.max_nesting = complexity.max_nesting.max(nesting);
+            }
+            // `delegate` ends its `try` like `end` does.
+            Operator::End | Operator::Delegate { .. } => nesting = nesting.saturating_sub(1),
+            Operator::BrTable { .. } => br_tables.count += 1,
+            Operator::I32Const { value } => {
+                let address = value as u32 as u64;
+                let segment = segments.iter().position(|s| {
+                    s.memory_offset.is_some_and(|start| {
+                        (start..start + s.data.len() as u64).contains(&address)
+                    })
+                });
+                if let Some(segment) = segment {
+                    *references.entry(segment).or_default() += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some((true, start)) = previous {
+        br_tables.size += body_end - start as u64;
+    }
+
+    let mut referenced_data: Vec<_> = references
+        .into_iter()
+        .map(|(segment, references)| DataReference {
+            segment,
+            address: segments[segment].memory_offset.unwrap_or_default(),
+            segment_size: segments[segment].data.len() as u64,
+            references,
+        })
+        .collect();
+    referenced_data.sort_by_key(|r| r.segment);
+
+    Ok(Explanation {
+        name: name.to_string(),
+        index: function.index,
+        size: function.size,
+        self_size,
+        inlined,
+        unattributed: function.size.saturating_sub(attributed),
+        br_tables,
+        referenced_data,
+        complexity,
+    })
+}
+
+pub fn write_text(explanations: &[Explanation], mut output: impl Write) -> anyhow::Result<()> {
+    for e in explanations {
+        writeln!(
+            output,
+            "{} (function #{}, {} bytes)",
+            e.name, e.index, e.size
+        )?;
+        writeln!(output, "  {:>10}  self code", e.self_size)?;
+        let inlined: u64 = e.inlined.iter().map(|i| i.size).sum();
+        writeln!(output, "  {inlined:>10}  inlined callees")?;
+        for callee in &e.inlined {
+            writeln!(output, "  {:>10}    {}", callee.size, callee.name)?;
+        }
+        writeln!(output, "  {:>10}  without line info", e.unattributed)?;
+        writeln!(
+            output,
+            "  {:>10}  in {} br_table instructions",
+            e.br_tables.size, e.br_tables.count
+        )?;
+        writeln!(
+            output,
+            "  {} locals, blocks nested up to {} deep",
+            e.complexity.locals, e.complexity.max_nesting
+        )?;
+        writeln!(
+            output,
+            "  referenced data segments: {}",
+            e.referenced_data.len()
+        )?;
+        for data in &e.referenced_data {
+            writeln!(
+                output,
+                "  {:>10}    segment #{} at 0x{:x} ({} references)",
+                data.segment_size, data.segment, data.address, data.references
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Name of the (non-inlined) function containing `address`, according to DWARF.
+pub(crate) fn outermost_function(
+    context: &addr2line::Context<Reader>,
+    demangler: &mut Demangler,
+    address: u64,
+) -> anyhow::Result<Option<String>> {
+    let mut frames = context.find_frames(address).skip_all_loads()?;
+    let mut name = None;
+    while let Some(frame) = frames.next()? {
+        if let Some(function) = frame.function {
+            name = Some(
+                demangler
+                    .demangle(&function.name.to_string_lossy()?)
+                    .to_string(),
+            );
+        }
+    }
+    Ok(name)
+}
+
+/// Removes Rust v0 crate disambiguators: `core[1a2b3c]::fmt` becomes `core::fmt`.
+pub(crate) fn strip_disambiguators(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut rest = name;
+    while let Some(open) = rest.find('[') {
+        let (before, after) = rest.split_at(open);
+        result.push_str(before);
+        match after.find(']') {
+            Some(close) if after[1..close].chars().all(|c| c.is_ascii_hexdigit()) && close > 1 => {
+                rest = &after[close + 1..];
+            }
+            _ => {
+                result.push('[');
+                rest = &after[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
@@ -0,0 +1,168 @@
+//! Sizes of the import and export names. The JS bindings wasm-bindgen generates get names like
+//! `__wbg_addEventListener_0f6b3a1c2d4e5f67`, which can add up to tens of KB, although nothing
+//! but the generated JS glue ever sees them.
+
+use std::io::Write;
+
+use wasmparser::{Parser, Payload};
+
+use crate::messages::{Lang, Message};
+
+/// Modules wasm-bindgen imports its bindings from, before and after its post-processing.
+const BINDGEN_MODULES: [&str; 2] = ["wbg", "__wbindgen_placeholder__"];
+
+/// How many of the largest names the report lists.
+const REPORT_LIMIT: usize = 20;
+
+/// Whether a name is imported or exported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Import,
+    Export,
+}
+
+/// The name of an import or export, and the bytes its strings take up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameString {
+    pub direction: Direction,
+    /// The module an import is imported from.
+    pub module: Option<String>,
+    pub name: String,
+    /// Bytes of the name and, for imports, the module name, with their length prefixes.
+    pub size: u64,
+}
+
+impl NameString {
+    /// The binding the name belongs to, without wasm-bindgen's `__wbg_` prefix and hash suffix,
+    /// e.g. `addEventListener` for `__wbg_addEventListener_0f6b3a1c2d4e5f67`.
+    pub fn binding(&self) -> &str {
+        let name = self.name.strip_prefix("__wbg_").unwrap_or(&self.name);
+        match name.rsplit_once('_') {
+            Some((binding, hash))
+                if !binding.is_empty()
+                    && hash.len() >= 8
+                    && hash.chars().all(|c| c.is_ascii_hexdigit()) =>
+            {
+                binding
+            }
+            _ => name,
+        }
+    }
+
+    /// Bytes this name's strings would take up if it were the `index`-th name renamed to the
+    /// shortest unused identifier, with a one-letter module name for imports.
+    fn shortened_size(&self, index: usize) -> u64 {
+        let length = match index {
+            0..62 => 1,
+            62..3844 => 2,
+            _ => 3,
+        };
+        let module = match self.direction {
+            Direction::Import => 2,
+            Direction::Export => 0,
+        };
+        (1 + length + module).min(self.size)
+    }
+}
+
+/// Returns the names of all imports and exports, in the order they appear in the module.
+pub fn names(module: &[u8]) -> anyhow::Result<Vec<NameString>> {
+    let mut names = vec![];
+    // Every string is prefixed with its length.
+    let string_size = |string: &str| leb128_size(string.len() as u64) + string.len() as u64;
+    for payload in Parser::new(0).parse_all(module) {
+        match payload? {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    names.push(NameString {
+                        direction: Direction::Import,
+                        module: Some(import.module.to_string()),
+                        name: import.name.to_string(),
+                        size: string_size(import.module) + string_size(import.name),
+                    });
+                }
+            }
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    let export = export?;
+                    names.push(NameString {
+                        direction: Direction::Export,
+                        module: None,
+                        name: export.name.to_string(),
+                        size: string_size(export.name),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(names)
+}
+
+fn leb128_size(value: u64) -> u64 {
+    (64 - u64::from(value.leading_zeros())).div_ceil(7).max(1)
+}
+
+/// Whether the module imports wasm-bindgen's JS bindings.
+pub fn uses_bindgen(names: &[NameString]) -> bool {
+    names
+        .iter()
+        .any(|name| BINDGEN_MODULES.contains(&name.module.as_deref().unwrap_or_default()))
+}
+
+/// Bytes that renaming all imports and exports to the shortest possible names would save. This
+/// is only an option when the only one calling them is generated glue code that can be renamed
+/// along with them, like wasm-bindgen's.
+pub fn shortening_savings(names: &[NameString]) -> u64 {
+    let mut sizes: Vec<_> = names.iter().collect();
+    // The shortest names go to the longest ones.
+    sizes.sort_by_key(|name| std::cmp::Reverse(name.size));
+    sizes
+        .iter()
+        .enumerate()
+        .map(|(index, name)| name.size - name.shortened_size(index))
+        .sum()
+}
+
+pub fn write_report(module: &[u8], lang: Lang, mut output: impl Write) -> anyhow::Result<()> {
+    writeln!(output, "== export-names ==")?;
+    let names = names(module)?;
+    let mut largest: Vec<_> = names.iter().collect();
+    largest.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    largest.truncate(REPORT_LIMIT);
+    let width = largest
+        .iter()
+        .map(|name| name.binding().chars().count())
+        .chain(["binding".len()])
+        .max()
+        .unwrap_or_default();
+    writeln!(
+        output,
+        "  {:>8}  {:<6}  {:<width$}  name",
+        "bytes", "kind", "binding"
+    )?;
+    for name in largest {
+        let kind = match name.direction {
+            Direction::Import => "import",
+            Direction::Export => "export",
+        };
+        let full_name = match &name.module {
+            Some(module) => format!("{module}.{}", name.name),
+            None => name.name.clone(),
+        };
+        writeln!(
+            output,
+            "  {:>8}  {kind:<6}  {:<width$}  {full_name}",
+            name.size,
+            name.binding()
+        )?;
+    }
+    let message = Message::NameStrings {
+        count: names.len(),
+        bytes: names.iter().map(|name| name.size).sum(),
+        savings: shortening_savings(&names),
+    };
+    writeln!(output, "{}", message.text(lang))?;
+    Ok(())
+}
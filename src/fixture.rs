@@ -0,0 +1,654 @@
+//! Small wasm modules with synthetic DWARF, for tests, demos and bug reports that shouldn't need a
+//! toolchain or large binaries. Fixtures are described in code rather than compiled: every
+//! function body is padding of the given size, with line info and inlining as described, and
+//! variables and strings share a single data segment.
+
+use gimli::{
+    write::{
+        Address, AttributeValue, Dwarf, EndianVec, Expression, FileId, LineProgram, LineString,
+        Range, RangeList, Sections, Unit,
+    },
+    Encoding, Format, LineEncoding, LittleEndian,
+};
+
+/// A function of a fixture and the code its lines compiled to.
+#[derive(Debug)]
+pub struct Function {
+    pub name: &'static str,
+    /// Absolute path of the source file.
+    pub file: &'static str,
+    pub line: u64,
+    /// Bytes of code of the function's own lines.
+    pub size: u32,
+    /// A function inlined in the middle of this one.
+    pub inlined: Option<&'static Function>,
+    /// Whether the compiler generated the function (`DW_AT_artificial`).
+    pub artificial: bool,
+}
+
+/// A variable of a fixture, in the data segment.
+#[derive(Debug)]
+pub struct Variable {
+    pub name: &'static str,
+    /// Absolute path of the declaring source file.
+    pub file: &'static str,
+    pub line: u64,
+    pub size: u32,
+}
+
+/// A compilation unit of a fixture.
+#[derive(Debug)]
+pub struct CompilationUnit {
+    pub name: &'static str,
+    pub comp_dir: &'static str,
+    pub language: gimli::DwLang,
+    /// Whether the functions have `DW_TAG_subprogram` entries. Assemblers usually only emit line
+    /// info.
+    pub subprograms: bool,
+    /// Whether the unit has a line program. Linkers and some assemblers leave it out.
+    pub line_info: bool,
+    pub functions: &'static [Function],
+    /// Variables with a fixed address, typed as byte arrays.
+    pub variables: &'static [Variable],
+}
+
+#[derive(Debug)]
+pub struct Fixture {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub units: &'static [CompilationUnit],
+    /// Functions without debug info, only named in the name section, and their sizes.
+    pub without_dwarf: &'static [(&'static str, u32)],
+    /// String literals in the data segment, after the variables. No variable covers them.
+    pub strings: &'static [&'static str],
+}
+
+/// Address of the data segment in linear memory. Small enough to be a single byte of LEB128.
+const DATA_ADDRESS: u64 = 1024;
+
+static DROP_STATE: Function = Function {
+    name: "core::ptr::drop_in_place<app::State>",
+    file: "/rustc/9b00956e56009bab2aa15d7bff10916599e3d6d6/library/core/src/ptr/mod.rs",
+    line: 514,
+    size: 40,
+    inlined: None,
+    artificial: false,
+};
+
+pub const FIXTURES: &[Fixture] = &[
+    Fixture {
+        name: "rust",
+        description: "An application crate, a dependency and the standard library, with inlining",
+        units: &[
+            CompilationUnit {
+                name: "src/lib.rs/@/app.1a2b3c-cgu.0",
+                comp_dir: "/home/user/app",
+                language: gimli::DW_LANG_Rust,
+                subprograms: true,
+                line_info: true,
+                functions: &[
+                    Function {
+                        name: "app::run",
+                        file: "/home/user/app/src/lib.rs",
+                        line: 10,
+                        size: 120,
+                        inlined: Some(&DROP_STATE),
+                        artificial: false,
+                    },
+                    Function {
+                        name: "app::helper",
+                        file: "/home/user/app/src/lib.rs",
+                        line: 30,
+                        size: 60,
+                        inlined: None,
+                        artificial: false,
+                    },
+                ],
+                variables: &[Variable {
+                    name: "app::DEFAULT_CONFIG",
+                    file: "/home/user/app/src/lib.rs",
+                    line: 3,
+                    size: 24,
+                }],
+            },
+            CompilationUnit {
+                name: "/cargo/registry/src/index.crates.io-6f17d22bba15001f/serde-1.0.200/src/lib.rs/@/serde.4d5e6f-cgu.0",
+                comp_dir: "/cargo/registry/src/index.crates.io-6f17d22bba15001f/serde-1.0.200",
+                language: gimli::DW_LANG_Rust,
+                subprograms: true,
+                line_info: true,
+                functions: &[Function {
+                    name: "serde::de::Visitor::visit_str",
+                    file: "/cargo/registry/src/index.crates.io-6f17d22bba15001f/serde-1.0.200/src/de/mod.rs",
+                    line: 1380,
+                    size: 200,
+                    inlined: None,
+                    artificial: false,
+                }],
+                variables: &[],
+            },
+            CompilationUnit {
+                name: "library/core/src/lib.rs/@/core.7a8b9c-cgu.0",
+                comp_dir: "/rustc/9b00956e56009bab2aa15d7bff10916599e3d6d6",
+                language: gimli::DW_LANG_Rust,
+                subprograms: true,
+                line_info: true,
+                functions: &[
+                    Function {
+                        name: "core::fmt::write",
+                        file: "/rustc/9b00956e56009bab2aa15d7bff10916599e3d6d6/library/core/src/fmt/mod.rs",
+                        line: 1140,
+                        size: 300,
+                        inlined: None,
+                        artificial: false,
+                    },
+                    Function {
+                        name: "core::ptr::drop_in_place<alloc::vec::Vec<u8>>",
+                        file: "/rustc/9b00956e56009bab2aa15d7bff10916599e3d6d6/library/core/src/ptr/mod.rs",
+                        line: 514,
+                        size: 30,
+                        inlined: None,
+                        artificial: false,
+                    },
+                ],
+                variables: &[],
+            },
+        ],
+        without_dwarf: &[("__wasm_call_ctors", 4)],
+        strings: &["called `Option::unwrap()` on a `None` value"],
+    },
+    Fixture {
+        name: "c",
+        description: "A C program with parts of wasi-libc",
+        units: &[
+            CompilationUnit {
+                name: "main.c",
+                comp_dir: "/home/user/app",
+                language: gimli::DW_LANG_C11,
+                subprograms: true,
+                line_info: true,
+                functions: &[Function {
+                    name: "main",
+                    file: "/home/user/app/main.c",
+                    line: 5,
+                    size: 50,
+                    inlined: None,
+                    artificial: false,
+                }],
+                variables: &[Variable {
+                    name: "lookup_table",
+                    file: "/home/user/app/main.c",
+                    line: 3,
+                    size: 64,
+                }],
+            },
+            CompilationUnit {
+                name: "simd/blend.s",
+                comp_dir: "/home/user/app",
+                language: gimli::DW_LANG_Mips_Assembler,
+                subprograms: false,
+                line_info: true,
+                functions: &[Function {
+                    name: "blend_rows",
+                    file: "/home/user/app/simd/blend.s",
+                    line: 12,
+                    size: 120,
+                    inlined: None,
+                    artificial: false,
+                }],
+                variables: &[],
+            },
+            CompilationUnit {
+                name: "crt/crt1-command.s",
+                comp_dir: "/opt/wasi-libc",
+                language: gimli::DW_LANG_Mips_Assembler,
+                subprograms: true,
+                line_info: false,
+                functions: &[Function {
+                    name: "_start",
+                    file: "/opt/wasi-libc/crt/crt1-command.s",
+                    line: 1,
+                    size: 30,
+                    inlined: None,
+                    artificial: false,
+                }],
+                variables: &[],
+            },
+            CompilationUnit {
+                name: "dlmalloc/src/dlmalloc.c",
+                comp_dir: "/opt/wasi-libc",
+                language: gimli::DW_LANG_C11,
+                subprograms: true,
+                line_info: true,
+                functions: &[
+                    Function {
+                        name: "malloc",
+                        file: "/opt/wasi-libc/dlmalloc/src/malloc.c",
+                        line: 4590,
+                        size: 400,
+                        inlined: None,
+                        artificial: false,
+                    },
+                    Function {
+                        name: "free",
+                        file: "/opt/wasi-libc/dlmalloc/src/malloc.c",
+                        line: 4820,
+                        size: 250,
+                        inlined: None,
+                        artificial: false,
+                    },
+                ],
+                variables: &[],
+            },
+            CompilationUnit {
+                name: "libc-top-half/musl/src/string/memcpy.c",
+                comp_dir: "/opt/wasi-libc",
+                language: gimli::DW_LANG_C11,
+                subprograms: true,
+                line_info: true,
+                functions: &[Function {
+                    name: "memcpy",
+                    file: "/opt/wasi-libc/libc-top-half/musl/src/string/memcpy.c",
+                    line: 5,
+                    size: 80,
+                    inlined: None,
+                    artificial: false,
+                }],
+                variables: &[],
+            },
+        ],
+        without_dwarf: &[],
+        strings: &["usage: app <input file>\n"],
+    },
+    Fixture {
+        name: "cpp",
+        description: "A C++ class with an implicit destructor, a thunk and templates",
+        units: &[CompilationUnit {
+            name: "src/widget.cpp",
+            comp_dir: "/home/user/gui",
+            language: gimli::DW_LANG_C_plus_plus_14,
+            subprograms: true,
+            line_info: true,
+            functions: &[
+                Function {
+                    name: "Widget::draw()",
+                    file: "/home/user/gui/src/widget.cpp",
+                    line: 12,
+                    size: 150,
+                    inlined: None,
+                    artificial: false,
+                },
+                Function {
+                    name: "Widget::~Widget()",
+                    file: "/home/user/gui/src/widget.h",
+                    line: 8,
+                    size: 50,
+                    inlined: None,
+                    artificial: true,
+                },
+                Function {
+                    name: "non-virtual thunk to Widget::draw()",
+                    file: "/home/user/gui/src/widget.h",
+                    line: 8,
+                    size: 10,
+                    inlined: None,
+                    artificial: true,
+                },
+                Function {
+                    name: "std::__2::basic_ostream<char, std::__2::char_traits<char> >& operator<<(std::__2::basic_ostream<char, std::__2::char_traits<char> >&, Widget const&)",
+                    file: "/home/user/gui/src/widget.cpp",
+                    line: 30,
+                    size: 60,
+                    inlined: None,
+                    artificial: false,
+                },
+                Function {
+                    name: "std::__2::vector<Widget, std::__2::allocator<Widget> >::push_back(Widget const&)",
+                    file: "/opt/wasi-sdk/share/wasi-sysroot/include/c++/v1/vector",
+                    line: 1520,
+                    size: 80,
+                    inlined: None,
+                    artificial: false,
+                },
+            ],
+            variables: &[],
+        }],
+        without_dwarf: &[],
+        strings: &[],
+    },
+    Fixture {
+        name: "names-only",
+        description: "Named functions without any debug info",
+        units: &[],
+        without_dwarf: &[("first", 30), ("second", 60), ("third", 90)],
+        strings: &[],
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Fixture> {
+    FIXTURES.iter().find(|fixture| fixture.name == name)
+}
+
+/// Encodes `fixture` as a wasm module.
+pub fn generate(fixture: &Fixture) -> anyhow::Result<Vec<u8>> {
+    // Function bodies: no locals, padding and `end`.
+    let functions: Vec<&Function> = fixture
+        .units
+        .iter()
+        .flat_map(|unit| unit.functions)
+        .collect();
+    let mut sizes: Vec<u32> = functions
+        .iter()
+        .map(|function| function.size + function.inlined.map_or(0, |inlined| inlined.size))
+        .collect();
+    sizes.extend(fixture.without_dwarf.iter().map(|&(_, size)| size));
+    let mut code = vec![];
+    leb128(&mut code, sizes.len() as u64);
+    // Offset of every body in the code section, which is the address DWARF uses for it.
+    let mut addresses = vec![];
+    for &size in &sizes {
+        let size = size.max(2);
+        leb128(&mut code, size as u64);
+        addresses.push(code.len() as u64);
+        code.push(0x00);
+        code.extend(std::iter::repeat_n(0x01, size as usize - 2));
+        code.push(0x0b);
+    }
+
+    // Variables as zeros, then the strings with their terminating NUL.
+    let mut data = vec![];
+    for variable in fixture.units.iter().flat_map(|unit| unit.variables) {
+        data.extend(std::iter::repeat_n(0x00, variable.size as usize));
+    }
+    for string in fixture.strings {
+        data.extend_from_slice(string.as_bytes());
+        data.push(0x00);
+    }
+
+    let mut module = b"\0asm\x01\0\0\0".to_vec();
+    section(&mut module, 1, &[0x01, 0x60, 0x00, 0x00]);
+    let mut function_types = vec![];
+    leb128(&mut function_types, sizes.len() as u64);
+    function_types.extend(std::iter::repeat_n(0x00, sizes.len()));
+    section(&mut module, 3, &function_types);
+    if !data.is_empty() {
+        // One memory of at least one page.
+        section(&mut module, 5, &[0x01, 0x00, 0x01]);
+    }
+    section(&mut module, 10, &code);
+    if !data.is_empty() {
+        // One active segment for memory 0, at `i32.const DATA_ADDRESS`.
+        let mut segments = vec![0x01, 0x00, 0x41];
+        leb128(&mut segments, DATA_ADDRESS);
+        segments.push(0x0b);
+        leb128(&mut segments, data.len() as u64);
+        segments.extend(data);
+        section(&mut module, 11, &segments);
+    }
+
+    let names: Vec<&str> = functions
+        .iter()
+        .map(|function| function.name)
+        .chain(fixture.without_dwarf.iter().map(|&(name, _)| name))
+        .collect();
+    let mut function_names = vec![];
+    leb128(&mut function_names, names.len() as u64);
+    for (index, name) in names.iter().enumerate() {
+        leb128(&mut function_names, index as u64);
+        string(&mut function_names, name);
+    }
+    let mut name_section = vec![0x01];
+    leb128(&mut name_section, function_names.len() as u64);
+    name_section.extend(function_names);
+    custom_section(&mut module, "name", &name_section);
+
+    if !fixture.units.is_empty() {
+        let sections = dwarf(fixture, &addresses)?;
+        sections.for_each(|id, data| {
+            if !data.slice().is_empty() {
+                custom_section(&mut module, id.name(), data.slice());
+            }
+            Ok::<_, anyhow::Error>(())
+        })?;
+    }
+    Ok(module)
+}
+
+/// Debug info for the units of `fixture`, whose functions start at `addresses` in order and whose
+/// variables follow each other from [`DATA_ADDRESS`].
+fn dwarf(
+    fixture: &Fixture,
+    addresses: &[u64],
+) -> anyhow::Result<Sections<EndianVec<LittleEndian>>> {
+    let encoding = Encoding {
+        format: Format::Dwarf32,
+        version: 4,
+        address_size: 4,
+    };
+    let mut dwarf = Dwarf::new();
+    let mut addresses = addresses.iter().copied();
+    let mut variable_address = DATA_ADDRESS;
+    for compilation_unit in fixture.units {
+        let line_program = match compilation_unit.line_info {
+            true => LineProgram::new(
+                encoding,
+                LineEncoding::default(),
+                LineString::String(compilation_unit.comp_dir.into()),
+                LineString::String(compilation_unit.name.into()),
+                None,
+            ),
+            false => LineProgram::none(),
+        };
+        let id = dwarf.units.add(Unit::new(encoding, line_program));
+        let unit = dwarf.units.get_mut(id);
+        let root = unit.root();
+        let entry = unit.get_mut(root);
+        entry.set(
+            gimli::DW_AT_name,
+            AttributeValue::String(compilation_unit.name.into()),
+        );
+        entry.set(
+            gimli::DW_AT_comp_dir,
+            AttributeValue::String(compilation_unit.comp_dir.into()),
+        );
+        entry.set(
+            gimli::DW_AT_producer,
+            AttributeValue::String("wasmphobia gen-fixture".into()),
+        );
+        entry.set(
+            gimli::DW_AT_language,
+            AttributeValue::Language(compilation_unit.language),
+        );
+        entry.set(
+            gimli::DW_AT_low_pc,
+            AttributeValue::Address(Address::Constant(0)),
+        );
+
+        let mut ranges = vec![];
+        for function in compilation_unit.functions {
+            let start = addresses.next().unwrap_or_default();
+            let length =
+                (function.size + function.inlined.map_or(0, |inlined| inlined.size)).max(2) as u64;
+            ranges.push(Range::StartLength {
+                begin: Address::Constant(start),
+                length,
+            });
+
+            if !compilation_unit.line_info {
+                if compilation_unit.subprograms {
+                    let subprogram = unit.add(root, gimli::DW_TAG_subprogram);
+                    let entry = unit.get_mut(subprogram);
+                    entry.set(
+                        gimli::DW_AT_name,
+                        AttributeValue::String(function.name.into()),
+                    );
+                    entry.set(
+                        gimli::DW_AT_low_pc,
+                        AttributeValue::Address(Address::Constant(start)),
+                    );
+                    entry.set(gimli::DW_AT_high_pc, AttributeValue::Udata(length));
+                }
+                continue;
+            }
+            let file = file_id(&mut unit.line_program, function.file);
+            unit.line_program
+                .begin_sequence(Some(Address::Constant(start)));
+            let row = unit.line_program.row();
+            row.file = file;
+            row.line = function.line;
+            unit.line_program.generate_row();
+            if !compilation_unit.subprograms {
+                unit.line_program.end_sequence(length);
+                continue;
+            }
+
+            let subprogram = unit.add(root, gimli::DW_TAG_subprogram);
+            let entry = unit.get_mut(subprogram);
+            entry.set(
+                gimli::DW_AT_name,
+                AttributeValue::String(function.name.into()),
+            );
+            entry.set(
+                gimli::DW_AT_decl_file,
+                AttributeValue::FileIndex(Some(file)),
+            );
+            entry.set(gimli::DW_AT_decl_line, AttributeValue::Udata(function.line));
+            if function.artificial {
+                entry.set(gimli::DW_AT_artificial, AttributeValue::Flag(true));
+            }
+            entry.set(
+                gimli::DW_AT_low_pc,
+                AttributeValue::Address(Address::Constant(start)),
+            );
+            entry.set(gimli::DW_AT_high_pc, AttributeValue::Udata(length));
+            if let Some(inlined) = function.inlined {
+                // The inlined code sits in the middle of the function's own code.
+                let inlined_start = (function.size / 2) as u64;
+                let inlined_file = file_id(&mut unit.line_program, inlined.file);
+                let origin = unit.add(root, gimli::DW_TAG_subprogram);
+                let entry = unit.get_mut(origin);
+                entry.set(
+                    gimli::DW_AT_name,
+                    AttributeValue::String(inlined.name.into()),
+                );
+                entry.set(
+                    gimli::DW_AT_inline,
+                    AttributeValue::Inline(gimli::DW_INL_inlined),
+                );
+                let call = unit.add(subprogram, gimli::DW_TAG_inlined_subroutine);
+                let entry = unit.get_mut(call);
+                entry.set(
+                    gimli::DW_AT_abstract_origin,
+                    AttributeValue::UnitRef(origin),
+                );
+                entry.set(
+                    gimli::DW_AT_low_pc,
+                    AttributeValue::Address(Address::Constant(start + inlined_start)),
+                );
+                entry.set(
+                    gimli::DW_AT_high_pc,
+                    AttributeValue::Udata(inlined.size as u64),
+                );
+                entry.set(
+                    gimli::DW_AT_call_file,
+                    AttributeValue::FileIndex(Some(file)),
+                );
+                entry.set(
+                    gimli::DW_AT_call_line,
+                    AttributeValue::Udata(function.line + 1),
+                );
+
+                let row = unit.line_program.row();
+                row.address_offset = inlined_start;
+                row.file = inlined_file;
+                row.line = inlined.line;
+                unit.line_program.generate_row();
+                let row = unit.line_program.row();
+                row.address_offset = inlined_start + inlined.size as u64;
+                row.file = file;
+                row.line = function.line + 2;
+                unit.line_program.generate_row();
+            }
+            unit.line_program.end_sequence(length);
+        }
+
+        for variable in compilation_unit.variables {
+            let byte = unit.add(root, gimli::DW_TAG_base_type);
+            let entry = unit.get_mut(byte);
+            entry.set(gimli::DW_AT_name, AttributeValue::String("u8".into()));
+            entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(1));
+            let array = unit.add(root, gimli::DW_TAG_array_type);
+            unit.get_mut(array)
+                .set(gimli::DW_AT_type, AttributeValue::UnitRef(byte));
+            let subrange = unit.add(array, gimli::DW_TAG_subrange_type);
+            unit.get_mut(subrange).set(
+                gimli::DW_AT_count,
+                AttributeValue::Udata(variable.size as u64),
+            );
+
+            let file = compilation_unit
+                .line_info
+                .then(|| file_id(&mut unit.line_program, variable.file));
+            let mut location = Expression::new();
+            location.op_addr(Address::Constant(variable_address));
+            variable_address += variable.size as u64;
+            let id = unit.add(root, gimli::DW_TAG_variable);
+            let entry = unit.get_mut(id);
+            entry.set(
+                gimli::DW_AT_name,
+                AttributeValue::String(variable.name.into()),
+            );
+            entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(array));
+            if let Some(file) = file {
+                entry.set(
+                    gimli::DW_AT_decl_file,
+                    AttributeValue::FileIndex(Some(file)),
+                );
+                entry.set(gimli::DW_AT_decl_line, AttributeValue::Udata(variable.line));
+            }
+            entry.set(gimli::DW_AT_location, AttributeValue::Exprloc(location));
+        }
+        let ranges = unit.ranges.add(RangeList(ranges));
+        unit.get_mut(root)
+            .set(gimli::DW_AT_ranges, AttributeValue::RangeListRef(ranges));
+    }
+    let mut sections = Sections::new(EndianVec::new(LittleEndian));
+    dwarf.write(&mut sections)?;
+    Ok(sections)
+}
+
+fn file_id(line_program: &mut LineProgram, path: &str) -> FileId {
+    let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+    let dir = line_program.add_directory(LineString::String(dir.into()));
+    line_program.add_file(LineString::String(name.into()), dir, None)
+}
+
+fn leb128(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn string(out: &mut Vec<u8>, s: &str) {
+    leb128(out, s.len() as u64);
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn section(module: &mut Vec<u8>, id: u8, content: &[u8]) {
+    module.push(id);
+    leb128(module, content.len() as u64);
+    module.extend_from_slice(content);
+}
+
+fn custom_section(module: &mut Vec<u8>, name: &str, data: &[u8]) {
+    let mut content = vec![];
+    string(&mut content, name);
+    content.extend_from_slice(data);
+    section(module, 0, &content);
+}
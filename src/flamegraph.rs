@@ -0,0 +1,340 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+    io::Write,
+    path::Path,
+};
+
+use anyhow::Context;
+use regex::{Captures, Regex};
+use sha2::{Digest, Sha256};
+
+use crate::{html, output::AtomicFile, Contributors};
+
+pub use inferno::flamegraph::{color::PaletteMap, Options};
+
+/// Flame graph options for a size breakdown: sizes are shown in KB, and regions smaller than
+/// `size_threshold` bytes are omitted.
+pub fn options(title: impl Into<String>, size_threshold: usize) -> Options<'static> {
+    let mut options = Options::default();
+    options.title = title.into();
+    options.subtitle = Some("Wasm module size breakdown".to_string());
+    options.count_name = "KB".to_string();
+    options.factor = 1.0 / 1000.0;
+    options.min_width = size_threshold as f64 / 1000.0;
+    options.frame_height = 24;
+    options.name_type = "".to_string();
+    options
+}
+
+/// Flame graph options for a breakdown of debug info entry counts.
+pub fn die_count_options(title: impl Into<String>) -> Options<'static> {
+    let mut options = options(title, 0);
+    options.subtitle = Some("Debug info entries per source file".to_string());
+    options.count_name = "DIEs".to_string();
+    options.factor = 1.0;
+    options
+}
+
+/// Flame graph options for a breakdown of estimated gzip-compressed bytes.
+pub fn gzip_options(title: impl Into<String>, size_threshold: usize) -> Options<'static> {
+    let mut options = options(title, size_threshold);
+    options.subtitle = Some("Estimated gzip-compressed size breakdown".to_string());
+    options
+}
+
+/// Loads a palette map in flamegraph.pl's `frame->rgb(r,g,b)` format, or an empty one if `path`
+/// does not exist yet.
+pub fn load_palette_map(path: &Path) -> anyhow::Result<PaletteMap> {
+    PaletteMap::load_from_file_or_empty(&path)
+        .with_context(|| format!("Reading palette map {}", path.display()))
+}
+
+/// Writes `palette_map` to `path`, replacing its previous content.
+pub fn save_palette_map(palette_map: &PaletteMap, path: &Path) -> anyhow::Result<()> {
+    let mut file = AtomicFile::create(path)
+        .with_context(|| format!("Writing palette map {}", path.display()))?;
+    palette_map.to_writer(&mut file)?;
+    file.commit()
+}
+
+/// How [`write_flamegraph`] post-processes the SVG rendered by inferno.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Rendering {
+    /// Pass the SVG through [`minify`].
+    pub minify: bool,
+    /// Shorten frame labels to this many characters with [`truncate_labels`].
+    pub max_label_length: Option<usize>,
+}
+
+/// Renders `contributors` as an SVG flame graph, post-processed as `rendering` says.
+pub fn write_flamegraph(
+    contributors: &Contributors,
+    options: &mut Options<'_>,
+    rendering: Rendering,
+    output: impl Write,
+) -> anyhow::Result<()> {
+    render(&folded_lines(contributors), options, rendering, output)
+}
+
+/// Options for [`write_differential`]: like [`options`], but with sizes in bytes, so that inferno
+/// computes the percentages of the changes right.
+pub fn differential_options(title: impl Into<String>, size_threshold: usize) -> Options<'static> {
+    let mut options = options(title, size_threshold);
+    options.subtitle =
+        Some("Size difference: grown frames are red, shrunk frames blue".to_string());
+    options.count_name = "bytes".to_string();
+    options.factor = 1.0;
+    options
+}
+
+/// Renders the breakdown of `new` as a differential flame graph against `old`: frames are as wide
+/// as their new size, and shaded red if they grew or blue if they shrank. Every frame that changed
+/// has the change in bytes appended to its name, and its tooltip shows the change in percent of
+/// the total. Code that is gone entirely has no width, and only shows in its parents' changes.
+pub fn write_differential(
+    old: &Contributors,
+    new: &Contributors,
+    options: &mut Options<'_>,
+    rendering: Rendering,
+    output: impl Write,
+) -> anyhow::Result<()> {
+    let (old_sizes, new_sizes) = (node_sizes(old), node_sizes(new));
+    let keys: BTreeSet<_> = old.keys().chain(new.keys()).collect();
+    let lines: Vec<_> = keys
+        .into_iter()
+        .map(|key| {
+            let mut frames = vec![];
+            for (start, end) in frame_bounds(key) {
+                let path = &key[..end];
+                let delta = new_sizes.get(path).copied().unwrap_or(0) as i64
+                    - old_sizes.get(path).copied().unwrap_or(0) as i64;
+                let frame = &key[start..end];
+                frames.push(match delta {
+                    0 => frame.to_string(),
+                    delta => format!("{frame} ({delta:+} B)"),
+                });
+            }
+            let size = |contributors: &Contributors| contributors.get(key).copied().unwrap_or(0);
+            format!("{} {} {}", frames.join(";"), size(old), size(new))
+        })
+        .collect();
+    render(&lines, options, rendering, output)
+}
+
+/// The total size of every node of the breakdown, by its path.
+fn node_sizes(contributors: &Contributors) -> HashMap<&str, u64> {
+    let mut sizes = HashMap::new();
+    for (key, &size) in contributors {
+        for (_, end) in frame_bounds(key) {
+            *sizes.entry(&key[..end]).or_default() += size;
+        }
+    }
+    sizes
+}
+
+/// Start and end of every frame of `key`.
+fn frame_bounds(key: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let mut start = 0;
+    key.split(';').map(move |frame| {
+        let bounds = (start, start + frame.len());
+        start += frame.len() + 1;
+        bounds
+    })
+}
+
+fn render(
+    lines: &[String],
+    options: &mut Options<'_>,
+    rendering: Rendering,
+    mut output: impl Write,
+) -> anyhow::Result<()> {
+    let mut svg = vec![];
+    inferno::flamegraph::from_lines(options, lines.iter().map(|v| v.as_str()), &mut svg)?;
+    let svg = String::from_utf8(svg)?;
+    let mut svg = make_accessible(&svg, options);
+    if let Some(max_length) = rendering.max_label_length {
+        svg = truncate_labels(&svg, max_length);
+    }
+    if rendering.minify {
+        output.write_all(self::minify(&svg).as_bytes())?;
+    } else {
+        output.write_all(svg.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// `contributors` as folded stack lines (`frame;frame;frame size`), sorted by their frames.
+pub fn folded_lines(contributors: &Contributors) -> Vec<String> {
+    let mut lines: Vec<_> = contributors
+        .iter()
+        .map(|(key, size)| format!("{} {}", key, size))
+        .collect();
+    lines.sort();
+    lines
+}
+
+/// Writes `contributors` in the folded stack format of flamegraph.pl, one line per contributor, for
+/// other flame graph tools and scripts.
+pub fn write_folded(contributors: &Contributors, mut output: impl Write) -> anyhow::Result<()> {
+    for line in folded_lines(contributors) {
+        writeln!(output, "{line}")?;
+    }
+    Ok(())
+}
+
+/// Shortens `name` to at most `max_length` characters. A shortened name ends in `…` and four hex
+/// digits of its SHA-256, so names that only differ after the cut still get different labels, and
+/// the same name gets the same label in every run.
+pub fn truncate_label(name: &str, max_length: usize) -> Cow<'_, str> {
+    if name.chars().count() <= max_length {
+        return Cow::Borrowed(name);
+    }
+    let hash = Sha256::digest(name.as_bytes());
+    let suffix = format!("…{:02x}{:02x}", hash[0], hash[1]);
+    let keep = max_length.saturating_sub(suffix.chars().count());
+    let end = name.char_indices().nth(keep).map_or(name.len(), |(i, _)| i);
+    Cow::Owned(format!("{}{suffix}", &name[..end]))
+}
+
+/// Shortens the frame labels of an SVG written by [`write_flamegraph`] with [`truncate_label`].
+/// Titles (the tooltips and accessible names) keep the full names, and labels that inferno
+/// already cut shorter to fit their frame are left alone. Zooming in lets inferno's script fit
+/// the full name into the frame again.
+pub fn truncate_labels(svg: &str, max_length: usize) -> String {
+    let frame = Regex::new(
+        r"(<title>([^<]*)</title>(?:<desc>[^<]*</desc>)?\s*<rect[^>]*>\s*<text[^>]*>)([^<]*)</text>",
+    )
+    .unwrap();
+    frame
+        .replace_all(svg, |c: &Captures| {
+            let title = unescape(&c[2]);
+            // Titles are `<name> (<size>, <percent>)`.
+            let name = title
+                .rsplit_once(" (")
+                .map_or(title.as_str(), |(name, _)| name);
+            let label = unescape(&c[3]);
+            let truncated = truncate_label(name, max_length);
+            match truncated.chars().count() < label.chars().count() {
+                true => format!("{}{}</text>", &c[1], html::escape(&truncated)),
+                false => c[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Shrinks an SVG written by [`write_flamegraph`] without changing how it renders or behaves:
+/// coordinates are rounded to two decimals, colors are written in hex, frame labels that repeat
+/// the frame's `<title>` (which is its accessible name already) are dropped, and so are comments
+/// (except the notes), indentation and blank lines.
+pub fn minify(svg: &str) -> String {
+    let label = Regex::new(r#"<g tabindex="0" role="img" aria-label="[^"]*">"#).unwrap();
+    let svg = label.replace_all(svg, r#"<g tabindex="0" role="img">"#);
+    let number = Regex::new(r#"(\s(?:x|y|width|height)=")(\d+\.\d+)(%?")"#).unwrap();
+    let svg = number.replace_all(&svg, |c: &Captures| {
+        let value: f64 = c[2].parse().unwrap_or_default();
+        let value = format!("{value:.2}");
+        let value = value.trim_end_matches('0').trim_end_matches('.');
+        format!("{}{value}{}", &c[1], &c[3])
+    });
+    let color = Regex::new(r"rgb\((\d+),(\d+),(\d+)\)").unwrap();
+    let svg = color.replace_all(&svg, |c: &Captures| {
+        let [r, g, b] = [&c[1], &c[2], &c[3]].map(|v| v.parse::<u8>().unwrap_or_default());
+        format!("#{r:02x}{g:02x}{b:02x}")
+    });
+    let comment = Regex::new(r"<!--(?s:.*?)-->").unwrap();
+    let svg = comment.replace_all(&svg, |c: &Captures| match c[0].starts_with("<!--NOTES:") {
+        true => Cow::Owned(c[0].to_string()),
+        false => Cow::Borrowed(""),
+    });
+
+    // Scripts rely on line breaks (and comments end at them), so only whole lines are removed.
+    let mut out = String::with_capacity(svg.len());
+    for line in svg.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("//") {
+            continue;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Lets screen readers and keyboard users navigate a flame graph: frames are focusable, labelled
+/// with their name and size and carry the exact count in a `<desc>`, the document gets a title
+/// and description, and Enter or Space zooms into the focused frame.
+fn make_accessible(svg: &str, options: &Options<'_>) -> String {
+    // Counts are bytes scaled into `count_name`, unless they are shown as-is.
+    let unit = if options.factor == 1.0 {
+        options.count_name.as_str()
+    } else {
+        "bytes"
+    };
+    let mut out = String::with_capacity(svg.len() * 5 / 4);
+
+    let Some(root_end) = svg
+        .find("<svg ")
+        .and_then(|start| svg[start..].find('>').map(|end| start + end))
+    else {
+        return svg.to_string();
+    };
+    out.push_str(&svg[..root_end]);
+    out.push_str(" role=\"document\" aria-labelledby=\"a11y-title a11y-desc\">");
+    let subtitle = options.subtitle.as_deref().unwrap_or("Flame graph");
+    out.push_str(&format!(
+        "<title id=\"a11y-title\">{}</title><desc id=\"a11y-desc\">{}. Use Tab to move between frames and Enter to zoom into one.</desc>",
+        html::escape(&options.title),
+        html::escape(subtitle)
+    ));
+    out.push_str(ACCESSIBILITY_SCRIPT);
+
+    let mut rest = &svg[root_end + 1..];
+    while let Some(start) = rest.find("<g><title>") {
+        out.push_str(&rest[..start]);
+        rest = &rest[start + "<g><title>".len()..];
+        let Some(title_end) = rest.find("</title>") else {
+            out.push_str("<g><title>");
+            continue;
+        };
+        let title = &rest[..title_end];
+        let count = rest[title_end..]
+            .split_once("fg:w=\"")
+            .and_then(|(_, after)| after.split_once('"'))
+            .map(|(count, _)| count);
+        out.push_str(&format!(
+            "<g tabindex=\"0\" role=\"img\" aria-label=\"{}\"><title>{title}</title>",
+            title.replace('"', "&quot;")
+        ));
+        if let Some(count) = count {
+            out.push_str(&format!("<desc>{count} {unit}</desc>"));
+        }
+        rest = &rest[title_end + "</title>".len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Keyboard support for the frames, built on top of the functions of inferno's script.
+const ACCESSIBILITY_SCRIPT: &str = r#"<style type="text/css">#frames > *:focus { stroke:black; stroke-width:1; outline:none; }</style><script type="text/ecmascript"><![CDATA[
+window.addEventListener("keydown", function(e) {
+    if (e.key !== "Enter" && e.key !== " ") return;
+    var target = find_group(e.target);
+    if (!target) return;
+    e.preventDefault();
+    target.dispatchEvent(new MouseEvent("click", { bubbles: true }));
+}, false)
+window.addEventListener("focusin", function(e) {
+    var target = find_group(e.target);
+    if (target) details.nodeValue = nametype + " " + g_to_text(target);
+}, false)
+]]></script>"#;
@@ -0,0 +1,54 @@
+use regex::Regex;
+
+/// Compiles shell-style glob patterns (`*` matches anything, including `/`, and `?` matches one
+/// character) into a single regex matching any of them.
+pub fn matcher(patterns: &[String]) -> anyhow::Result<Regex> {
+    let alternatives: Vec<_> = patterns
+        .iter()
+        .map(|pattern| {
+            let mut regex = String::new();
+            for c in pattern.chars() {
+                match c {
+                    '*' => regex.push_str(".*"),
+                    '?' => regex.push('.'),
+                    c => regex.push_str(&regex::escape(&c.to_string())),
+                }
+            }
+            format!("(?:{regex})")
+        })
+        .collect();
+    Ok(Regex::new(&format!("^(?:{})$", alternatives.join("|")))?)
+}
+
+/// Which source files to keep in the breakdown, by glob patterns of their paths.
+#[derive(Clone, Debug, Default)]
+pub struct PathFilter {
+    include: Option<Regex>,
+    exclude: Option<Regex>,
+}
+
+impl PathFilter {
+    /// Keeps the files matching one of `include` (all files if it's empty) but none of `exclude`.
+    pub fn new(include: &[String], exclude: &[String]) -> anyhow::Result<PathFilter> {
+        let compile = |patterns: &[String]| match patterns.is_empty() {
+            true => Ok(None),
+            false => matcher(patterns).map(Some),
+        };
+        Ok(PathFilter {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    /// Whether to keep code from `path`. Code without a source file is only kept without include
+    /// patterns.
+    pub fn keeps(&self, path: Option<&str>) -> bool {
+        match path {
+            Some(path) => {
+                self.include.as_ref().is_none_or(|r| r.is_match(path))
+                    && !self.exclude.as_ref().is_some_and(|r| r.is_match(path))
+            }
+            None => self.include.is_none(),
+        }
+    }
+}
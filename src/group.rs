@@ -0,0 +1,793 @@
+use std::collections::{BTreeMap, HashMap};
+
+use clap::ValueEnum;
+
+use crate::{
+    artificial, data, escape_frame, explain, libc, tinygo, tree::Instantiation, Contributors,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    /// Group by source directories and files.
+    #[default]
+    Files,
+    /// Group by the namespace/class path of the demangled function name.
+    Namespace,
+    /// Group by the Rust crate (with its version, if known) of the source file.
+    Crate,
+}
+
+/// Prefix of the frames of crates when grouping by crate.
+pub const CRATE_PREFIX: &str = "crate: ";
+
+/// C++ operators whose names contain brackets, longest first. They neither open nor close
+/// anything.
+const BRACKET_OPERATORS: [&str; 13] = [
+    "<<=", ">>=", "<=>", "->*", "<<", ">>", "<=", ">=", "->", "()", "[]", "<", ">",
+];
+
+/// Prefixes of the names the Itanium demangler gives special symbols, like
+/// `non-virtual thunk to Widget::draw()`.
+const SPECIAL_PREFIXES: [&str; 9] = [
+    "non-virtual thunk to ",
+    "virtual thunk to ",
+    "covariant return thunk to ",
+    "guard variable for ",
+    "construction vtable for ",
+    "vtable for ",
+    "typeinfo name for ",
+    "typeinfo for ",
+    "VTT for ",
+];
+
+/// Splits a demangled, qualified function name like
+/// `Eigen::internal::foo<int, 3>::bar(int) const` into its path components
+/// (`["Eigen", "internal", "foo<int, 3>", "bar"]`).
+///
+/// `::` inside template arguments, parameter lists or Rust's `<T as Trait>`
+/// qualifiers does not split the name. The return type of function templates,
+/// parameter lists and trailing qualifiers of the last component are dropped.
+/// The prefix of special symbols like thunks stays with the last component.
+/// Go names are split into package path, receiver type and function.
+pub fn namespace_path(name: &str) -> Vec<String> {
+    if let Some(path) = tinygo::go_path(name) {
+        return path;
+    }
+    let special = SPECIAL_PREFIXES
+        .iter()
+        .find_map(|prefix| Some((*prefix, name.strip_prefix(prefix)?)));
+    if let Some((prefix, name)) = special {
+        let mut path = namespace_path(name);
+        if let Some(last) = path.last_mut() {
+            last.insert_str(0, prefix);
+        }
+        return path;
+    }
+    let mut components = vec![];
+    let mut depth = 0usize;
+    let mut current = String::new();
+    let mut rest = name;
+    while let Some(c) = rest.chars().next() {
+        if current.trim_start() == "operator" {
+            if let Some(operator) = BRACKET_OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+                current.push_str(operator);
+                rest = &rest[operator.len()..];
+                continue;
+            }
+        }
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '<' | '[' | '{' => depth += 1,
+            // The `>` of `->`, like in `decltype(p->x)` or Rust's `fn() -> T`, closes nothing.
+            '>' if current.ends_with('-') => {}
+            '>' | ']' | '}' => depth = depth.saturating_sub(1),
+            // Everything after the top-level parameter list (`const`, `&&`, ...) is not part of the
+            // path. `decltype (...)` in a return type is no parameter list.
+            '(' if depth == 0 && !current.trim().is_empty() && !current.ends_with(' ') => break,
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ':' if depth == 0 && rest.starts_with(':') => {
+                rest = &rest[1..];
+                components.push(std::mem::take(&mut current));
+                continue;
+            }
+            // A name after a space, like in `void ns::f<int>(int)`, follows the return type of a
+            // function template.
+            ' ' if depth == 0
+                && !current.trim_start().starts_with("operator")
+                && rest.starts_with(|c: char| c.is_alphabetic() || c == '_') =>
+            {
+                components.clear();
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    components.push(current);
+    components
+        .into_iter()
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+/// The frames of a contributor's `path` below its section or segment, and below the [`data::FRAME`]
+/// and [`artificial::FRAME`] that code and data of any crate can be under: where its crate or source
+/// path starts. Other synthetic frames, like [`libc::FRAME`], are crates of their own.
+pub fn source_frames<'a, 'b>(path: &'b [&'a str]) -> &'b [&'a str] {
+    let start = path
+        .iter()
+        .position(|frame| {
+            !(frame.starts_with("@section: ")
+                || frame.starts_with("@segment: ")
+                || [data::FRAME, artificial::FRAME].contains(frame))
+        })
+        .unwrap_or(path.len());
+    &path[start..]
+}
+
+/// Returns the Rust crate a source file belongs to, with its version if the path contains it
+/// (e.g. `serde 1.0.188`).
+///
+/// Recognizes cargo registry and git checkouts, the Rust standard library in the sysroot and its
+/// vendored dependencies, files generated into a build script's `OUT_DIR` (see
+/// [`generated_by_crate`]), and `<crate>/src/...` for workspace crates. Paths below [`libc::FRAME`] are attributed to the C
+/// runtime as a whole.
+pub fn crate_for_path(path: &str) -> Option<String> {
+    let components: Vec<_> = path.split('/').filter(|c| !c.is_empty()).collect();
+    if components.first() == Some(&libc::FRAME) {
+        return Some(libc::FRAME.to_string());
+    }
+    let after = |marker: &[&str]| {
+        components
+            .windows(marker.len())
+            .position(|w| w == marker)
+            .and_then(|i| components.get(i + marker.len()).copied())
+    };
+
+    // ~/.cargo/registry/src/<index>/<name>-<version>/...
+    if let Some(index) = components.windows(2).position(|w| w == ["registry", "src"]) {
+        if let Some(dir) = components.get(index + 3) {
+            return Some(match split_version(dir) {
+                Some((name, version)) => format!("{name} {version}"),
+                None => dir.to_string(),
+            });
+        }
+    }
+    // ~/.cargo/git/checkouts/<name>-<hash>/<rev>/...
+    if let Some(dir) = after(&["git", "checkouts"]) {
+        return Some(
+            dir.rsplit_once('-')
+                .map_or(dir, |(name, _)| name)
+                .to_string(),
+        );
+    }
+    // The standard library's own dependencies: /rust/deps/<name>-<version>/...
+    if let Some(dir) = after(&["rust", "deps"]) {
+        if let Some((name, version)) = split_version(dir) {
+            return Some(format!("{name} {version}"));
+        }
+    }
+    // /rustc/<hash>/library/<crate>/... or <sysroot>/lib/rustlib/src/rust/library/<crate>/...
+    if components.contains(&"rustc") || components.contains(&"rustlib") {
+        if let Some(name) = after(&["library"]) {
+            return Some(name.to_string());
+        }
+    }
+    if let Some(name) = generated_by_crate(path) {
+        return Some(name.to_string());
+    }
+    // <workspace>/<crate>/src/...
+    let src = components.iter().rposition(|&c| c == "src")?;
+    components
+        .get(src.checked_sub(1)?)
+        .map(|name| name.to_string())
+}
+
+/// Frame below a crate's frame for the code of the files generated into its `OUT_DIR`.
+pub const GENERATED: &str = "@generated";
+
+/// Returns the crate whose build script generated the file at `path` into its `OUT_DIR`, like
+/// `foo` for `target/release/build/foo-1a2b3c4d5e6f7a8b/out/bindings.rs`. The directory's hash
+/// changes with every build configuration, so it's left out. Files that proc macros include from
+/// there are recognized the same way.
+pub fn generated_by_crate(path: &str) -> Option<&str> {
+    let components: Vec<_> = path.split('/').filter(|c| !c.is_empty()).collect();
+    components.windows(3).rev().find_map(|w| {
+        let [build, dir, out] = w else {
+            return None;
+        };
+        if *build != "build" || *out != "out" {
+            return None;
+        }
+        let (name, hash) = dir.rsplit_once('-')?;
+        let is_hash = hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit());
+        (is_hash && !name.is_empty()).then_some(name)
+    })
+}
+
+/// Returns the crate of a demangled function name without a known source file: the first
+/// component of its path, like `core` for `core::fmt::write`. Methods of `<Type as Trait>`
+/// belong to the crate of the type if it has a path, otherwise to that of the trait.
+pub fn crate_for_symbol(name: &str) -> Option<String> {
+    let path = namespace_path(&explain::strip_disambiguators(name));
+    if path.len() < 2 {
+        return None;
+    }
+    match path[0].strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+        Some(qualified) => {
+            let (ty, r#trait) = qualified.split_once(" as ").unwrap_or((qualified, ""));
+            crate_for_symbol(ty).or_else(|| crate_for_symbol(r#trait))
+        }
+        None => Some(path[0].clone()),
+    }
+}
+
+/// Removes the generic arguments from a (demangled) name, like `<T>` in `Vec<T>::push` or
+/// `::<u8>` in `mem::size_of::<u8>`, and returns them separately, split at top-level commas.
+///
+/// Only `<` right after a name or `::` opens generic arguments. Qualified paths like
+/// `<T as Trait>::f` and the comparison and shift operators of C++ are kept.
+pub fn collapse_generics(name: &str) -> (String, Vec<String>) {
+    let mut collapsed = String::with_capacity(name.len());
+    let mut args = vec![];
+    let mut chars = name.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let before = &name[..index];
+        let opens = c == '<'
+            && !before.ends_with("operator")
+            && !before.ends_with("operator<")
+            && (before.ends_with("::")
+                || before.ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '>'));
+        if !opens {
+            collapsed.push(c);
+            continue;
+        }
+        let mut depth = 1;
+        let mut current = String::new();
+        for (_, c) in chars.by_ref() {
+            match c {
+                '<' | '(' | '[' => depth += 1,
+                // The `>` of `->`, like in `Box<dyn Fn() -> u32>`, closes nothing.
+                '>' if current.ends_with('-') => {}
+                '>' | ')' | ']' => depth -= 1,
+                ',' if depth == 1 => {
+                    args.push(std::mem::take(&mut current).trim().to_string());
+                    continue;
+                }
+                _ => {}
+            }
+            if depth == 0 {
+                break;
+            }
+            current.push(c);
+        }
+        args.push(current.trim().to_string());
+        // `mem::size_of::<u8>` becomes `mem::size_of`.
+        if let Some(stripped) = collapsed.strip_suffix("::") {
+            collapsed.truncate(stripped.len());
+        }
+    }
+    (collapsed, args)
+}
+
+/// Collapses the generic arguments of every frame with [`collapse_generics`], adding up the
+/// sizes of all instantiations of the same generic code.
+///
+/// Returns the collapsed contributors and, for every collapsed node (by its `;`-separated path),
+/// the instantiations it stands for: their generic arguments and their sizes.
+pub fn collapse_generic_frames(
+    contributors: &Contributors,
+) -> (Contributors, HashMap<String, Vec<Instantiation>>) {
+    let mut collapsed = Contributors::new();
+    let mut instantiations: HashMap<String, BTreeMap<Vec<String>, u64>> = HashMap::new();
+    for (key, &size) in contributors {
+        let mut path = String::new();
+        for frame in key.split(';') {
+            let (name, args) = collapse_generics(frame);
+            if !path.is_empty() {
+                path.push(';');
+            }
+            path.push_str(&name);
+            if !args.is_empty() {
+                *instantiations
+                    .entry(path.clone())
+                    .or_default()
+                    .entry(args)
+                    .or_default() += size;
+            }
+        }
+        *collapsed.entry(path).or_default() += size;
+    }
+    let instantiations = instantiations
+        .into_iter()
+        .map(|(path, sizes)| (path, sorted_instantiations(sizes)))
+        .collect();
+    (collapsed, instantiations)
+}
+
+/// The instantiations with these sizes by generic arguments, largest first.
+fn sorted_instantiations(sizes: BTreeMap<Vec<String>, u64>) -> Vec<Instantiation> {
+    let mut list: Vec<_> = sizes
+        .into_iter()
+        .map(|(args, size)| Instantiation { args, size })
+        .collect();
+    list.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.args.cmp(&b.args)));
+    list
+}
+
+/// Frame that groups vendored C libraries in the breakdown.
+pub const C_LIBRARIES: &str = "@c-libraries";
+
+/// Well-known C libraries, with the prefixes of the directory (or file) names they are built from.
+const C_LIBRARY_NAMES: &[(&str, &[&str])] = &[
+    ("zlib", &["zlib"]),
+    ("libpng", &["libpng", "lpng"]),
+    ("libjpeg", &["libjpeg", "jpeg-"]),
+    ("libwebp", &["libwebp"]),
+    ("sqlite", &["sqlite"]),
+    ("icu", &["icu4c", "icu"]),
+    ("freetype", &["freetype"]),
+    ("harfbuzz", &["harfbuzz"]),
+    ("brotli", &["brotli"]),
+    ("zstd", &["zstd"]),
+];
+
+/// File extensions of C and C++ sources and headers.
+const C_EXTENSIONS: &[&str] = &["c", "h", "cc", "cpp", "cxx", "hh", "hpp", "hxx", "inc", "S"];
+
+/// Returns the well-known C library a C/C++ source file belongs to, and the components of the
+/// path within the library.
+///
+/// Libraries are recognized by a path component starting with their name, optionally followed by a
+/// version or other suffix (`zlib-1.3`, `libpng16`, `sqlite-amalgamation-3450000`, `sqlite3.c`),
+/// so odd build directory layouts don't matter.
+pub fn c_library_for_path(path: &str) -> Option<(&'static str, Vec<&str>)> {
+    let extension = path.rsplit_once('.')?.1;
+    if !C_EXTENSIONS.contains(&extension) {
+        return None;
+    }
+    let components: Vec<_> = path.split('/').filter(|c| !c.is_empty()).collect();
+    for (index, component) in components.iter().enumerate() {
+        let lowercase = component.to_ascii_lowercase();
+        for &(name, prefixes) in C_LIBRARY_NAMES {
+            let matches = prefixes.iter().any(|prefix| {
+                lowercase
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| !rest.starts_with(|c: char| c.is_ascii_alphabetic()))
+            });
+            if !matches {
+                continue;
+            }
+            let within = match &components[index + 1..] {
+                [] => vec![*component],
+                rest => rest.to_vec(),
+            };
+            return Some((name, within));
+        }
+    }
+    None
+}
+
+/// Frame that groups assembly sources in the breakdown.
+pub const ASSEMBLY: &str = "@assembly";
+
+/// File extensions of assembly sources.
+const ASSEMBLY_EXTENSIONS: &[&str] = &["s", "S", "asm"];
+
+/// Whether a source file is hand-written or generated assembly. Its directories rarely mean
+/// anything, so it is shown as a whole under [`ASSEMBLY`].
+pub fn is_assembly(path: &str) -> bool {
+    path.rsplit_once('.')
+        .is_some_and(|(_, extension)| ASSEMBLY_EXTENSIONS.contains(&extension))
+}
+
+/// Frame for source files that are below none of the roots given to [`root_path`].
+pub const OTHER_ROOT: &str = "other";
+
+/// Splits a source path into the first of `roots` (like `src` or `third_party/zlib`) it lies in
+/// and the components of the path within that root. Whatever comes before the root, such as the
+/// checkout directory of the build machine, is dropped. Paths below no root are returned whole
+/// under [`OTHER_ROOT`].
+pub fn root_path<'a>(path: &'a str, roots: &'a [String]) -> (&'a str, Vec<&'a str>) {
+    let components: Vec<_> = path.split('/').filter(|c| !c.is_empty()).collect();
+    let matched = roots
+        .iter()
+        .filter_map(|root| {
+            let root_components: Vec<_> = root.split('/').filter(|c| !c.is_empty()).collect();
+            if root_components.is_empty() {
+                return None;
+            }
+            let index = components
+                .windows(root_components.len())
+                .position(|w| w == root_components)?;
+            Some((index, index + root_components.len(), root.trim_matches('/')))
+        })
+        // The outermost root wins, so a `src` within a vendored library stays in `vendor`. Of
+        // roots starting at the same directory, the longest wins.
+        .min_by_key(|&(index, end, _)| (index, std::cmp::Reverse(end)));
+    match matched {
+        Some((_, end, root)) => (root, components[end..].to_vec()),
+        None => (OTHER_ROOT, components),
+    }
+}
+
+/// Puts every frame path of `paths`, like [`Contributors`] or the instantiations of
+/// [`collapse_generic_frames`], below a `root` frame. Breakdowns of several modules or tools can
+/// then be combined under distinct roots, e.g. by concatenating their folded stacks.
+pub fn under_root<T>(paths: HashMap<String, T>, root: &str) -> HashMap<String, T> {
+    let root = escape_frame(root);
+    paths
+        .into_iter()
+        .map(|(key, value)| (format!("{root};{key}"), value))
+        .collect()
+}
+
+/// Frame that [`fold_small`] gathers small contributors in.
+pub const OTHER_FRAME: &str = "@other";
+
+/// Folds every node of the breakdown smaller than `min_size` bytes (with everything below it) into
+/// an [`OTHER_FRAME`] of its parent, so that large modules don't turn into thousands of frames too
+/// narrow to see. Nodes are folded as high up as possible, and the total size stays the same.
+pub fn fold_small(contributors: &Contributors, min_size: u64) -> Contributors {
+    Folding::new(contributors, min_size).contributors(contributors)
+}
+
+/// Where [`fold_small`] moves the nodes of a breakdown. Decided once by the sizes of the
+/// breakdown, it folds anything keyed by the same paths alike, like the instantiations of
+/// [`collapse_generic_frames`] or the estimates of [`crate::compressed::Uncertainty`].
+#[derive(Clone, Debug)]
+pub struct Folding {
+    /// Size of every node, by its `;`-separated path.
+    sizes: HashMap<String, u64>,
+    min_size: u64,
+}
+
+impl Folding {
+    pub fn new(contributors: &Contributors, min_size: u64) -> Folding {
+        let mut sizes: HashMap<String, u64> = HashMap::new();
+        if min_size > 0 {
+            for (key, &size) in contributors {
+                for (end, _) in key.match_indices(';').chain([(key.len(), "")]) {
+                    *sizes.entry(key[..end].to_string()).or_default() += size;
+                }
+            }
+        }
+        Folding { sizes, min_size }
+    }
+
+    /// The path the node at `key` ends up at: `key` itself, or the [`OTHER_FRAME`] of the parent of
+    /// its outermost small node.
+    pub fn path(&self, key: &str) -> String {
+        let small = key
+            .match_indices(';')
+            .chain([(key.len(), "")])
+            .map(|(end, _)| end)
+            .find(|end| {
+                self.sizes
+                    .get(&key[..*end])
+                    .is_some_and(|&size| size < self.min_size)
+            });
+        match small {
+            Some(end) => match key[..end].rfind(';') {
+                Some(parent) => format!("{};{OTHER_FRAME}", &key[..parent]),
+                None => OTHER_FRAME.to_string(),
+            },
+            None => key.to_string(),
+        }
+    }
+
+    pub fn contributors(&self, contributors: &Contributors) -> Contributors {
+        let mut folded = Contributors::new();
+        for (key, &size) in contributors {
+            *folded.entry(self.path(key)).or_default() += size;
+        }
+        folded
+    }
+
+    /// Folds the instantiations of collapsed nodes. Those that end up in the same
+    /// [`OTHER_FRAME`] are merged, adding up the sizes of the same generic arguments.
+    pub fn instantiations(
+        &self,
+        instantiations: HashMap<String, Vec<Instantiation>>,
+    ) -> HashMap<String, Vec<Instantiation>> {
+        let mut folded: HashMap<String, BTreeMap<Vec<String>, u64>> = HashMap::new();
+        for (key, list) in instantiations {
+            let sizes = folded.entry(self.path(&key)).or_default();
+            for instantiation in list {
+                *sizes.entry(instantiation.args).or_default() += instantiation.size;
+            }
+        }
+        folded
+            .into_iter()
+            .map(|(path, sizes)| (path, sorted_instantiations(sizes)))
+            .collect()
+    }
+}
+
+/// Splits a `<name>-<version>` directory name. The version starts at the first `-` followed by a
+/// digit, as crate names can contain dashes themselves.
+fn split_version(dir: &str) -> Option<(&str, &str)> {
+    let index = dir
+        .match_indices('-')
+        .map(|(i, _)| i)
+        .find(|&i| dir[i + 1..].starts_with(|c: char| c.is_ascii_digit()))?;
+    Some((&dir[..index], &dir[index + 1..]))
+}
+
+/// Makes the paths of one file compare equal across toolchains: `\` separators (from Windows
+/// builds) become `/`, drive letters are upper case and verbatim `\\?\` prefixes are dropped, so
+/// `C:\src\lib.rs` is shown as `C:;src;lib.rs` and a UNC path as `server;share;...`. If paths are
+/// case-insensitive, every path component is spelled the way it was first seen in its directory.
+#[derive(Clone, Debug, Default)]
+pub struct PathNormalizer {
+    case_insensitive: bool,
+    /// First spelling of every path prefix seen, keyed by the lowercased prefix.
+    spellings: HashMap<String, String>,
+}
+
+impl PathNormalizer {
+    pub fn new(case_insensitive: bool) -> PathNormalizer {
+        PathNormalizer {
+            case_insensitive,
+            spellings: HashMap::new(),
+        }
+    }
+
+    pub fn normalize(&mut self, path: &str) -> String {
+        let mut path = path.replace('\\', "/");
+        // Verbatim paths (`\\?\C:\...`, `\\?\UNC\server\share\...`) are ordinary ones with a
+        // prefix.
+        if let Some(rest) = path.strip_prefix("//?/UNC/") {
+            path = format!("//{rest}");
+        } else if let Some(rest) = path.strip_prefix("//?/") {
+            path = rest.to_string();
+        }
+        // Drive letters are case-insensitive, but tools spell them either way.
+        if path.as_bytes().get(1) == Some(&b':') && path.as_bytes()[0].is_ascii_lowercase() {
+            path[..1].make_ascii_uppercase();
+        }
+        if !self.case_insensitive {
+            return path;
+        }
+        let mut prefix = String::new();
+        let components: Vec<_> = path
+            .split('/')
+            .map(|component| {
+                prefix.push('/');
+                prefix.push_str(&component.to_lowercase());
+                self.spellings
+                    .entry(prefix.clone())
+                    .or_insert_with(|| component.to_string())
+                    .clone()
+            })
+            .collect();
+        components.join("/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_namespaces() {
+        assert_eq!(
+            namespace_path("Eigen::internal::foo<int, 3>::bar(int) const"),
+            ["Eigen", "internal", "foo<int, 3>", "bar"]
+        );
+        assert_eq!(
+            namespace_path("<alloc::string::String as core::fmt::Display>::fmt"),
+            ["<alloc::string::String as core::fmt::Display>", "fmt"]
+        );
+        assert_eq!(
+            namespace_path("(anonymous namespace)::Parser::parse()"),
+            ["(anonymous namespace)", "Parser", "parse"]
+        );
+    }
+
+    #[test]
+    fn splits_operators() {
+        // std::ostream::operator<<(int)
+        assert_eq!(
+            namespace_path("std::basic_ostream<char, std::char_traits<char> >::operator<<(int)"),
+            [
+                "std",
+                "basic_ostream<char, std::char_traits<char> >",
+                "operator<<"
+            ]
+        );
+        // Foo::operator<(Foo const&)
+        assert_eq!(
+            namespace_path("Foo::operator<(Foo const&)"),
+            ["Foo", "operator<"]
+        );
+        // Foo::operator>>(int)
+        assert_eq!(
+            namespace_path("Foo::operator>>(int)"),
+            ["Foo", "operator>>"]
+        );
+        // std::unique_ptr<Foo>::operator->()
+        assert_eq!(
+            namespace_path("std::unique_ptr<Foo, std::default_delete<Foo> >::operator->()"),
+            [
+                "std",
+                "unique_ptr<Foo, std::default_delete<Foo> >",
+                "operator->"
+            ]
+        );
+        // std::function<int (int)>::operator()(int) const
+        assert_eq!(
+            namespace_path("std::function<int (int)>::operator()(int) const"),
+            ["std", "function<int (int)>", "operator()"]
+        );
+        // std::vector<int>::operator[](unsigned long) const
+        assert_eq!(
+            namespace_path(
+                "std::vector<int, std::allocator<int> >::operator[](unsigned long) const"
+            ),
+            ["std", "vector<int, std::allocator<int> >", "operator[]"]
+        );
+        assert_eq!(
+            namespace_path("Foo::operator bool() const"),
+            ["Foo", "operator bool"]
+        );
+    }
+
+    #[test]
+    fn keeps_special_prefixes_with_the_name() {
+        assert_eq!(
+            namespace_path("non-virtual thunk to Widget::draw()"),
+            ["Widget", "non-virtual thunk to draw"]
+        );
+        assert_eq!(
+            namespace_path("guard variable for ns::instance()::cache"),
+            ["ns", "guard variable for instance"]
+        );
+    }
+
+    #[test]
+    fn drops_return_types() {
+        // std::operator<< <std::char_traits<char> >(std::ostream&, char const*)
+        assert_eq!(
+            namespace_path("std::basic_ostream<char, std::char_traits<char> >& std::operator<< <std::char_traits<char> >(std::basic_ostream<char, std::char_traits<char> >&, char const*)"),
+            ["std", "operator<< <std::char_traits<char> >"]
+        );
+        assert_eq!(
+            namespace_path("std::__1::basic_ostream<char, std::__1::char_traits<char> >& std::__1::operator<< <std::__1::char_traits<char> >(std::__1::basic_ostream<char, std::__1::char_traits<char> >&, char const*)"),
+            ["std", "__1", "operator<< <std::__1::char_traits<char> >"]
+        );
+        assert_eq!(
+            namespace_path("void ns::f<int (*)()>(int (*)())"),
+            ["ns", "f<int (*)()>"]
+        );
+        assert_eq!(namespace_path("decltype ({parm#1}->x) f<A>(A)"), ["f<A>"]);
+    }
+
+    #[test]
+    fn arrows_in_template_arguments() {
+        assert_eq!(
+            namespace_path("ns::apply<decltype ({parm#1}->x)>::run()"),
+            ["ns", "apply<decltype ({parm#1}->x)>", "run"]
+        );
+        assert_eq!(
+            namespace_path("core::ops::function::FnOnce::call_once<fn() -> u32, ()>"),
+            [
+                "core",
+                "ops",
+                "function",
+                "FnOnce",
+                "call_once<fn() -> u32, ()>"
+            ]
+        );
+    }
+
+    #[test]
+    fn collapses_generic_arguments() {
+        assert_eq!(
+            collapse_generics("alloc::vec::Vec<T,A>::push"),
+            (
+                "alloc::vec::Vec::push".to_string(),
+                vec!["T".to_string(), "A".to_string()]
+            )
+        );
+        assert_eq!(
+            collapse_generics("core::mem::size_of::<u8>"),
+            ("core::mem::size_of".to_string(), vec!["u8".to_string()])
+        );
+    }
+
+    #[test]
+    fn keeps_qualified_paths_and_operators() {
+        let name = "<alloc::string::String as core::fmt::Display>::fmt";
+        assert_eq!(collapse_generics(name), (name.to_string(), vec![]));
+        let name = "Foo::operator<<(int)";
+        assert_eq!(collapse_generics(name), (name.to_string(), vec![]));
+        let name = "Foo::operator<(Foo const&)";
+        assert_eq!(collapse_generics(name), (name.to_string(), vec![]));
+    }
+
+    #[test]
+    fn arrows_in_closure_generics() {
+        assert_eq!(
+            collapse_generics("alloc::boxed::Box<dyn Fn() -> u32>::new"),
+            (
+                "alloc::boxed::Box::new".to_string(),
+                vec!["dyn Fn() -> u32".to_string()]
+            )
+        );
+        assert_eq!(
+            collapse_generics(
+                "core::ptr::drop_in_place<alloc::boxed::Box<dyn core::ops::FnMut(&str) -> bool>>"
+            ),
+            (
+                "core::ptr::drop_in_place".to_string(),
+                vec!["alloc::boxed::Box<dyn core::ops::FnMut(&str) -> bool>".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn arrows_in_fn_pointer_generics() {
+        assert_eq!(
+            collapse_generics(
+                "std::sys::backtrace::__rust_begin_short_backtrace<fn() -> core::result::Result<(), std::io::error::Error>, core::result::Result<(), std::io::error::Error>>"
+            ),
+            (
+                "std::sys::backtrace::__rust_begin_short_backtrace".to_string(),
+                vec![
+                    "fn() -> core::result::Result<(), std::io::error::Error>".to_string(),
+                    "core::result::Result<(), std::io::error::Error>".to_string(),
+                ]
+            )
+        );
+        assert_eq!(
+            collapse_generics("std::rt::lang_start::<fn()>::{{closure}}"),
+            (
+                "std::rt::lang_start::{{closure}}".to_string(),
+                vec!["fn()".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn folds_instantiations_like_their_nodes() {
+        let contributors = Contributors::from([
+            ("code;big".to_string(), 100),
+            ("code;small;a".to_string(), 3),
+            ("code;tiny".to_string(), 2),
+        ]);
+        let folding = Folding::new(&contributors, 10);
+        assert_eq!(
+            folding.contributors(&contributors),
+            Contributors::from([
+                ("code;big".to_string(), 100),
+                ("code;@other".to_string(), 5),
+            ])
+        );
+        let instantiation = |arg: &str, size| Instantiation {
+            args: vec![arg.to_string()],
+            size,
+        };
+        let instantiations = HashMap::from([
+            ("code;big".to_string(), vec![instantiation("u8", 100)]),
+            (
+                "code;small".to_string(),
+                vec![instantiation("u8", 1), instantiation("u16", 2)],
+            ),
+            ("code;tiny".to_string(), vec![instantiation("u16", 2)]),
+        ]);
+        assert_eq!(
+            folding.instantiations(instantiations),
+            HashMap::from([
+                ("code;big".to_string(), vec![instantiation("u8", 100)]),
+                (
+                    "code;@other".to_string(),
+                    vec![instantiation("u16", 4), instantiation("u8", 1)]
+                ),
+            ])
+        );
+    }
+}
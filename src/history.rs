@@ -0,0 +1,107 @@
+//! Sizes of a module over time, read from the JSON breakdowns (`--format json`) of its past builds.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+
+use crate::{diff, json::Breakdown, Contributors};
+
+/// The breakdown of one past build.
+pub struct Snapshot {
+    pub path: PathBuf,
+    /// The file name without its extension, e.g. a date or a version.
+    pub name: String,
+    pub total: u64,
+    /// Sizes of the top-level nodes of the breakdown, usually the sections.
+    pub sections: BTreeMap<String, u64>,
+    /// Other reports on the same build next to the breakdown: files with the same name and an
+    /// `.html` or `.svg` extension.
+    pub reports: Vec<PathBuf>,
+    pub contributors: Contributors,
+}
+
+/// A file or function whose size changed between two consecutive snapshots.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mover {
+    pub kind: &'static str,
+    pub name: String,
+    pub old: u64,
+    pub new: u64,
+}
+
+impl Mover {
+    pub fn delta(&self) -> i64 {
+        self.new as i64 - self.old as i64
+    }
+}
+
+/// Reads the breakdowns at `paths`, which are expected in chronological order.
+pub fn load(paths: &[PathBuf]) -> anyhow::Result<Vec<Snapshot>> {
+    paths
+        .iter()
+        .map(|path| load_one(path).with_context(|| format!("Reading {}", path.display())))
+        .collect()
+}
+
+fn load_one(path: &Path) -> anyhow::Result<Snapshot> {
+    let json = std::fs::read_to_string(path)?;
+    let breakdown: Breakdown = serde_json::from_str(&json)?;
+    let reports = ["html", "svg"]
+        .into_iter()
+        .map(|extension| path.with_extension(extension))
+        .filter(|report| report.is_file())
+        .collect();
+    Ok(Snapshot {
+        path: path.to_path_buf(),
+        name: path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string()),
+        total: breakdown.tree.size,
+        sections: breakdown
+            .tree
+            .children
+            .iter()
+            .map(|child| (child.name.clone(), child.size))
+            .collect(),
+        reports,
+        contributors: breakdown.tree.to_contributors(),
+    })
+}
+
+/// The `limit` files and functions whose size changed the most from `old` to `new`, by the
+/// absolute change. Files and functions are named like in [`diff::SizeDiff`].
+pub fn movers(old: &Snapshot, new: &Snapshot, limit: usize) -> Vec<Mover> {
+    let mut movers = vec![];
+    for (kind, name_of) in [
+        ("file", diff::file as fn(&str) -> Option<String>),
+        ("function", diff::function),
+    ] {
+        let (old, new) = (
+            diff::by(&old.contributors, name_of),
+            diff::by(&new.contributors, name_of),
+        );
+        let names: BTreeSet<_> = old.keys().chain(new.keys()).collect();
+        movers.extend(names.into_iter().filter_map(|name| {
+            let size = |sizes: &BTreeMap<String, u64>| sizes.get(name).copied().unwrap_or(0);
+            let mover = Mover {
+                kind,
+                name: name.clone(),
+                old: size(&old),
+                new: size(&new),
+            };
+            (mover.delta() != 0).then_some(mover)
+        }));
+    }
+    movers.sort_by(|a, b| {
+        b.delta()
+            .unsigned_abs()
+            .cmp(&a.delta().unsigned_abs())
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    movers.truncate(limit);
+    movers
+}
@@ -0,0 +1,690 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write as _,
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::Context;
+
+use crate::{
+    batch::BatchEntry,
+    disasm::FunctionCode,
+    flamegraph, group,
+    history::{self, Snapshot},
+    output::{self, AtomicFile},
+    Contributors, SizeTree,
+};
+
+const STYLE: &str = "
+body { font-family: system-ui, sans-serif; margin: 2rem auto; max-width: 1200px; color: #1f2328; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 2rem; }
+th, td { text-align: left; padding: 0.25rem 0.5rem; border-bottom: 1px solid #d0d7de; }
+td.num, th.num { text-align: right; font-variant-numeric: tabular-nums; white-space: nowrap; }
+.name { word-break: break-all; font-weight: normal; }
+object { width: 100%; }
+";
+
+const VIEWER_STYLE: &str = "
+#graph { position: relative; overflow: hidden; margin: 1rem 0 2rem; }
+.frame { position: absolute; box-sizing: border-box; height: 19px; padding: 0 4px; overflow: hidden;
+  font: 12px monospace; line-height: 19px; white-space: nowrap; text-overflow: ellipsis; cursor: pointer;
+  border-right: 1px solid #fff; }
+.frame:hover, .frame:focus { outline: 1px solid #1f2328; }
+.frame.match { background: #e600e6 !important; color: #fff; }
+#search { width: 20rem; }
+th button { all: inherit; cursor: pointer; }
+th[aria-sort=\"descending\"] button::after { content: \" \\25BE\"; }
+th[aria-sort=\"ascending\"] button::after { content: \" \\25B4\"; }
+#code pre { max-height: 30rem; overflow: auto; padding: 0.5rem; background: #f6f8fa; font-size: 12px; }
+";
+
+const VIEWER_SCRIPT: &str = include_str!("viewer.js");
+
+const TREEMAP_STYLE: &str = "
+#treemap { position: relative; height: 70vh; min-height: 400px; margin: 1rem 0; }
+.node { position: absolute; box-sizing: border-box; overflow: hidden; border: 1px solid #fff;
+  font: 12px monospace; line-height: 16px; cursor: pointer; }
+.node > span { display: block; padding: 1px 4px; white-space: nowrap; overflow: hidden; text-overflow: ellipsis; }
+.node:hover, .node:focus { outline: 1px solid #1f2328; }
+#tooltip { position: absolute; z-index: 1; padding: 0.25rem 0.5rem; background: #1f2328; color: #fff;
+  font: 12px monospace; white-space: pre; pointer-events: none; }
+";
+
+const TREEMAP_SCRIPT: &str = include_str!("treemap.js");
+
+const DASHBOARD_STYLE: &str = "
+svg.chart { width: 100%; height: auto; margin-bottom: 0.5rem; font: 12px system-ui, sans-serif; }
+svg.chart .grid { stroke: #d0d7de; }
+svg.chart polyline { fill: none; stroke-width: 2; }
+.legend { list-style: none; padding: 0; margin: 0 0 2rem; display: flex; flex-wrap: wrap; gap: 0.25rem 1rem; }
+.swatch { display: inline-block; width: 0.8rem; height: 0.8rem; margin-right: 0.3rem; vertical-align: -0.1rem; }
+td.grew { color: #cf222e; }
+td.shrank { color: #1a7f37; }
+";
+
+/// Colors of the lines in the dashboard's charts, one per series.
+const CHART_COLORS: [&str; 8] = [
+    "#0969da", "#cf222e", "#1a7f37", "#9a6700", "#8250df", "#bf3989", "#1b7c83", "#57606a",
+];
+
+/// Number of sections with their own line in the dashboard's section chart, the largest ones.
+const CHART_SECTIONS: usize = 6;
+
+/// Sizes of everything attributed to one crate.
+#[derive(Default)]
+struct CrateEntry {
+    size: u64,
+    files: HashMap<String, Sizes>,
+    functions: HashMap<String, Sizes>,
+    contributors: Contributors,
+}
+
+/// Size of a row in a table: the total, and (where it makes sense) the bytes attributed to the row
+/// itself rather than to something nested in it.
+#[derive(Clone, Copy, Debug, Default)]
+struct Sizes {
+    total: u64,
+    self_size: Option<u64>,
+}
+
+impl Sizes {
+    fn total(total: u64) -> Sizes {
+        Sizes {
+            total,
+            self_size: None,
+        }
+    }
+
+    fn add(&mut self, size: u64, is_self: bool) {
+        self.total += size;
+        let self_size = self.self_size.get_or_insert(0);
+        if is_self {
+            *self_size += size;
+        }
+    }
+}
+
+/// Writes a static multi-page HTML report into `dir`: an `index.html` with a per-section and
+/// per-crate summary, and one page per crate with its files, functions and a flame graph.
+///
+/// `contributors` must have been produced with file grouping and function breakdown enabled.
+/// `metadata` is listed on the index page and embedded as `<meta>` tags. `rendering` applies to
+/// the per-crate flame graphs.
+pub fn write_report(
+    dir: &Path,
+    title: &str,
+    contributors: &Contributors,
+    metadata: &[(String, String)],
+    size_threshold: usize,
+    rendering: flamegraph::Rendering,
+    mut palette_map: Option<&mut flamegraph::PaletteMap>,
+) -> anyhow::Result<()> {
+    let tree = SizeTree::from_contributors(contributors);
+    let mut crates: HashMap<String, CrateEntry> = HashMap::new();
+    for (key, &size) in contributors {
+        let mut frames = key.split(';');
+        let _section = frames.next();
+        let (path, functions): (Vec<_>, Vec<_>) =
+            frames.partition(|frame| !frame.starts_with("@function: "));
+        let path = path.join("/");
+        let Some(name) = group::crate_for_path(&path) else {
+            continue;
+        };
+        let entry = crates.entry(name).or_default();
+        entry.size += size;
+        entry
+            .files
+            .entry(path)
+            .or_default()
+            .add(size, functions.is_empty());
+        // Frames list the innermost inlined function first. Its source produced the bytes, so they
+        // are its self size, and the functions it was inlined into count them in their total.
+        let innermost = functions
+            .first()
+            .map(|function| function.trim_start_matches("@function: "));
+        let functions: HashSet<_> = functions
+            .iter()
+            .map(|function| function.trim_start_matches("@function: "))
+            .collect();
+        for function in functions {
+            let sizes = entry.functions.entry(function.to_string()).or_default();
+            sizes.add(size, Some(function) == innermost);
+        }
+        let without_section = key.split_once(';').map_or(key.as_str(), |(_, rest)| rest);
+        *entry
+            .contributors
+            .entry(without_section.to_string())
+            .or_default() += size;
+    }
+
+    let mut crates: Vec<_> = crates.into_iter().collect();
+    crates.sort_by(|a, b| b.1.size.cmp(&a.1.size).then_with(|| a.0.cmp(&b.0)));
+    std::fs::create_dir_all(dir.join("crates"))
+        .with_context(|| format!("Creating {}", dir.display()))?;
+
+    let mut index = String::new();
+    writeln!(index, "<h1>{}</h1>", escape(title))?;
+    writeln!(index, "<p>Total size: {} bytes</p>", tree.size)?;
+    writeln!(index, "<h2>Sections</h2>")?;
+    let sections = tree.children.iter().map(|section| {
+        let sizes = Sizes {
+            total: section.size,
+            self_size: Some(section.self_size),
+        };
+        (escape(&section.name), sizes)
+    });
+    write_table(&mut index, "Section", sections, tree.size)?;
+    writeln!(index, "<h2>Crates</h2>")?;
+    let crate_rows = crates.iter().map(|(name, entry)| {
+        let link = format!(
+            "<a href=\"crates/{}.html\">{}</a>",
+            page_name(name),
+            escape(name)
+        );
+        (link, Sizes::total(entry.size))
+    });
+    write_table(&mut index, "Crate", crate_rows, tree.size)?;
+    if !metadata.is_empty() {
+        writeln!(index, "<h2>Metadata</h2>\n<table>")?;
+        for (key, value) in metadata {
+            writeln!(
+                index,
+                "<tr><th scope=\"row\">{}</th><td class=\"name\"><code>{}</code></td></tr>",
+                escape(key),
+                escape(value)
+            )?;
+        }
+        writeln!(index, "</table>")?;
+    }
+    write_page(&dir.join("index.html"), title, metadata, &index)?;
+
+    for (name, entry) in &crates {
+        let page = page_name(name);
+        let mut options = flamegraph::options(name.as_str(), size_threshold);
+        options.frame_height = 16;
+        options.palette_map = palette_map.as_deref_mut();
+        let mut svg = AtomicFile::create(&dir.join("crates").join(format!("{page}.svg")))?;
+        flamegraph::write_flamegraph(&entry.contributors, &mut options, rendering, &mut svg)
+            .with_context(|| format!("Rendering flame graph for {name}"))?;
+        svg.commit()?;
+
+        let mut body = String::new();
+        writeln!(
+            body,
+            "<p><a href=\"../index.html\">&larr; {}</a></p>",
+            escape(title)
+        )?;
+        writeln!(body, "<h1>{}</h1>", escape(name))?;
+        writeln!(
+            body,
+            "<p>{} bytes ({:.2}% of the module)</p>",
+            entry.size,
+            percent(entry.size, tree.size)
+        )?;
+        writeln!(
+            body,
+            "<object type=\"image/svg+xml\" data=\"{page}.svg\" aria-label=\"Flame graph of {}\"><a href=\"{page}.svg\">Flame graph of {}</a></object>",
+            escape(name),
+            escape(name)
+        )?;
+        writeln!(body, "<h2>Files</h2>")?;
+        let files = sorted(&entry.files).map(|(file, size)| (escape(file), size));
+        write_table(&mut body, "File", files, entry.size)?;
+        writeln!(body, "<h2>Functions</h2>")?;
+        let functions = sorted(&entry.functions).map(|(function, size)| (escape(function), size));
+        write_table(&mut body, "Function", functions, entry.size)?;
+        write_page(
+            &dir.join("crates").join(format!("{page}.html")),
+            name,
+            metadata,
+            &body,
+        )?;
+    }
+    Ok(())
+}
+
+/// The files [`write_report`] writes into `dir` for `contributors`.
+pub fn report_files(dir: &Path, contributors: &Contributors) -> Vec<PathBuf> {
+    let crates: HashSet<_> = contributors
+        .keys()
+        .filter_map(|key| {
+            let path: Vec<_> = key
+                .split(';')
+                .skip(1)
+                .filter(|frame| !frame.starts_with("@function: "))
+                .collect();
+            group::crate_for_path(&path.join("/"))
+        })
+        .collect();
+    let pages = crates.iter().flat_map(|name| {
+        let page = page_name(name);
+        ["svg", "html"].map(|extension| dir.join("crates").join(format!("{page}.{extension}")))
+    });
+    std::iter::once(dir.join("index.html"))
+        .chain(pages)
+        .collect()
+}
+
+/// Writes the `index.html` of a batch run into `dir`. Expects the flame graph of every entry as
+/// `<entry name>.svg` and the merged flame graph as `merged.svg` next to it.
+pub fn write_batch_index(dir: &Path, entries: &[BatchEntry]) -> anyhow::Result<()> {
+    let total: u64 = entries.iter().map(|entry| entry.size).sum();
+    let mut index = String::new();
+    writeln!(index, "<h1>Batch report</h1>")?;
+    writeln!(
+        index,
+        "<p>{} modules, {total} bytes in total. <a href=\"merged.svg\">Merged flame graph</a></p>",
+        entries.len()
+    )?;
+    let rows = entries.iter().map(|entry| {
+        let link = format!(
+            "<a href=\"{}.svg\">{}</a>",
+            escape(&entry.name),
+            escape(&entry.name)
+        );
+        (link, Sizes::total(entry.size))
+    });
+    write_table(&mut index, "Module", rows, total)?;
+    write_page(&dir.join("index.html"), "Batch report", &[], &index)
+}
+
+/// Writes a static HTML dashboard of `snapshots` (in chronological order) to `path`: charts of the
+/// total and the section sizes over time, the `movers` largest changes between consecutive
+/// snapshots, and links to the breakdowns and any other reports next to them.
+pub fn write_dashboard(
+    path: &Path,
+    title: &str,
+    snapshots: &[Snapshot],
+    movers: usize,
+) -> anyhow::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut body = String::new();
+    writeln!(body, "<style>{DASHBOARD_STYLE}</style>")?;
+    writeln!(body, "<h1>{}</h1>", escape(title))?;
+    let (Some(first), Some(last)) = (snapshots.first(), snapshots.last()) else {
+        anyhow::bail!("No snapshots");
+    };
+    writeln!(
+        body,
+        "<p>{} snapshots, from {} ({} bytes) to {} ({} bytes, {:+}).</p>",
+        snapshots.len(),
+        escape(&first.name),
+        first.total,
+        escape(&last.name),
+        last.total,
+        last.total as i64 - first.total as i64
+    )?;
+
+    writeln!(body, "<h2>Total size</h2>")?;
+    let totals = snapshots.iter().map(|snapshot| snapshot.total).collect();
+    write_chart(&mut body, snapshots, &[("Total".to_string(), totals)])?;
+    writeln!(body, "<h2>Sections</h2>")?;
+    let mut sections: Vec<_> = last.sections.iter().collect();
+    sections.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    let series: Vec<_> = sections
+        .into_iter()
+        .take(CHART_SECTIONS)
+        .map(|(name, _)| {
+            let sizes = snapshots
+                .iter()
+                .map(|snapshot| snapshot.sections.get(name).copied().unwrap_or(0))
+                .collect();
+            (name.clone(), sizes)
+        })
+        .collect();
+    write_chart(&mut body, snapshots, &series)?;
+
+    writeln!(body, "<h2>Snapshots</h2>")?;
+    writeln!(
+        body,
+        "<table><tr><th scope=\"col\">Snapshot</th><th scope=\"col\" class=\"num\">Bytes</th><th scope=\"col\" class=\"num\">Change</th><th scope=\"col\">Reports</th></tr>"
+    )?;
+    let mut previous = None;
+    for snapshot in snapshots {
+        let change = match previous {
+            Some(previous) => format!("{:+}", snapshot.total as i64 - previous as i64),
+            None => String::new(),
+        };
+        previous = Some(snapshot.total);
+        let links: Vec<_> = std::iter::once(&snapshot.path)
+            .chain(&snapshot.reports)
+            .map(|report| {
+                let extension = report.extension().unwrap_or_default().to_string_lossy();
+                format!(
+                    "<a href=\"{}\">{}</a>",
+                    escape(&href(report, dir)),
+                    escape(&extension)
+                )
+            })
+            .collect();
+        writeln!(
+            body,
+            "<tr><th scope=\"row\" class=\"name\">{}</th><td class=\"num\">{}</td><td class=\"num\">{change}</td><td>{}</td></tr>",
+            escape(&snapshot.name),
+            snapshot.total,
+            links.join(" ")
+        )?;
+    }
+    writeln!(body, "</table>")?;
+
+    writeln!(body, "<h2>Biggest movers</h2>")?;
+    for pair in snapshots.windows(2).rev() {
+        let (old, new) = (&pair[0], &pair[1]);
+        writeln!(
+            body,
+            "<h3>{} &rarr; {}</h3>",
+            escape(&old.name),
+            escape(&new.name)
+        )?;
+        let movers = history::movers(old, new, movers);
+        if movers.is_empty() {
+            writeln!(body, "<p>No changes.</p>")?;
+            continue;
+        }
+        writeln!(
+            body,
+            "<table><tr><th scope=\"col\">Name</th><th scope=\"col\">Kind</th><th scope=\"col\" class=\"num\">Before</th><th scope=\"col\" class=\"num\">After</th><th scope=\"col\" class=\"num\">Change</th></tr>"
+        )?;
+        for mover in movers {
+            let class = match mover.delta() > 0 {
+                true => "grew",
+                false => "shrank",
+            };
+            writeln!(
+                body,
+                "<tr><th scope=\"row\" class=\"name\">{}</th><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num {class}\">{:+}</td></tr>",
+                escape(&mover.name),
+                mover.kind,
+                mover.old,
+                mover.new,
+                mover.delta()
+            )?;
+        }
+        writeln!(body, "</table>")?;
+    }
+    write_page(path, title, &[], &body)
+}
+
+/// Writes a line chart of `series` (named sizes, one per snapshot) as inline SVG, followed by its
+/// legend. Hovering a point shows the snapshot and the size.
+fn write_chart(
+    out: &mut String,
+    snapshots: &[Snapshot],
+    series: &[(String, Vec<u64>)],
+) -> std::fmt::Result {
+    const WIDTH: f64 = 1000.0;
+    const HEIGHT: f64 = 240.0;
+    const LEFT: f64 = 80.0;
+    const TOP: f64 = 10.0;
+    const BOTTOM: f64 = 20.0;
+    let max = series
+        .iter()
+        .flat_map(|(_, sizes)| sizes.iter().copied())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let step = match snapshots.len() {
+        0 | 1 => 0.0,
+        n => (WIDTH - LEFT - 10.0) / (n - 1) as f64,
+    };
+    let x = |i: usize| LEFT + step * i as f64;
+    let y = |size: u64| TOP + (HEIGHT - TOP - BOTTOM) * (1.0 - size as f64 / max as f64);
+    writeln!(
+        out,
+        "<svg class=\"chart\" viewBox=\"0 0 {WIDTH} {HEIGHT}\" role=\"img\" aria-label=\"Sizes over time\">"
+    )?;
+    for size in [0, max / 2, max] {
+        writeln!(
+            out,
+            "<line class=\"grid\" x1=\"{LEFT}\" x2=\"{WIDTH}\" y1=\"{0:.1}\" y2=\"{0:.1}\"/><text x=\"{1}\" y=\"{0:.1}\" text-anchor=\"end\" dominant-baseline=\"middle\">{size}</text>",
+            y(size),
+            LEFT - 6.0
+        )?;
+    }
+    if let (Some(first), Some(last)) = (snapshots.first(), snapshots.last()) {
+        writeln!(
+            out,
+            "<text x=\"{LEFT}\" y=\"{HEIGHT}\">{}</text><text x=\"{:.1}\" y=\"{HEIGHT}\" text-anchor=\"end\">{}</text>",
+            escape(&first.name),
+            x(snapshots.len() - 1),
+            escape(&last.name)
+        )?;
+    }
+    for ((name, sizes), color) in series.iter().zip(CHART_COLORS.iter().cycle()) {
+        let points: Vec<_> = sizes
+            .iter()
+            .enumerate()
+            .map(|(i, &size)| format!("{:.1},{:.1}", x(i), y(size)))
+            .collect();
+        writeln!(
+            out,
+            "<polyline stroke=\"{color}\" points=\"{}\"/>",
+            points.join(" ")
+        )?;
+        for (i, (&size, snapshot)) in sizes.iter().zip(snapshots).enumerate() {
+            writeln!(
+                out,
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"{color}\"><title>{}: {}, {size} bytes</title></circle>",
+                x(i),
+                y(size),
+                escape(name),
+                escape(&snapshot.name)
+            )?;
+        }
+    }
+    writeln!(out, "</svg>")?;
+    writeln!(out, "<ul class=\"legend\">")?;
+    for ((name, _), color) in series.iter().zip(CHART_COLORS.iter().cycle()) {
+        writeln!(
+            out,
+            "<li><span class=\"swatch\" style=\"background: {color}\"></span>{}</li>",
+            escape(name)
+        )?;
+    }
+    writeln!(out, "</ul>")
+}
+
+/// Link from a page in `dir` to `target`: a relative path if both can be resolved, `target` as
+/// given otherwise.
+fn href(target: &Path, dir: &Path) -> String {
+    let resolve = |path: &Path| match path.as_os_str().is_empty() {
+        true => std::env::current_dir().ok(),
+        false => path.canonicalize().ok(),
+    };
+    let (Some(target_abs), Some(dir)) = (resolve(target), resolve(dir)) else {
+        return target.to_string_lossy().into_owned();
+    };
+    let common = target_abs
+        .components()
+        .zip(dir.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative: PathBuf = dir
+        .components()
+        .skip(common)
+        .map(|_| Component::ParentDir)
+        .collect();
+    relative.extend(target_abs.components().skip(common));
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Writes a single HTML file that embeds `tree` as JSON together with an interactive viewer
+/// (zoomable flame graph, table of the children of the selected node and regex search), so it can
+/// be shared and opened offline. Selecting the frame of a function in `code` shows its hexdump or
+/// disassembly on demand. `metadata` is embedded as `<meta>` tags.
+pub fn write_self_contained(
+    path: &Path,
+    title: &str,
+    tree: &SizeTree,
+    code: &[FunctionCode],
+    metadata: &[(String, String)],
+) -> anyhow::Result<()> {
+    // `</script>` inside a string must not end the data block early.
+    let data = serde_json::to_string(tree)?.replace("</", "<\\/");
+    let code = serde_json::to_string(code)?.replace("</", "<\\/");
+    let mut body = String::new();
+    writeln!(body, "<style>{VIEWER_STYLE}</style>")?;
+    writeln!(body, "<h1>{}</h1>", escape(title))?;
+    writeln!(body, "<p>Total size: {} bytes</p>", tree.size)?;
+    writeln!(
+        body,
+        "<p><input id=\"search\" type=\"search\" placeholder=\"Search (regex)\" aria-label=\"Search frames by regex\"> <span id=\"matched\" aria-live=\"polite\"></span></p>"
+    )?;
+    writeln!(
+        body,
+        "<nav id=\"crumbs\" aria-label=\"Selected node\"></nav>"
+    )?;
+    writeln!(body, "<div id=\"graph\"></div>")?;
+    writeln!(
+        body,
+        "<table id=\"children\"><thead><tr><th scope=\"col\" data-sort=\"name\">Name</th><th scope=\"col\" class=\"num\" data-sort=\"self\">Self</th><th scope=\"col\" class=\"num\" data-sort=\"size\" aria-sort=\"descending\">Bytes</th><th scope=\"col\" class=\"num\">%</th></tr></thead><tbody></tbody></table>"
+    )?;
+    writeln!(
+        body,
+        "<section id=\"code\" hidden><h2></h2><p><button type=\"button\" data-view=\"hexdump\">Hexdump</button> <button type=\"button\" data-view=\"disassembly\">Disassembly</button></p><pre></pre></section>"
+    )?;
+    writeln!(
+        body,
+        "<script type=\"application/json\" id=\"data\">{data}</script>"
+    )?;
+    writeln!(
+        body,
+        "<script type=\"application/json\" id=\"code-data\">{code}</script>"
+    )?;
+    writeln!(body, "<script>{VIEWER_SCRIPT}</script>")?;
+    write_page(path, title, metadata, &body)
+}
+
+/// Writes a single HTML page to `output` that embeds `tree` as JSON and shows it as an interactive
+/// treemap, with the same hierarchy as the flame graph. Clicking a node zooms into it, hovering it
+/// shows its size. `metadata` is embedded as `<meta>` tags.
+pub fn write_treemap(
+    title: &str,
+    tree: &SizeTree,
+    metadata: &[(String, String)],
+    mut output: impl io::Write,
+) -> anyhow::Result<()> {
+    let data = serde_json::to_string(tree)?.replace("</", "<\\/");
+    let mut body = String::new();
+    writeln!(body, "<style>{TREEMAP_STYLE}</style>")?;
+    writeln!(body, "<h1>{}</h1>", escape(title))?;
+    writeln!(body, "<p>Total size: {} bytes</p>", tree.size)?;
+    writeln!(
+        body,
+        "<nav id=\"crumbs\" aria-label=\"Selected node\"></nav>"
+    )?;
+    writeln!(body, "<div id=\"treemap\"></div>")?;
+    writeln!(body, "<div id=\"tooltip\" role=\"tooltip\" hidden></div>")?;
+    writeln!(
+        body,
+        "<script type=\"application/json\" id=\"data\">{data}</script>"
+    )?;
+    writeln!(body, "<script>{TREEMAP_SCRIPT}</script>")?;
+    output.write_all(page(title, metadata, &body).as_bytes())?;
+    Ok(())
+}
+
+/// Writes a table of rows with their sizes and share of `total`. A "Self" column is added if any
+/// row has a self size.
+fn write_table(
+    out: &mut String,
+    heading: &str,
+    rows: impl Iterator<Item = (String, Sizes)>,
+    total: u64,
+) -> std::fmt::Result {
+    let rows: Vec<_> = rows.collect();
+    let with_self = rows.iter().any(|(_, sizes)| sizes.self_size.is_some());
+    let self_heading = match with_self {
+        true => "<th scope=\"col\" class=\"num\">Self</th>",
+        false => "",
+    };
+    writeln!(
+        out,
+        "<table><tr><th scope=\"col\">{heading}</th>{self_heading}<th scope=\"col\" class=\"num\">Bytes</th><th scope=\"col\" class=\"num\">%</th></tr>"
+    )?;
+    for (name, sizes) in rows {
+        let self_cell = match (with_self, sizes.self_size) {
+            (false, _) => String::new(),
+            (true, self_size) => format!("<td class=\"num\">{}</td>", self_size.unwrap_or(0)),
+        };
+        writeln!(
+            out,
+            "<tr><th scope=\"row\" class=\"name\">{name}</th>{self_cell}<td class=\"num\">{}</td><td class=\"num\">{:.2}</td></tr>",
+            sizes.total,
+            percent(sizes.total, total)
+        )?;
+    }
+    writeln!(out, "</table>")
+}
+
+fn write_page(
+    path: &Path,
+    title: &str,
+    metadata: &[(String, String)],
+    body: &str,
+) -> anyhow::Result<()> {
+    output::write(path, page(title, metadata, body))
+}
+
+fn page(title: &str, metadata: &[(String, String)], body: &str) -> String {
+    let meta: String = metadata
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "<meta name=\"wasmphobia:{}\" content=\"{}\">\n",
+                escape(key),
+                escape(value)
+            )
+        })
+        .collect();
+    format!(
+        "<!doctype html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n{meta}<title>{}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<main>\n{body}</main>\n</body>\n</html>\n",
+        escape(title)
+    )
+}
+
+fn sorted(map: &HashMap<String, Sizes>) -> impl Iterator<Item = (&String, Sizes)> {
+    let mut entries: Vec<_> = map.iter().map(|(name, &sizes)| (name, sizes)).collect();
+    entries.sort_by(|a, b| b.1.total.cmp(&a.1.total).then_with(|| a.0.cmp(b.0)));
+    entries.into_iter()
+}
+
+fn percent(size: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    size as f64 * 100.0 / total as f64
+}
+
+/// File name (without extension) for a crate's page.
+fn page_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+pub fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,47 @@
+use std::{collections::BTreeMap, io::Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{provenance::Provenance, SizeTree};
+
+/// The whole breakdown as a tree, for scripts and dashboards that post-process it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Breakdown {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+    /// Notes about the module, like [`crate::Analysis::metadata`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+    /// Sections, directories, files and functions with their sizes. The root is called `all`.
+    pub tree: SizeTree,
+}
+
+/// The breakdown `tree`, with whatever is attached to its nodes (like instantiations, ranges of
+/// estimates or annotations), and the notes about the module.
+pub fn breakdown(
+    name: &str,
+    tree: SizeTree,
+    metadata: &[(String, String)],
+    provenance: Option<&Provenance>,
+) -> Breakdown {
+    Breakdown {
+        name: name.to_string(),
+        provenance: provenance.cloned(),
+        metadata: metadata.iter().cloned().collect(),
+        tree,
+    }
+}
+
+pub fn write_json(
+    name: &str,
+    tree: SizeTree,
+    metadata: &[(String, String)],
+    provenance: Option<&Provenance>,
+    mut output: impl Write,
+) -> anyhow::Result<()> {
+    let breakdown = breakdown(name, tree, metadata, provenance);
+    serde_json::to_writer_pretty(&mut output, &breakdown)?;
+    writeln!(output)?;
+    Ok(())
+}
@@ -0,0 +1,64 @@
+//! Wasmphobia attributes the bytes of a WebAssembly module to the sections, source files and
+//! functions they came from, using the module's DWARF debug info.
+
+pub mod advice;
+mod analysis;
+pub mod analysis_tree;
+mod analyzer;
+pub mod annotations;
+pub mod artificial;
+pub mod baseline;
+pub mod batch;
+pub mod checks;
+pub mod compressed;
+pub mod coverage;
+pub mod custom;
+pub mod data;
+pub mod demangle;
+mod dies;
+pub mod diff;
+pub mod disasm;
+pub mod duplicates;
+mod dwarf;
+pub mod eh;
+pub mod events;
+pub mod explain;
+pub mod exports;
+#[cfg(feature = "dev-fixtures")]
+pub mod fixture;
+pub mod flamegraph;
+mod glob;
+pub mod group;
+pub mod history;
+pub mod html;
+pub mod json;
+pub mod libc;
+mod lineless;
+mod lto;
+pub mod messages;
+pub mod names;
+pub mod output;
+pub mod profile;
+pub mod provenance;
+pub mod ratchet;
+pub mod reachability;
+pub mod report;
+pub mod sections;
+pub mod signing;
+pub mod sourcemap;
+pub mod speedscope;
+pub mod strings;
+pub mod table;
+pub mod threads;
+pub mod tinygo;
+pub mod tree;
+pub mod wasm;
+pub mod webpack;
+
+pub use analysis::{
+    analyze, analyze_tree, analyze_with_observer, escape_frame, AddressRange, Analysis,
+    Attribution, Contributors, DebugFile, Metric, Options,
+};
+pub use analysis_tree::{AnalysisTree, Node, NodeKind};
+pub use analyzer::Analyzer;
+pub use tree::{MergeStrategy, SizeTree};
@@ -0,0 +1,162 @@
+//! Recognizes the C runtime provided by wasi-libc or emscripten, so its size can be reported as a
+//! whole under [`FRAME`], split into groups like `malloc`, `stdio` or `math`.
+
+/// Frame that groups the C runtime in the breakdown.
+pub const FRAME: &str = "@libc-runtime";
+
+/// Directories of musl's `src` that are reported as groups of their own. Other musl code is
+/// reported as `other`.
+const MUSL_GROUPS: &[&str] = &[
+    "stdio",
+    "math",
+    "string",
+    "stdlib",
+    "ctype",
+    "env",
+    "errno",
+    "exit",
+    "locale",
+    "time",
+    "thread",
+    "unistd",
+    "multibyte",
+    "internal",
+];
+
+/// Entries of emscripten's `system/lib` that make up the C runtime. The rest (the C++ runtime and
+/// others) is left alone.
+const EMSCRIPTEN_LIBC: &[&str] = &[
+    "libc",
+    "compiler-rt",
+    "standalone",
+    "dlmalloc.c",
+    "emmalloc.c",
+    "mimalloc",
+    "sbrk.c",
+];
+
+/// Functions that come from the C runtime even when there is no line information for them.
+const SYMBOLS: &[(&str, &[&str])] = &[
+    (
+        "malloc",
+        &[
+            "malloc",
+            "free",
+            "calloc",
+            "realloc",
+            "aligned_alloc",
+            "posix_memalign",
+            "memalign",
+            "malloc_usable_size",
+            "sbrk",
+            "dlmalloc",
+            "dlfree",
+            "dlcalloc",
+            "dlrealloc",
+            "emmalloc_malloc",
+            "emmalloc_free",
+        ],
+    ),
+    (
+        "string",
+        &[
+            "memcpy", "memmove", "memset", "memcmp", "memchr", "strlen", "strcmp", "strncmp",
+            "strcpy", "strncpy", "strchr", "strrchr", "strdup",
+        ],
+    ),
+    (
+        "stdio",
+        &[
+            "printf",
+            "fprintf",
+            "snprintf",
+            "vfprintf",
+            "vsnprintf",
+            "puts",
+            "fputs",
+            "fwrite",
+            "fflush",
+            "__stdio_write",
+            "__stdio_close",
+            "__stdio_seek",
+            "__towrite",
+            "__fwritex",
+        ],
+    ),
+];
+
+/// Name prefixes of WASI and emscripten system call shims.
+const SYSCALL_PREFIXES: &[&str] = &[
+    "__wasi_",
+    "__imported_wasi_",
+    "__wasilibc_",
+    "__syscall",
+    "__emscripten_syscall",
+];
+
+/// Returns the group of a source file of the C runtime, and the components of its path within the
+/// runtime's sources.
+pub fn runtime_for_path(path: &str) -> Option<(&'static str, Vec<&str>)> {
+    let components: Vec<_> = path.split('/').filter(|c| !c.is_empty()).collect();
+    // .../wasi-libc/{libc-bottom-half,libc-top-half,dlmalloc,...}/...
+    let within = if let Some(index) = components.iter().rposition(|&c| c == "wasi-libc") {
+        &components[index + 1..]
+    } else {
+        // .../emscripten/system/lib/{libc,dlmalloc.c,...}/...
+        let index = components
+            .windows(2)
+            .rposition(|w| w == ["system", "lib"])?;
+        let within = &components[index + 2..];
+        if !EMSCRIPTEN_LIBC.contains(within.first()?) {
+            return None;
+        }
+        within
+    };
+    if within.is_empty() {
+        return None;
+    }
+    Some((group_for_path(within), within.to_vec()))
+}
+
+fn group_for_path(within: &[&str]) -> &'static str {
+    let file = within.last().copied().unwrap_or_default();
+    if within.iter().any(|c| c.contains("malloc")) || file == "sbrk.c" {
+        return "malloc";
+    }
+    if within.first() == Some(&"compiler-rt") {
+        return "compiler-rt";
+    }
+    if within
+        .iter()
+        .any(|&c| c == "libc-bottom-half" || c == "standalone")
+        || file.contains("syscall")
+    {
+        return "syscalls";
+    }
+    if within.contains(&"libm") {
+        return "math";
+    }
+    // .../musl/src/<group>/...
+    let group = within
+        .windows(2)
+        .position(|w| w == ["musl", "src"])
+        .and_then(|index| within.get(index + 2));
+    match group.and_then(|group| MUSL_GROUPS.iter().find(|g| *g == group)) {
+        Some(group) => group,
+        None => "other",
+    }
+}
+
+/// Returns the group of a C runtime function, for code without line information.
+pub fn runtime_for_symbol(name: &str) -> Option<&'static str> {
+    if SYSCALL_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+    {
+        return Some("syscalls");
+    }
+    SYMBOLS
+        .iter()
+        .find(|(_, names)| names.contains(&name))
+        .map(|&(group, _)| group)
+}
@@ -0,0 +1,145 @@
+//! Recovers the source files of code in compilation units without a line program, like those
+//! written by assemblers or synthesized by linkers. Their code has no rows in any line table, so
+//! it would otherwise only show up as unmapped bytes.
+
+use addr2line::gimli;
+
+use crate::dwarf::{self, Reader};
+
+/// An address range of a unit without a line program, and the source file it comes from.
+#[derive(Clone, Debug)]
+pub struct Range {
+    pub begin: u64,
+    pub end: u64,
+    pub file: String,
+}
+
+/// Returns the address ranges of all units without a line program, sorted by address.
+///
+/// Functions that are declared elsewhere (`DW_AT_abstract_origin` or `DW_AT_specification` into a
+/// unit that does have a line program) get the file of their declaration. Everything else gets the
+/// unit's name, joined with its compilation directory.
+pub fn ranges(dwarf: &gimli::Dwarf<Reader>) -> anyhow::Result<Vec<Range>> {
+    let mut ranges = vec![];
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        let unit = dwarf.unit(header)?;
+        if unit.line_program.is_some() {
+            continue;
+        }
+        let tombstone = !0u64 >> (64 - 8 * u32::from(unit.encoding().address_size));
+        let unit_file = unit_file(&unit);
+
+        let mut functions = vec![];
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs()? {
+            if entry.tag() != gimli::DW_TAG_subprogram {
+                continue;
+            }
+            let Some(file) = declaration_file(dwarf, entry)? else {
+                continue;
+            };
+            let mut die_ranges = dwarf.die_ranges(&unit, entry)?;
+            while let Some(range) = die_ranges.next()? {
+                if range.begin < tombstone && range.end > range.begin {
+                    functions.push((range.begin, range.end, file.clone()));
+                }
+            }
+        }
+        functions.sort_unstable_by_key(|&(begin, _, _)| begin);
+
+        let mut unit_ranges = dwarf.unit_ranges(&unit)?;
+        while let Some(range) = unit_ranges.next()? {
+            if range.begin >= tombstone || range.end <= range.begin {
+                continue;
+            }
+            // The parts of the unit's range that aren't covered by a declared function.
+            let mut covered = range.begin;
+            for (begin, end, file) in &functions {
+                let (begin, end) = ((*begin).max(covered), (*end).min(range.end));
+                if begin >= end {
+                    continue;
+                }
+                if covered < begin {
+                    ranges.push(Range {
+                        begin: covered,
+                        end: begin,
+                        file: unit_file.clone(),
+                    });
+                }
+                ranges.push(Range {
+                    begin,
+                    end,
+                    file: file.clone(),
+                });
+                covered = end;
+            }
+            if covered < range.end {
+                ranges.push(Range {
+                    begin: covered,
+                    end: range.end,
+                    file: unit_file.clone(),
+                });
+            }
+        }
+    }
+    ranges.sort_unstable_by_key(|range| range.begin);
+    Ok(ranges)
+}
+
+/// The unit's name, joined with its compilation directory if it is relative.
+fn unit_file(unit: &gimli::Unit<Reader>) -> String {
+    let name = dwarf::unit_name(unit);
+    let comp_dir = unit
+        .comp_dir
+        .as_ref()
+        .and_then(|dir| gimli::Reader::to_string_lossy(dir).ok());
+    match comp_dir {
+        Some(dir) => dwarf::join_path(&dir, &name),
+        None => name,
+    }
+}
+
+/// The file a function is declared in, if its declaration lies in another unit.
+fn declaration_file(
+    dwarf: &gimli::Dwarf<Reader>,
+    entry: &gimli::DebuggingInformationEntry<Reader>,
+) -> anyhow::Result<Option<String>> {
+    for name in [gimli::DW_AT_abstract_origin, gimli::DW_AT_specification] {
+        let Some(gimli::AttributeValue::DebugInfoRef(offset)) = entry.attr_value(name)? else {
+            continue;
+        };
+        let Some(unit) = unit_containing(dwarf, offset)? else {
+            continue;
+        };
+        let Some(unit_offset) = offset.to_unit_offset(&unit.header) else {
+            continue;
+        };
+        let origin = unit.entry(unit_offset)?;
+        let Some(gimli::AttributeValue::FileIndex(index)) =
+            origin.attr_value(gimli::DW_AT_decl_file)?
+        else {
+            continue;
+        };
+        if let Some(file) = dwarf::file_path(dwarf, &unit, index) {
+            return Ok(Some(file));
+        }
+    }
+    Ok(None)
+}
+
+fn unit_containing(
+    dwarf: &gimli::Dwarf<Reader>,
+    offset: gimli::DebugInfoOffset,
+) -> anyhow::Result<Option<gimli::Unit<Reader>>> {
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        let Some(start) = header.offset().as_debug_info_offset() else {
+            continue;
+        };
+        if start.0 <= offset.0 && offset.0 < start.0 + header.length_including_self() {
+            return Ok(Some(dwarf.unit(header)?));
+        }
+    }
+    Ok(None)
+}
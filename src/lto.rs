@@ -0,0 +1,45 @@
+use addr2line::gimli;
+
+use crate::dwarf::{self, Reader};
+
+/// Units whose code is split into at least this many runs, interleaved with other units' code,
+/// are considered to be merged by LTO.
+const MERGED_RUNS: usize = 3;
+
+/// Returns the names of the compilation units that look like they were merged by (fat) LTO.
+///
+/// Without LTO, the linker lays out each unit's code in one piece. LTO optimizes all units as one
+/// module, so their functions end up interleaved in arbitrary order, and anything grouped by unit
+/// would be misleading. The breakdown attributes every line table row to its own file instead.
+pub fn merged_units(dwarf: &gimli::Dwarf<Reader>) -> anyhow::Result<Vec<String>> {
+    let mut names = vec![];
+    let mut ranges = vec![];
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        let unit = dwarf.unit(header)?;
+        let tombstone = !0u64 >> (64 - 8 * u32::from(unit.encoding().address_size));
+        let mut unit_ranges = dwarf.unit_ranges(&unit)?;
+        while let Some(range) = unit_ranges.next()? {
+            if range.begin < tombstone && range.end > range.begin {
+                ranges.push((range.begin, names.len()));
+            }
+        }
+        names.push(dwarf::unit_name(&unit));
+    }
+    ranges.sort_unstable();
+
+    let mut runs = vec![0; names.len()];
+    let mut previous = None;
+    for (_, unit) in ranges {
+        if previous != Some(unit) {
+            runs[unit] += 1;
+            previous = Some(unit);
+        }
+    }
+    Ok(names
+        .into_iter()
+        .zip(runs)
+        .filter(|&(_, runs)| runs >= MERGED_RUNS)
+        .map(|(name, _)| name)
+        .collect())
+}
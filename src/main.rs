@@ -1,197 +1,1717 @@
 use std::{
     collections::HashMap,
     io::{Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 
-use addr2line::fallible_iterator::FallibleIterator;
 use anyhow::Context;
 
-use clap::Parser;
-use object::{Object, ObjectSection};
+use clap::{Parser, Subcommand, ValueEnum};
+use regex::Regex;
+use wasmphobia::{
+    annotations::Annotations,
+    baseline, batch,
+    compressed::Uncertainty,
+    custom::CustomSection,
+    data, diff, disasm,
+    events::{Event, NdjsonWriter, Observer, StopReason},
+    explain, flamegraph,
+    group::{self, GroupBy},
+    history, html, json,
+    messages::{Lang, Message},
+    output::{AtomicFile, Overwrite},
+    provenance::Provenance,
+    ratchet, reachability,
+    report::{self, Report},
+    signing::{self, Signature},
+    speedscope, table, wasm, webpack, Analysis, Attribution, Contributors, DebugFile, Metric,
+    SizeTree,
+};
+
+const STDINOUT_MARKER: &str = "-";
+
+/// Memory limit of the Wasm build, which can't grow past 4 GB and often gets less in a browser.
+const WASM_MEMORY_LIMIT: u64 = 2_000_000_000;
+
+/// Counts the allocated bytes for `--memory-limit`. A browser gives away no other measure of the
+/// memory in use; natively, the operating system does, and the system allocator stays as is.
+#[cfg(target_family = "wasm")]
+mod allocator {
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        sync::atomic::{AtomicU64, Ordering},
+    };
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    /// Bytes currently allocated.
+    static ALLOCATED: AtomicU64 = AtomicU64::new(0);
+
+    /// The system allocator, counting the allocated bytes in [`ALLOCATED`].
+    struct CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                ALLOCATED.fetch_add(layout.size() as u64, Ordering::Relaxed);
+            }
+            ptr
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc_zeroed(layout);
+            if !ptr.is_null() {
+                ALLOCATED.fetch_add(layout.size() as u64, Ordering::Relaxed);
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            ALLOCATED.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_ptr = System.realloc(ptr, layout, new_size);
+            if !new_ptr.is_null() {
+                ALLOCATED.fetch_add(new_size as u64, Ordering::Relaxed);
+                ALLOCATED.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+            }
+            new_ptr
+        }
+    }
+
+    pub fn allocated() -> u64 {
+        ALLOCATED.load(Ordering::Relaxed)
+    }
+}
+
+/// Bytes of memory in use: the allocated bytes in the Wasm build.
+#[cfg(target_family = "wasm")]
+fn memory_used() -> Option<u64> {
+    Some(allocator::allocated())
+}
+
+/// Bytes of memory in use: the resident memory of the process, where the system tells it
+/// (Linux), else `None`.
+#[cfg(not(target_family = "wasm"))]
+fn memory_used() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let resident = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?;
+    let kilobytes: u64 = resident.trim().strip_suffix("kB")?.trim().parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+/// Stops the analysis once more than `limit` bytes are allocated, so that it ends with a partial
+/// breakdown rather than an allocation failure, which aborts the process (or traps the whole
+/// instance in a browser).
+struct MemoryGuard {
+    limit: u64,
+}
+
+impl Observer for MemoryGuard {
+    fn event(&mut self, _event: Event) {}
+
+    fn should_stop(&mut self) -> Option<StopReason> {
+        (memory_used()? > self.limit).then_some(StopReason::Memory { limit: self.limit })
+    }
+}
+
+/// Prints the warnings of the analysis to stderr, for a terminal or a CI log.
+struct WarningPrinter {
+    lang: Lang,
+}
+
+impl Observer for WarningPrinter {
+    fn event(&mut self, event: Event) {
+        if let Event::Warning { message } = event {
+            eprintln!("{}", Message::Warning { message }.text(self.lang));
+        }
+    }
+}
+
+/// What to write to the output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// An SVG flame graph.
+    Svg,
+    /// An approximation of webpack's stats.json, for bundle analysis tools.
+    WebpackStats,
+    /// The breakdown as a JSON tree of sections, directories, files and functions.
+    Json,
+    /// A single HTML file showing the breakdown as an interactive treemap.
+    Treemap,
+    /// Folded stack lines (`frame;frame;frame size`), as read by flamegraph.pl, inferno and speedscope.
+    Folded,
+    /// A speedscope profile, for its left heavy and sandwich views.
+    Speedscope,
+    /// A plain text table of the largest functions (see --top), with their file, crate, size and share.
+    Table,
+}
 
 #[derive(Clone, Debug, Parser)]
 #[command(version)]
 struct Args {
-    #[arg(short, long)]
+    #[arg(short, long, env = "WASMPHOBIA_INPUT")]
     input: Option<PathBuf>,
-    #[arg(short, long)]
+    #[arg(short, long, env = "WASMPHOBIA_OUTPUT")]
     output: Option<PathBuf>,
 
-    #[arg(long)]
+    #[arg(long, global = true, env = "WASMPHOBIA_FILES_ONLY")]
     /// Only break down to files, not functions.
     files_only: bool,
 
-    #[arg(long)]
-    /// Show raw object symbol names for functions, rather than demangling them.
+    #[arg(
+        long,
+        global = true,
+        alias = "no-demangle",
+        env = "WASMPHOBIA_RAW_SYMBOLS"
+    )]
+    /// Show raw object symbol names for functions, rather than demangling them. Also available as `--no-demangle`.
     raw_symbols: bool,
 
-    #[arg(long)]
+    #[arg(long, global = true, env = "WASMPHOBIA_COLLAPSE_GENERICS")]
+    /// Show all instantiations of generic functions and types as one frame. JSON output keeps their generic arguments and sizes.
+    collapse_generics: bool,
+
+    #[arg(long, global = true, env = "WASMPHOBIA_GROUP_COMPILER_GENERATED")]
+    /// Group functions the compiler generated (marked DW_AT_artificial, like thunks or implicit constructors) under @compiler-generated, apart from code written by hand.
+    group_compiler_generated: bool,
+
+    #[arg(long, global = true, env = "WASMPHOBIA_UNMAPPED_FUNCTIONS")]
+    /// Break the code without debug info down by function (named after the name section) under @unmapped, rather than showing it all as <no mapping info>.
+    unmapped_functions: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_delimiter = ',',
+        env = "WASMPHOBIA_DEBUG_FILE"
+    )]
+    /// File with the module's debug info: a separate debug module (by default the one its external_debug_info section names, next to the input), split DWARF objects (.dwo) or packages (.dwp) of its skeleton units, or a source map (.wasm.map, by default the one its sourceMappingURL section names) for modules without DWARF (repeatable or comma-separated).
+    debug_file: Vec<PathBuf>,
+
+    #[arg(long, value_enum, default_value = "svg", env = "WASMPHOBIA_FORMAT")]
+    /// Output format: an SVG flame graph ("svg"), a webpack-compatible stats.json ("webpack-stats"), the breakdown as a JSON tree ("json"), an interactive HTML treemap ("treemap"), folded stack lines ("folded"), a speedscope profile ("speedscope") or a text table of the largest functions ("table").
+    format: Format,
+
+    #[arg(long, env = "WASMPHOBIA_TITLE")]
     /// Title for the flame graph (default: input file name).
     title: Option<String>,
 
-    #[arg(long)]
+    #[arg(long, global = true, env = "WASMPHOBIA_SHOW_DEBUG_SECTIONS")]
     /// Show DWARF debug sections in the breakdown.
     show_debug_sections: bool,
 
-    #[arg(long, default_value_t = 32)]
+    #[arg(long, default_value_t = 32, env = "WASMPHOBIA_SIZE_THRESHOLD")]
     /// Minimum size of a mapped region in bytes to be shown in the flamegraph. (WARNING: Small values can make the flamegraph very big and slow.)
     size_threshold: usize,
+
+    #[arg(long, env = "WASMPHOBIA_MIN_BYTES")]
+    /// Fold everything smaller than this many bytes into an "@other" frame of its parent directory, file or function.
+    min_bytes: Option<u64>,
+
+    #[arg(long, env = "WASMPHOBIA_MIN_PERCENT")]
+    /// Fold everything smaller than this percentage of the module (e.g. 0.1) into an "@other" frame of its parent directory, file or function.
+    min_percent: Option<f64>,
+
+    #[arg(long, env = "WASMPHOBIA_ROOT_FRAME")]
+    /// Put the whole breakdown below a frame with this name (e.g. "my-app v1.2.3"), so that folded stacks of several modules or tools can be combined under distinct roots in one flame graph.
+    root_frame: Option<String>,
+
+    #[arg(long, default_value_t = 20, env = "WASMPHOBIA_TOP")]
+    /// Number of rows of the table with --format table.
+    top: usize,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "files",
+        global = true,
+        env = "WASMPHOBIA_GROUP_BY"
+    )]
+    /// How to group code: by source file path ("files"), by the C++/Rust namespace and class of each function ("namespace"), or by Rust crate ("crate").
+    group_by: GroupBy,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        global = true,
+        env = "WASMPHOBIA_DEMANGLE_CACHE_SIZE"
+    )]
+    /// Maximum number of demangled symbol names to keep cached (0 = no limit). Lower values save memory on huge binaries.
+    demangle_cache_size: usize,
+
+    #[arg(long, global = true, env = "WASMPHOBIA_DEMANGLER_CMD")]
+    /// External demangler for names the built-in Rust, C++ and Go demanglers don't understand: a program (plus arguments) that reads one name per line from stdin and writes each one demangled on its own line to stdout, like c++filt.
+    demangler_cmd: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "bytes",
+        global = true,
+        env = "WASMPHOBIA_METRIC"
+    )]
+    /// What to measure: module bytes ("bytes"), the number of debug info entries per source file ("die-count") or the estimated bytes of the gzip-compressed module ("gzip").
+    metric: Metric,
+
+    #[arg(
+        long = "only-unit",
+        global = true,
+        value_delimiter = ',',
+        env = "WASMPHOBIA_ONLY_UNIT"
+    )]
+    /// Only analyze compilation units whose name matches this glob (repeatable or comma-separated, e.g. "*serde*").
+    only_unit: Vec<String>,
+
+    #[arg(long, global = true, value_delimiter = ',', env = "WASMPHOBIA_INCLUDE")]
+    /// Only show code from source files whose path matches this glob (repeatable or comma-separated, e.g. "*/my-workspace/*").
+    include: Vec<String>,
+
+    #[arg(long, global = true, value_delimiter = ',', env = "WASMPHOBIA_EXCLUDE")]
+    /// Leave out code from source files whose path matches this glob (repeatable or comma-separated, e.g. "*/library/std/*").
+    exclude: Vec<String>,
+
+    #[arg(
+        long = "root",
+        global = true,
+        value_delimiter = ',',
+        env = "WASMPHOBIA_ROOT"
+    )]
+    /// Source directory to show as its own top-level group, whatever path it is checked out at (repeatable or comma-separated, e.g. "src"). Files below none of them are grouped as "other".
+    root: Vec<String>,
+
+    #[arg(long, global = true, env = "WASMPHOBIA_ASSEMBLY_SYMBOLS")]
+    /// Break assembly sources without function debug info down by the functions of the name section.
+    assembly_symbols: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "dwarf",
+        global = true,
+        env = "WASMPHOBIA_ATTRIBUTION"
+    )]
+    /// How code is attributed to functions: by the address ranges of the debug info ("dwarf"), or by each function's exact size in the code section, named by the debug info ("code-section").
+    attribution: Attribution,
+
+    #[arg(long, global = true, env = "WASMPHOBIA_CASE_INSENSITIVE_PATHS")]
+    /// Treat source paths that differ only in case as the same file (e.g. for binaries built on Windows).
+    case_insensitive_paths: bool,
+
+    #[arg(
+        long = "custom-section",
+        global = true,
+        value_delimiter = ',',
+        env = "WASMPHOBIA_CUSTOM_SECTION"
+    )]
+    /// How to read a custom section, as <name>=opaque|utf8|json (repeatable or comma-separated). Shown in the breakdown and the "custom-sections" report.
+    custom_section: Vec<CustomSection>,
+
+    #[arg(long, value_enum, value_delimiter = ',', env = "WASMPHOBIA_REPORT")]
+    /// Additional reports to print to stderr (comma-separated, e.g. "duplicated-data").
+    report: Vec<Report>,
+
+    #[arg(long, value_delimiter = ',', env = "WASMPHOBIA_BUDGET")]
+    /// Exit with an error if the module or a crate is larger than this, as total=<size> or crate:<name>=<size> (repeatable or comma-separated, e.g. "total=2MiB" or "crate:serde=200KiB"). Sizes are in the chosen --metric.
+    budget: Vec<ratchet::Limit>,
+
+    #[arg(long, num_args = 0..=1, default_missing_value = STDINOUT_MARKER, env = "WASMPHOBIA_EMIT_EVENTS")]
+    /// Stream NDJSON progress events during the analysis to the given file (or stderr if no file is given, in place of the plain text warnings).
+    emit_events: Option<PathBuf>,
+
+    #[arg(long, value_parser = parse_duration, env = "WASMPHOBIA_CHECKPOINT_EVERY")]
+    /// While analyzing, overwrite the output file with the breakdown so far at most this often (e.g. "30s" or "2m"), to peek at large modules before the analysis is done.
+    checkpoint_every: Option<Duration>,
+
+    #[arg(long, value_parser = ratchet::parse_size, env = "WASMPHOBIA_MEMORY_LIMIT")]
+    /// Stop attributing code once the analysis uses more memory than this (e.g. "1.5GB"), and show the rest as unmapped rather than running out of memory. Natively this is the resident memory of the process (Linux only). Defaults to 2GB in the Wasm build.
+    memory_limit: Option<u64>,
+
+    #[arg(long, env = "WASMPHOBIA_SELF_CONTAINED_HTML")]
+    /// Also write a single interactive HTML file (flame graph, table and search) with the breakdown embedded.
+    self_contained_html: Option<PathBuf>,
+
+    #[arg(long, default_value_t = 20, env = "WASMPHOBIA_EMBED_CODE")]
+    /// Embed a hexdump and disassembly of this many of the largest functions in the --self-contained-html file (0 to leave them out).
+    embed_code: usize,
+
+    #[arg(long, global = true, env = "WASMPHOBIA_NO_CLOBBER")]
+    /// Fail before analyzing if an output file already exists, rather than replacing it.
+    no_clobber: bool,
+
+    #[arg(long, global = true, env = "WASMPHOBIA_FORCE")]
+    /// Replace existing output files even if --no-clobber is set (e.g. through the environment).
+    force: bool,
+
+    #[arg(long, global = true, env = "WASMPHOBIA_MINIFY")]
+    /// Minify the generated SVG (rounded coordinates, hex colors, no indentation or comments).
+    minify: bool,
+
+    #[arg(long, global = true, value_enum, default_value_t = Lang::En, env = "WASMPHOBIA_LANG")]
+    /// Language of warnings and advice.
+    lang: Lang,
+
+    #[arg(long, global = true, env = "WASMPHOBIA_MAX_LABEL_LENGTH")]
+    /// Shorten flame graph labels to this many characters. Tooltips keep the full names, and shortened labels end in a hash of the name so that different names stay distinguishable.
+    max_label_length: Option<usize>,
+
+    #[arg(long, global = true, env = "WASMPHOBIA_PALETTE_MAP")]
+    /// Keep flame graph colors consistent across runs: frame colors are read from this file (flamegraph.pl's palette.map format) and new ones are added to it.
+    palette_map: Option<PathBuf>,
+
+    #[arg(long, global = true, env = "WASMPHOBIA_ANNOTATIONS")]
+    /// JSON file of rules that attach metadata (like an owner or ticket) to the files and functions matching their glob patterns, in JSON and HTML outputs.
+    annotations: Option<PathBuf>,
+
+    #[arg(long, global = true, env = "WASMPHOBIA_SIGN")]
+    /// Embed a SHA-256 of the input and of the breakdown into the output metadata.
+    sign: bool,
+
+    #[arg(long, global = true, env = "WASMPHOBIA_SIGN_KEY")]
+    /// Ed25519 private key (32-byte seed, raw or hex) to additionally sign the embedded hashes with. Implies --sign.
+    sign_key: Option<PathBuf>,
+
+    #[arg(long, global = true, env = "WASMPHOBIA_OFFLINE")]
+    /// Guarantee that nothing is fetched from or sent over the network: fail before analyzing if an input is a URL rather than a local file.
+    offline: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+enum Command {
+    /// Print the size attributed to one path or symbol of the breakdown, plus its children.
+    Size(SizeArgs),
+    /// Write a multi-page static HTML report with per-crate pages into a directory.
+    HtmlReport(HtmlReportArgs),
+    /// Explain where the bytes of a single function come from.
+    Explain(ExplainArgs),
+    /// Analyze many wasm files, writing a flame graph for each plus a merged one and an index.
+    Batch(BatchArgs),
+    /// Estimate how much code would become unreachable if some exports were removed.
+    WhatIf(WhatIfArgs),
+    /// List the largest strings in the data segments, like panic messages and format strings.
+    Strings(StringsArgs),
+    /// Compare two builds of a module, listing the crates, files and functions that changed size.
+    Diff(DiffArgs),
+    /// Snapshot per-crate sizes as budgets, and check modules against them.
+    Ratchet(RatchetArgs),
+    /// Write the sizes of the module, its files and its functions as a baseline (JSON).
+    Snapshot(SnapshotArgs),
+    /// Fail if the module, or a file or function in it, grew too much since a baseline.
+    Check(CheckArgs),
+    /// Write an HTML dashboard of how sizes changed over past JSON breakdowns (--format json).
+    Dashboard(DashboardArgs),
+    /// Write a small module with synthetic debug info, for tests and bug reports.
+    #[cfg(feature = "dev-fixtures")]
+    GenFixture(GenFixtureArgs),
+}
+
+#[cfg(feature = "dev-fixtures")]
+#[derive(Clone, Debug, clap::Args)]
+struct GenFixtureArgs {
+    /// Fixture to write. Lists the fixtures if omitted.
+    name: Option<String>,
+
+    /// File to write the module to ("-" for stdout).
+    #[arg(short, long, default_value = STDINOUT_MARKER)]
+    output: PathBuf,
 }
 
-impl From<Args> for inferno::flamegraph::Options<'static> {
+#[derive(Clone, Debug, clap::Args)]
+struct SizeArgs {
+    /// Wasm file to analyze ("-" for stdin).
+    input: PathBuf,
+
+    /// `;`-separated path (e.g. "@section: <code>;src;main.rs") or function name to look up.
+    #[arg(required_unless_present = "symbol", conflicts_with = "symbol")]
+    path: Option<String>,
+
+    /// Regex matched against every frame name instead of looking up an exact path.
+    #[arg(long)]
+    symbol: Option<String>,
+}
+
+#[derive(Clone, Debug, clap::Args)]
+struct HtmlReportArgs {
+    /// Wasm file to analyze ("-" for stdin).
+    input: PathBuf,
+
+    /// Directory to write the report into.
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+#[derive(Clone, Debug, clap::Args)]
+struct ExplainArgs {
+    /// Wasm file to analyze ("-" for stdin).
+    input: PathBuf,
+
+    /// Name of the function (exact, or a substring if nothing matches exactly).
+    function: String,
+
+    /// Print the explanation as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Clone, Debug, clap::Args)]
+struct BatchArgs {
+    /// Wasm files to analyze.
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// Directory to write the reports into.
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+#[derive(Clone, Debug, clap::Args)]
+struct WhatIfArgs {
+    /// Wasm file to analyze ("-" for stdin).
+    input: PathBuf,
+
+    /// Name of an export to hypothetically remove (repeatable, or comma-separated).
+    #[arg(long = "remove-export", required = true, value_delimiter = ',')]
+    exports: Vec<String>,
+
+    /// Print the result as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Clone, Debug, clap::Args)]
+struct StringsArgs {
+    /// Wasm file to analyze ("-" for stdin).
+    input: PathBuf,
+
+    /// Number of strings to list, the largest first.
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+
+    /// Print the strings as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Clone, Debug, clap::Args)]
+struct DiffArgs {
+    /// The old build.
+    old: PathBuf,
+
+    /// The new build.
+    new: PathBuf,
+
+    /// Number of crates, files and functions to list, the largest changes first.
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+
+    /// Print all changes as JSON.
+    #[arg(long)]
+    json: bool,
+
+    /// Also write a differential flame graph of the new build to this file, with grown frames in red and shrunk frames in blue.
+    #[arg(long)]
+    svg: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, clap::Args)]
+struct SnapshotArgs {
+    /// Wasm file to analyze ("-" for stdin).
+    input: PathBuf,
+
+    /// File to write the baseline to ("-" for stdout).
+    #[arg(short, long, default_value = STDINOUT_MARKER)]
+    output: PathBuf,
+}
+
+#[derive(Clone, Debug, clap::Args)]
+struct CheckArgs {
+    /// Wasm file to analyze ("-" for stdin).
+    input: PathBuf,
+
+    /// Baseline written by `snapshot`.
+    #[arg(long, default_value = "baseline.json")]
+    baseline: PathBuf,
+
+    /// How much the module and every file and function may grow, in percent (e.g. "2%").
+    #[arg(long, value_parser = parse_percent, default_value = "0%")]
+    max_growth: f64,
+
+    /// Ignore files and functions that grew by fewer bytes than this, however large that is in percent.
+    #[arg(long, default_value_t = 32)]
+    min_bytes: u64,
+}
+
+#[derive(Clone, Debug, clap::Args)]
+struct DashboardArgs {
+    /// JSON breakdowns of past builds, oldest first (e.g. named by date).
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// HTML file to write.
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Number of files and functions to list for every change between two breakdowns.
+    #[arg(long, default_value_t = 10)]
+    movers: usize,
+}
+
+#[derive(Clone, Debug, clap::Args)]
+struct RatchetArgs {
+    #[command(subcommand)]
+    command: RatchetCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+enum RatchetCommand {
+    /// Print budgets (TOML) with the current per-crate sizes plus some slack.
+    Init(RatchetInitArgs),
+    /// Fail if the module or any crate in it exceeds its budget.
+    Check(RatchetCheckArgs),
+}
+
+#[derive(Clone, Debug, clap::Args)]
+struct RatchetInitArgs {
+    /// Wasm file to analyze ("-" for stdin).
+    input: PathBuf,
+
+    /// How much every size may grow, in percent.
+    #[arg(long, default_value_t = 5.0)]
+    slack: f64,
+}
+
+#[derive(Clone, Debug, clap::Args)]
+struct RatchetCheckArgs {
+    /// Wasm file to analyze ("-" for stdin).
+    input: PathBuf,
+
+    /// Budgets written by `ratchet init`.
+    #[arg(long, default_value = "budgets.toml")]
+    budgets: PathBuf,
+}
+
+impl From<&Args> for wasmphobia::Options {
+    fn from(value: &Args) -> Self {
+        wasmphobia::Options {
+            files_only: value.files_only,
+            collapse_generics: value.collapse_generics,
+            compiler_generated: value.group_compiler_generated,
+            unmapped_functions: value.unmapped_functions,
+            // Read in `render`, where the input is known.
+            debug_files: vec![],
+            raw_symbols: value.raw_symbols,
+            show_debug_sections: value.show_debug_sections,
+            group_by: value.group_by,
+            demangle_cache_size: value.demangle_cache_size,
+            demangler_cmd: value.demangler_cmd.clone(),
+            metric: value.metric,
+            only_units: value.only_unit.clone(),
+            include: value.include.clone(),
+            exclude: value.exclude.clone(),
+            custom_sections: value.custom_section.clone(),
+            case_insensitive_paths: value.case_insensitive_paths,
+            lang: value.lang,
+            assembly_symbols: value.assembly_symbols,
+            attribution: value.attribution,
+            // Roots are matched against normalized paths.
+            roots: value
+                .root
+                .iter()
+                .map(|root| root.replace('\\', "/"))
+                .collect(),
+        }
+    }
+}
+
+impl<'a> From<Args> for flamegraph::Options<'a> {
     fn from(value: Args) -> Self {
-        let mut options = inferno::flamegraph::Options::default();
-        options.title = value
-            .title
-            .or_else(|| Some(value.input.as_ref()?.file_name()?.to_str()?.to_string()))
-            .unwrap_or("<Unknown wasm file>".to_string());
-        options.subtitle = Some("Wasm module size breakdown".to_string());
-        options.count_name = "KB".to_string();
-        options.factor = 1.0 / 1000.0;
-        options.min_width = value.size_threshold as f64 / 1000.0;
-        options.frame_height = 24;
-        options.name_type = "".to_string();
-        options
+        let title = title(&value);
+        match value.metric {
+            Metric::Bytes => flamegraph::options(title, value.size_threshold),
+            Metric::DieCount => flamegraph::die_count_options(title),
+            Metric::Gzip => flamegraph::gzip_options(title, value.size_threshold),
+        }
     }
 }
 
-struct Segment {
-    name: String,
-    start: u64,
-    end: u64,
-    mapped: u64,
+/// The breakdown of `analysis` as a tree, with the instantiations of collapsed generics, the
+/// ranges of estimated sizes and the metadata of `annotations` attached to its nodes.
+fn size_tree(analysis: &Analysis, annotations: &Annotations) -> SizeTree {
+    let mut tree = SizeTree::from_contributors(&analysis.contributors);
+    tree.annotate(&analysis.instantiations);
+    if let Some(uncertainty) = &analysis.uncertainty {
+        tree.attach_ranges(&uncertainty.low, &uncertainty.high);
+    }
+    annotations.apply(&mut tree);
+    tree
 }
 
-impl Segment {
-    fn size(&self) -> u64 {
-        self.end - self.start
+/// Title for the report: `--title`, or the input file name.
+fn title(args: &Args) -> String {
+    args.title
+        .clone()
+        .or_else(|| Some(args.input.as_ref()?.file_name()?.to_str()?.to_string()))
+        .unwrap_or("<Unknown wasm file>".to_string())
+}
+
+/// Parses non-negative percentages like "2%" or "0.5", with or without the percent sign.
+fn parse_percent(s: &str) -> Result<f64, String> {
+    let value = s.strip_suffix('%').unwrap_or(s).trim();
+    match value.parse::<f64>() {
+        Ok(percent) if percent >= 0.0 => Ok(percent),
+        _ => Err(format!("expected a percentage like \"2%\", got {s:?}")),
     }
 }
 
-fn main() -> anyhow::Result<()> {
-    let stdinout_marker: PathBuf = PathBuf::from("-");
+/// Parses durations like "500ms", "30s", "2m" or "1h".
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(digits);
+    let value: u64 = value
+        .parse()
+        .map_err(|_| format!("expected a duration like \"30s\", got {s:?}"))?;
+    let seconds = |per_unit: u64| {
+        value
+            .checked_mul(per_unit)
+            .map(Duration::from_secs)
+            .ok_or_else(|| format!("{s:?} is too long a duration"))
+    };
+    match unit {
+        "ms" => Ok(Duration::from_millis(value)),
+        "s" | "" => Ok(Duration::from_secs(value)),
+        "m" => seconds(60),
+        "h" => seconds(60 * 60),
+        _ => Err(format!("unknown unit {unit:?}, expected ms, s, m or h")),
+    }
+}
+
+/// Overwrites the output file with the breakdown so far, at most every `every`.
+struct Checkpoints {
+    path: PathBuf,
+    title: String,
+    format: Format,
+    size_threshold: usize,
+    top: usize,
+    rendering: flamegraph::Rendering,
+    lang: Lang,
+    provenance: Provenance,
+    every: Duration,
+    last: Instant,
+}
+
+impl Checkpoints {
+    fn write(&self, contributors: &Contributors) -> anyhow::Result<()> {
+        let mut file = AtomicFile::create(&self.path)?;
+        match self.format {
+            Format::Svg => {
+                let mut options = flamegraph::options(self.title.as_str(), self.size_threshold);
+                options.subtitle = Some("Partial size breakdown, still analyzing".to_string());
+                options.notes = format!(
+                    "wasmphobia-input: {}",
+                    note_fields(&self.provenance.metadata())
+                );
+                flamegraph::write_flamegraph(contributors, &mut options, self.rendering, &mut file)?
+            }
+            Format::WebpackStats => {
+                webpack::write_stats(&self.title, contributors, Some(&self.provenance), &mut file)?
+            }
+            Format::Json => json::write_json(
+                &self.title,
+                SizeTree::from_contributors(contributors),
+                &[],
+                Some(&self.provenance),
+                &mut file,
+            )?,
+            Format::Treemap => html::write_treemap(
+                &self.title,
+                &SizeTree::from_contributors(contributors),
+                &self.provenance.metadata(),
+                &mut file,
+            )?,
+            Format::Folded => flamegraph::write_folded(contributors, &mut file)?,
+            Format::Speedscope => speedscope::write_profile(
+                &self.title,
+                contributors,
+                speedscope::Unit::Bytes,
+                &mut file,
+            )?,
+            Format::Table => {
+                table::write_table(contributors, &HashMap::new(), None, self.top, &mut file)?
+            }
+        }
+        file.commit()
+    }
+}
+
+impl Observer for Checkpoints {
+    fn event(&mut self, _event: Event) {}
+
+    fn checkpoint(&mut self, contributors: &Contributors) {
+        if contributors.is_empty() || self.last.elapsed() < self.every {
+            return;
+        }
+        if let Err(err) = self.write(contributors) {
+            let message = Message::CheckpointFailed {
+                error: format!("{err:#}"),
+            };
+            let message = message.text(self.lang);
+            eprintln!("{}", Message::Warning { message }.text(self.lang));
+        }
+        self.last = Instant::now();
+    }
+}
+
+fn rendering(args: &Args) -> flamegraph::Rendering {
+    flamegraph::Rendering {
+        minify: args.minify,
+        max_label_length: args.max_label_length,
+    }
+}
 
+fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    if args.offline {
+        check_offline(&args)?;
+    }
+    match &args.command {
+        Some(Command::Size(size_args)) => size(&args, size_args),
+        Some(Command::HtmlReport(report_args)) => html_report(&args, report_args),
+        Some(Command::Explain(explain_args)) => explain(&args, explain_args),
+        Some(Command::Batch(batch_args)) => batch(&args, batch_args),
+        Some(Command::WhatIf(what_if_args)) => what_if(&args, what_if_args),
+        Some(Command::Strings(strings_args)) => strings(&args, strings_args),
+        Some(Command::Diff(diff_args)) => diff(&args, diff_args),
+        Some(Command::Ratchet(ratchet_args)) => ratchet(&args, ratchet_args),
+        Some(Command::Snapshot(snapshot_args)) => snapshot(&args, snapshot_args),
+        Some(Command::Check(check_args)) => check(&args, check_args),
+        Some(Command::Dashboard(dashboard_args)) => dashboard(&args, dashboard_args),
+        #[cfg(feature = "dev-fixtures")]
+        Some(Command::GenFixture(gen_args)) => gen_fixture(gen_args),
+        None => render(args),
+    }
+}
+
+fn overwrite(args: &Args) -> Overwrite {
+    match args.no_clobber && !args.force {
+        true => Overwrite::Refuse,
+        false => Overwrite::Replace,
+    }
+}
+
+fn render(args: Args) -> anyhow::Result<()> {
     let input_data = match &args.input {
-        Some(path) if path != &stdinout_marker => std::fs::read(path)?,
-        _ => read_stdin()?,
+        Some(path) => read_input(path)?,
+        None => read_stdin()?,
     };
-    let module_size = input_data.len();
 
-    let wasm_file = object::wasm::WasmFile::parse(input_data.as_slice())?;
+    let overwrite = overwrite(&args);
+    let outputs = [&args.output, &args.self_contained_html, &args.emit_events];
+    for path in outputs.into_iter().flatten() {
+        if path != Path::new(STDINOUT_MARKER) {
+            overwrite.check(path)?;
+        }
+    }
 
-    let mut segments: Vec<_> = wasm_file
-        .sections()
-        .filter_map(|s| {
-            let name = s.name().ok()?.to_string();
-            if !args.show_debug_sections && name.starts_with(".debug_") {
-                return None;
-            }
-            let (start, end) = s.file_range()?;
-            Some(Segment {
-                name,
-                start,
-                end,
-                mapped: 0,
-            })
-        })
-        .collect();
+    let provenance = Provenance::new(args.input.as_deref().and_then(input_path), &input_data);
+    let mut options: wasmphobia::Options = (&args).into();
+    options.debug_files = debug_files(&args, &input_data)?;
+    // Read before the analysis, so a broken file doesn't waste it.
+    let annotations = args
+        .annotations
+        .as_deref()
+        .map(Annotations::load)
+        .transpose()?
+        .unwrap_or_default();
+    let events_on_stderr = args.emit_events.as_deref() == Some(Path::new(STDINOUT_MARKER));
+    let stderr_events = events_on_stderr.then(|| NdjsonWriter::new(std::io::stderr()));
+    let file_events = match &args.emit_events {
+        Some(path) if !events_on_stderr => Some(NdjsonWriter::new(AtomicFile::create(path)?)),
+        _ => None,
+    };
+    let checkpoints = match (args.checkpoint_every, &args.output) {
+        (None, _) => None,
+        (Some(every), Some(path)) if path != Path::new(STDINOUT_MARKER) => Some(Checkpoints {
+            path: path.clone(),
+            title: title(&args),
+            format: args.format,
+            size_threshold: args.size_threshold,
+            top: args.top,
+            rendering: rendering(&args),
+            lang: args.lang,
+            provenance: provenance.clone(),
+            every,
+            last: Instant::now(),
+        }),
+        (Some(_), _) => anyhow::bail!("--checkpoint-every needs an output file (--output)"),
+    };
+    let memory_limit = args
+        .memory_limit
+        .or(cfg!(target_family = "wasm").then_some(WASM_MEMORY_LIMIT));
+    if memory_limit.is_some() && memory_used().is_none() {
+        anyhow::bail!("--memory-limit can't measure the memory in use on this platform");
+    }
+    let guard = memory_limit.map(|limit| MemoryGuard { limit });
+    // Events on stderr carry the warnings already.
+    let warnings = (!events_on_stderr).then_some(WarningPrinter { lang: args.lang });
+    let mut observer = (
+        (stderr_events, file_events),
+        (warnings, (checkpoints, guard)),
+    );
+    let mut analysis = wasmphobia::analyze_with_observer(&input_data, &options, &mut observer)?;
+    let ((stderr_events, file_events), _) = observer;
+    if let Some(writer) = stderr_events {
+        writer.finish().context("Writing events")?;
+    }
+    if let Some(writer) = file_events {
+        writer.finish().context("Writing events")?.commit()?;
+    }
+    let total: u64 = analysis.contributors.values().sum();
+    let min_size = [
+        args.min_bytes,
+        args.min_percent
+            .map(|percent| (total as f64 * percent / 100.0).ceil() as u64),
+    ]
+    .into_iter()
+    .flatten()
+    .max();
+    // Budgets go by every crate, however small.
+    let violations = check_budgets(&args, &analysis.contributors)?;
+    if let Some(min_size) = min_size {
+        let folding = group::Folding::new(&analysis.contributors, min_size);
+        analysis.contributors = folding.contributors(&analysis.contributors);
+        analysis.instantiations =
+            folding.instantiations(std::mem::take(&mut analysis.instantiations));
+        if let Some(Uncertainty { low, high }) = &mut analysis.uncertainty {
+            *low = folding.contributors(low);
+            *high = folding.contributors(high);
+        }
+    }
+
+    report::write_reports(
+        &args.report,
+        analysis.parseable(&input_data),
+        &analysis,
+        args.lang,
+        std::io::stderr(),
+    )
+    .context("Writing reports")?;
+
+    let mut file = match &args.output {
+        Some(path) if path != Path::new(STDINOUT_MARKER) => Some(AtomicFile::create(path)?),
+        _ => None,
+    };
+    let mut stdout = std::io::stdout();
+    let output: &mut dyn Write = match &mut file {
+        Some(file) => file,
+        None => &mut stdout,
+    };
+
+    // Unlike the budgets, everything written (and signed) has the root.
+    if let Some(root) = &args.root_frame {
+        analysis.contributors = group::under_root(analysis.contributors, root);
+        analysis.instantiations = group::under_root(analysis.instantiations, root);
+        if let Some(Uncertainty { low, high }) = &mut analysis.uncertainty {
+            *low = group::under_root(std::mem::take(low), root);
+            *high = group::under_root(std::mem::take(high), root);
+        }
+    }
+    let signature = signature(&args, &input_data, &analysis.contributors)?;
+    let lang = args.lang;
+    if let Some(path) = &args.self_contained_html {
+        let metadata = combine_metadata([
+            provenance.metadata(),
+            signature
+                .as_ref()
+                .map(|signature| signature.metadata())
+                .unwrap_or_default(),
+            analysis.metadata.clone(),
+        ]);
+        let tree = size_tree(&analysis, &annotations);
+        let code = disasm::largest_functions(
+            analysis.parseable(&input_data),
+            analysis.address_map(),
+            args.embed_code,
+        )
+        .context("Disassembling the largest functions")?;
+        html::write_self_contained(path, &title(&args), &tree, &code, &metadata)?;
+    }
+    match args.format {
+        Format::Svg => write_svg(args, &analysis, &provenance, signature, output)?,
+        Format::WebpackStats => {
+            let title = title(&args);
+            webpack::write_stats(&title, &analysis.contributors, Some(&provenance), output)
+                .context("Writing webpack stats")?
+        }
+        Format::Json => {
+            let title = title(&args);
+            let metadata = combine_metadata([
+                signature
+                    .as_ref()
+                    .map(|signature| signature.metadata())
+                    .unwrap_or_default(),
+                analysis.metadata.clone(),
+            ]);
+            json::write_json(
+                &title,
+                size_tree(&analysis, &annotations),
+                &metadata,
+                Some(&provenance),
+                output,
+            )
+            .context("Writing JSON")?
+        }
+        Format::Treemap => {
+            let metadata = combine_metadata([
+                provenance.metadata(),
+                signature
+                    .as_ref()
+                    .map(|signature| signature.metadata())
+                    .unwrap_or_default(),
+                analysis.metadata.clone(),
+            ]);
+            let tree = size_tree(&analysis, &annotations);
+            html::write_treemap(&title(&args), &tree, &metadata, output)
+                .context("Writing treemap")?
+        }
+        Format::Folded => flamegraph::write_folded(&analysis.contributors, output)
+            .context("Writing folded stacks")?,
+        Format::Speedscope => {
+            let unit = match args.metric {
+                Metric::DieCount => speedscope::Unit::None,
+                Metric::Bytes | Metric::Gzip => speedscope::Unit::Bytes,
+            };
+            speedscope::write_profile(&title(&args), &analysis.contributors, unit, output)
+                .context("Writing speedscope profile")?
+        }
+        Format::Table => table::write_table(
+            &analysis.contributors,
+            &analysis.instantiations,
+            analysis.uncertainty.as_ref(),
+            args.top,
+            output,
+        )
+        .context("Writing table")?,
+    }
+    // Only now does the output replace an existing file, so a failed run leaves it intact.
+    if let Some(file) = file {
+        file.commit()?;
+    }
+    // The output is written even if budgets are exceeded, to show what grew.
+    if let Some(violations) = violations {
+        ratchet::write_check(&violations, &[], lang, std::io::stderr())?;
+        if !violations.is_empty() {
+            anyhow::bail!("{} sizes exceed their budgets", violations.len());
+        }
+    }
+    Ok(())
+}
+
+/// The `--budget`s the breakdown exceeds, if any were given.
+fn check_budgets(
+    args: &Args,
+    contributors: &Contributors,
+) -> anyhow::Result<Option<Vec<ratchet::Violation>>> {
+    if args.budget.is_empty() {
+        return Ok(None);
+    }
+    let crates = args.budget.iter().any(|limit| limit.crate_name.is_some());
+    if crates && args.group_by == GroupBy::Namespace {
+        anyhow::bail!("Crate budgets need --group-by files or crate");
+    }
+    // Below a root, the directories that name a crate are gone.
+    if crates && args.group_by == GroupBy::Files && !args.root.is_empty() {
+        anyhow::bail!("Crate budgets need the full source paths, without --root");
+    }
+    let sizes = ratchet::CrateSizes::from_contributors(contributors);
+    Ok(Some(
+        ratchet::Budgets::from_limits(&args.budget).check_budgeted(&sizes),
+    ))
+}
 
-    let context = addr2line::Context::new(&wasm_file)?;
+fn write_svg(
+    args: Args,
+    analysis: &wasmphobia::Analysis,
+    provenance: &Provenance,
+    signature: Option<Signature>,
+    output: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let rendering = rendering(&args);
+    let palette_map_path = args.palette_map.clone();
+    let mut palette_map = palette_map_path
+        .as_deref()
+        .map(flamegraph::load_palette_map)
+        .transpose()?;
+    let mut options: flamegraph::Options = args.into();
+    options.palette_map = palette_map.as_mut();
+    let mut notes = vec![format!(
+        "wasmphobia-input: {}",
+        note_fields(&provenance.metadata())
+    )];
+    if let Some(signature) = signature {
+        notes.push(format!(
+            "wasmphobia-signature: {}",
+            note_fields(&signature.metadata())
+        ));
+    }
+    if !analysis.metadata.is_empty() {
+        notes.push(format!(
+            "wasmphobia-analysis: {}",
+            note_fields(&analysis.metadata)
+        ));
+    }
+    options.notes = notes.join("\n");
+    flamegraph::write_flamegraph(&analysis.contributors, &mut options, rendering, output)
+        .context("Rendering flame graph")?;
+    if let (Some(palette_map), Some(path)) = (&palette_map, &palette_map_path) {
+        flamegraph::save_palette_map(palette_map, path)?;
+    }
+
+    Ok(())
+}
+
+fn size(args: &Args, size_args: &SizeArgs) -> anyhow::Result<()> {
+    let input_data = read_input(&size_args.input)?;
+    let analysis = wasmphobia::analyze(&input_data, &args.into())?;
+    let tree = SizeTree::from_contributors(&analysis.contributors);
+
+    let lookup = match (&size_args.symbol, &size_args.path) {
+        (Some(pattern), _) => {
+            tree.lookup_matching(&Regex::new(pattern).context("Invalid --symbol regex")?)
+        }
+        (None, Some(path)) => tree.lookup(path),
+        (None, None) => unreachable!("clap requires a path or --symbol"),
+    };
+    if lookup.matches.is_empty() {
+        anyhow::bail!("Nothing in the breakdown matches");
+    }
 
-    let mut contributors = HashMap::new();
-    let locations: Vec<_> = FallibleIterator::collect(
-        context.find_location_range(0, module_size.try_into().unwrap())?,
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{} bytes", lookup.size)?;
+    writeln!(stdout, "{:>10} {:>10}", "total", "self")?;
+    for found in &lookup.matches {
+        let node = found.node;
+        let path = found.path.join(";");
+        writeln!(stdout, "{:>10} {:>10}  {path}", node.size, node.self_size)?;
+        for child in &node.children {
+            writeln!(
+                stdout,
+                "{:>10} {:>10}    {}",
+                child.size, child.self_size, child.name
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn html_report(args: &Args, report_args: &HtmlReportArgs) -> anyhow::Result<()> {
+    let overwrite = overwrite(args);
+    overwrite.check(&report_args.output.join("index.html"))?;
+    let input_data = read_input(&report_args.input)?;
+    // The report pages need the file and function structure, whatever the grouping flags say.
+    let options = wasmphobia::Options {
+        files_only: false,
+        group_by: GroupBy::Files,
+        ..args.into()
+    };
+    let analysis = wasmphobia::analyze(&input_data, &options)?;
+    // The crate pages are only known now, still check them before writing anything.
+    for path in html::report_files(&report_args.output, &analysis.contributors) {
+        overwrite.check(&path)?;
+    }
+    let title = args.title.clone().unwrap_or_else(|| {
+        let name = report_args.input.file_name().unwrap_or_default();
+        name.to_string_lossy().into_owned()
+    });
+    let provenance = Provenance::new(input_path(&report_args.input), &input_data);
+    let metadata = combine_metadata([
+        provenance.metadata(),
+        signature(args, &input_data, &analysis.contributors)?
+            .map(|signature| signature.metadata())
+            .unwrap_or_default(),
+        analysis.metadata.clone(),
+    ]);
+    let mut palette_map = args
+        .palette_map
+        .as_deref()
+        .map(flamegraph::load_palette_map)
+        .transpose()?;
+    html::write_report(
+        &report_args.output,
+        &title,
+        &analysis.contributors,
+        &metadata,
+        args.size_threshold,
+        rendering(args),
+        palette_map.as_mut(),
     )?;
-    for (map_start, size, loc) in locations.into_iter().rev() {
-        let map_end = map_start + size;
-        let section_name = if let Some(section) = segments
-            .iter_mut()
-            .find(|s| s.start <= map_start && s.end > map_end)
-        {
-            section.mapped += size;
-            section.name.as_str()
-        } else {
-            "<unknown section>"
-        };
-        let file = loc.file.unwrap_or("<unknown file>");
+    if let (Some(palette_map), Some(path)) = (&palette_map, &args.palette_map) {
+        flamegraph::save_palette_map(palette_map, path)?;
+    }
+    Ok(())
+}
+
+fn explain(args: &Args, explain_args: &ExplainArgs) -> anyhow::Result<()> {
+    let input_data = read_input(&explain_args.input)?;
+    let module = parseable(&input_data, args.lang)?;
+    let explanations = explain::explain(module, &explain_args.function)?;
+    if explanations.is_empty() {
+        anyhow::bail!("No function matches {:?}", explain_args.function);
+    }
+    let stdout = std::io::stdout().lock();
+    if explain_args.json {
+        serde_json::to_writer_pretty(stdout, &explanations)?;
+        println!();
+        return Ok(());
+    }
+    explain::write_text(&explanations, stdout)
+}
 
-        let mut key = format!(
-            "@section: {section_name};{}",
-            file.trim_start_matches('/').replace('/', ";")
+fn batch(args: &Args, batch_args: &BatchArgs) -> anyhow::Result<()> {
+    let dir = &batch_args.output;
+    let names = batch::names(&batch_args.inputs);
+    let svgs = names.iter().map(|name| format!("{name}.svg"));
+    for file in svgs.chain(["merged.svg".to_string(), "index.html".to_string()]) {
+        overwrite(args).check(&dir.join(file))?;
+    }
+    let entries = batch::analyze_all(&batch_args.inputs, &args.into())?;
+    std::fs::create_dir_all(dir).with_context(|| format!("Creating {}", dir.display()))?;
+    let mut palette_map = args
+        .palette_map
+        .as_deref()
+        .map(flamegraph::load_palette_map)
+        .transpose()?;
+    for entry in &entries {
+        let mut output = AtomicFile::create(&dir.join(format!("{}.svg", entry.name)))?;
+        let mut options = flamegraph::options(entry.name.as_str(), args.size_threshold);
+        options.palette_map = palette_map.as_mut();
+        options.notes = format!(
+            "wasmphobia-input: {}",
+            note_fields(&entry.provenance.metadata())
         );
+        flamegraph::write_flamegraph(
+            &entry.analysis.contributors,
+            &mut options,
+            rendering(args),
+            &mut output,
+        )
+        .with_context(|| format!("Rendering flame graph for {}", entry.path.display()))?;
+        output.commit()?;
+    }
+    let merged = batch::merge(&entries);
+    let title = args
+        .title
+        .clone()
+        .unwrap_or_else(|| "All modules".to_string());
+    let mut options = flamegraph::options(title, args.size_threshold);
+    options.palette_map = palette_map.as_mut();
+    let mut output = AtomicFile::create(&dir.join("merged.svg"))?;
+    flamegraph::write_flamegraph(&merged, &mut options, rendering(args), &mut output)
+        .context("Rendering merged flame graph")?;
+    output.commit()?;
+    if let (Some(palette_map), Some(path)) = (&palette_map, &args.palette_map) {
+        flamegraph::save_palette_map(palette_map, path)?;
+    }
+    html::write_batch_index(dir, &entries)
+}
 
-        if !args.files_only {
-            let funcs = functions_for_address(&args, &context, map_start)?;
-            key = format!("{key};{}", funcs.join(";"));
-        }
+fn what_if(args: &Args, what_if_args: &WhatIfArgs) -> anyhow::Result<()> {
+    let input_data = read_input(&what_if_args.input)?;
+    let module = parseable(&input_data, args.lang)?;
+    let exports: Vec<_> = what_if_args.exports.iter().map(String::as_str).collect();
+    let savings = reachability::removal_savings(module, &exports)?;
+    let stdout = std::io::stdout().lock();
+    if what_if_args.json {
+        serde_json::to_writer_pretty(stdout, &savings)?;
+        println!();
+        return Ok(());
+    }
+    reachability::write_text(&savings, stdout)
+}
 
-        *contributors.entry(key).or_insert(0) += size;
+fn strings(args: &Args, strings_args: &StringsArgs) -> anyhow::Result<()> {
+    let input_data = read_input(&strings_args.input)?;
+    let module = parseable(&input_data, args.lang)?;
+    let largest = data::largest_strings(module, strings_args.limit)?;
+    let stdout = std::io::stdout().lock();
+    if strings_args.json {
+        serde_json::to_writer_pretty(stdout, &largest)?;
+        println!();
+        return Ok(());
     }
+    data::write_text(&largest, stdout)
+}
 
-    for segment in segments {
-        let key = format!("@section: {};<no mapping info>", segment.name);
-        *contributors.entry(key).or_insert(0) += segment.size() - segment.mapped;
+fn diff(args: &Args, diff_args: &DiffArgs) -> anyhow::Result<()> {
+    // Crates are recognized by their source file paths.
+    let options = wasmphobia::Options {
+        files_only: false,
+        group_by: GroupBy::Files,
+        metric: Metric::Bytes,
+        ..args.into()
+    };
+    let gzip_options = wasmphobia::Options {
+        metric: Metric::Gzip,
+        ..options.clone()
+    };
+    if let Some(path) = &diff_args.svg {
+        overwrite(args).check(path)?;
+    }
+    let analyze = |path: &PathBuf| {
+        let module = read_input(path)?;
+        let bytes = wasmphobia::analyze(&module, &options)?;
+        let gzip = wasmphobia::analyze(&module, &gzip_options)?;
+        let instructions = diff::instruction_counts(bytes.parseable(&module), options.raw_symbols)?;
+        anyhow::Ok((bytes.contributors, gzip.contributors, instructions))
+    };
+    let old = analyze(&diff_args.old)
+        .with_context(|| format!("Analyzing {}", diff_args.old.display()))?;
+    let new = analyze(&diff_args.new)
+        .with_context(|| format!("Analyzing {}", diff_args.new.display()))?;
+    let build = |(bytes, gzip, instructions)| diff::Build {
+        bytes,
+        gzip,
+        instructions,
+    };
+    let diff = diff::diff(
+        &build((&old.0, &old.1, &old.2)),
+        &build((&new.0, &new.1, &new.2)),
+    );
+    if let Some(path) = &diff_args.svg {
+        let title = format!("{} → {}", diff_args.old.display(), diff_args.new.display());
+        let mut options = flamegraph::differential_options(title, args.size_threshold);
+        let mut file = AtomicFile::create(path)?;
+        flamegraph::write_differential(&old.0, &new.0, &mut options, rendering(args), &mut file)
+            .context("Rendering differential flame graph")?;
+        file.commit()?;
     }
+    let stdout = std::io::stdout().lock();
+    if diff_args.json {
+        serde_json::to_writer_pretty(stdout, &diff)?;
+        println!();
+        return Ok(());
+    }
+    diff::write_text(&diff, diff_args.limit, stdout)
+}
 
-    let output: Box<dyn Write> = match &args.output {
-        Some(path) if path != &stdinout_marker => Box::new(std::fs::File::create(path)?),
-        _ => Box::new(std::io::stdout()),
+fn ratchet(args: &Args, ratchet_args: &RatchetArgs) -> anyhow::Result<()> {
+    let input = match &ratchet_args.command {
+        RatchetCommand::Init(init_args) => &init_args.input,
+        RatchetCommand::Check(check_args) => &check_args.input,
+    };
+    let input_data = read_input(input)?;
+    // Crates are recognized by their full source file paths.
+    let options = wasmphobia::Options {
+        files_only: true,
+        group_by: GroupBy::Files,
+        roots: vec![],
+        ..args.into()
     };
+    let analysis = wasmphobia::analyze(&input_data, &options)?;
+    let sizes = ratchet::CrateSizes::from_contributors(&analysis.contributors);
 
-    write_flamegraph(contributors, args.into(), output).context("Rendering flame graph")?;
+    match &ratchet_args.command {
+        RatchetCommand::Init(init_args) => {
+            let budgets = ratchet::Budgets::from_sizes(&sizes, init_args.slack);
+            std::io::stdout().write_all(budgets.to_toml().as_bytes())?;
+        }
+        RatchetCommand::Check(check_args) => {
+            let path = &check_args.budgets;
+            let toml = std::fs::read_to_string(path)
+                .with_context(|| format!("Reading {}", path.display()))?;
+            let budgets = ratchet::Budgets::parse(&toml)
+                .with_context(|| format!("Parsing {}", path.display()))?;
+            let violations = budgets.check(&sizes);
+            let loose = budgets.loose(&sizes);
+            ratchet::write_check(&violations, &loose, args.lang, std::io::stdout())?;
+            if !violations.is_empty() {
+                anyhow::bail!("{} sizes exceed their budgets", violations.len());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Analyzes `input` down to files and functions, and snapshots the sizes.
+fn baseline_snapshot(args: &Args, input: &Path) -> anyhow::Result<baseline::Snapshot> {
+    let module = read_input(input)?;
+    let options = wasmphobia::Options {
+        files_only: false,
+        group_by: GroupBy::Files,
+        ..args.into()
+    };
+    let analysis = wasmphobia::analyze(&module, &options)?;
+    let metric = args
+        .metric
+        .to_possible_value()
+        .context("Metric without a name")?;
+    Ok(baseline::Snapshot::from_contributors(
+        &analysis.contributors,
+        metric.get_name(),
+    ))
+}
 
+fn snapshot(args: &Args, snapshot_args: &SnapshotArgs) -> anyhow::Result<()> {
+    if snapshot_args.output != Path::new(STDINOUT_MARKER) {
+        overwrite(args).check(&snapshot_args.output)?;
+    }
+    let snapshot = baseline_snapshot(args, &snapshot_args.input)?;
+    let mut json = serde_json::to_vec_pretty(&snapshot)?;
+    json.push(b'\n');
+    if snapshot_args.output == Path::new(STDINOUT_MARKER) {
+        std::io::stdout().write_all(&json)?;
+    } else {
+        wasmphobia::output::write(&snapshot_args.output, json)?;
+    }
     Ok(())
 }
 
-fn functions_for_address<R: addr2line::gimli::Reader>(
+fn check(args: &Args, check_args: &CheckArgs) -> anyhow::Result<()> {
+    let path = &check_args.baseline;
+    let json =
+        std::fs::read_to_string(path).with_context(|| format!("Reading {}", path.display()))?;
+    let baseline =
+        baseline::Snapshot::parse(&json).with_context(|| format!("Parsing {}", path.display()))?;
+    let current = baseline_snapshot(args, &check_args.input)?;
+    anyhow::ensure!(
+        baseline.metric == current.metric,
+        "The baseline measures {}, but --metric is {}",
+        baseline.metric,
+        current.metric
+    );
+    let grown = baseline::check(
+        &baseline,
+        &current,
+        check_args.max_growth,
+        check_args.min_bytes,
+    );
+    baseline::write_check(&grown, check_args.max_growth, args.lang, std::io::stdout())?;
+    if !grown.is_empty() {
+        anyhow::bail!("{} sizes grew too much", grown.len());
+    }
+    Ok(())
+}
+
+fn dashboard(args: &Args, dashboard_args: &DashboardArgs) -> anyhow::Result<()> {
+    overwrite(args).check(&dashboard_args.output)?;
+    let snapshots = history::load(&dashboard_args.inputs)?;
+    let title = args
+        .title
+        .clone()
+        .unwrap_or_else(|| "Size history".to_string());
+    html::write_dashboard(
+        &dashboard_args.output,
+        &title,
+        &snapshots,
+        dashboard_args.movers,
+    )
+}
+
+#[cfg(feature = "dev-fixtures")]
+fn gen_fixture(gen_args: &GenFixtureArgs) -> anyhow::Result<()> {
+    use wasmphobia::fixture;
+
+    let Some(name) = &gen_args.name else {
+        for fixture in fixture::FIXTURES {
+            println!("{:<12} {}", fixture.name, fixture.description);
+        }
+        return Ok(());
+    };
+    let fixture = fixture::find(name).with_context(|| format!("No fixture named {name:?}"))?;
+    let module = fixture::generate(fixture)?;
+    if gen_args.output == Path::new(STDINOUT_MARKER) {
+        std::io::stdout().write_all(&module)?;
+    } else {
+        wasmphobia::output::write(&gen_args.output, module)?;
+    }
+    Ok(())
+}
+
+fn signature(
     args: &Args,
-    context: &addr2line::Context<R>,
-    map_start: u64,
-) -> anyhow::Result<Vec<String>> {
-    let funcs: Vec<_> = context
-        .find_frames(map_start)
-        .skip_all_loads()?
-        .filter_map(|frame| {
-            let mut name = if let Some(function) = frame.function {
-                function.name.to_string_lossy()?.to_string()
-            } else {
-                "<Unknown>".to_string()
-            };
-            if !args.raw_symbols {
-                if let Ok(demangled) = rustc_demangle::try_demangle(&name) {
-                    name = demangled.to_string();
-                }
-                if let Ok(demangled) = cpp_demangle::Symbol::new(name.clone()) {
-                    name = demangled.to_string();
-                }
-            }
-            Ok(Some(format!("@function: {name}")))
-        })
-        .collect()?;
-    Ok(funcs)
+    input_data: &[u8],
+    contributors: &Contributors,
+) -> anyhow::Result<Option<Signature>> {
+    if !args.sign && args.sign_key.is_none() {
+        return Ok(None);
+    }
+    let key = args
+        .sign_key
+        .as_deref()
+        .map(signing::load_key)
+        .transpose()?;
+    Ok(Some(Signature::new(input_data, contributors, key.as_ref())))
 }
 
-fn write_flamegraph(
-    contributors: HashMap<String, u64>,
-    mut options: inferno::flamegraph::Options<'_>,
-    mut output: Box<dyn Write>,
-) -> anyhow::Result<()> {
-    let inferno_lines: Vec<_> = contributors
+/// The path of an input file, or none if the input is read from stdin.
+fn input_path(path: &Path) -> Option<&Path> {
+    (path != Path::new(STDINOUT_MARKER)).then_some(path)
+}
+
+/// Concatenates metadata, keeping only the first value of keys that appear more than once (like
+/// the input's hash, which is part of both the provenance and the signature).
+fn combine_metadata<const N: usize>(parts: [Vec<(String, String)>; N]) -> Vec<(String, String)> {
+    let mut seen = std::collections::HashSet::new();
+    parts
         .into_iter()
-        .map(|(key, size)| format!("{} {}", key, size))
+        .flatten()
+        .filter(|(key, _)| seen.insert(key.clone()))
+        .collect()
+}
+
+fn note_fields(metadata: &[(String, String)]) -> String {
+    let fields: Vec<_> = metadata
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
         .collect();
-    inferno::flamegraph::from_lines(
-        &mut options,
-        inferno_lines.iter().map(|v| v.as_str()),
-        &mut output,
-    )?;
+    fields.join("; ")
+}
+
+/// Fails if any input of the command is a URL. wasmphobia only ever reads local files, so this
+/// turns what would be a confusing "file not found" into a clear refusal.
+fn check_offline(args: &Args) -> anyhow::Result<()> {
+    let mut inputs: Vec<&Path> = args.input.iter().map(PathBuf::as_path).collect();
+    match &args.command {
+        Some(Command::Size(SizeArgs { input, .. }))
+        | Some(Command::HtmlReport(HtmlReportArgs { input, .. }))
+        | Some(Command::Explain(ExplainArgs { input, .. }))
+        | Some(Command::WhatIf(WhatIfArgs { input, .. }))
+        | Some(Command::Strings(StringsArgs { input, .. })) => inputs.push(input),
+        Some(Command::Batch(batch_args)) => {
+            inputs.extend(batch_args.inputs.iter().map(PathBuf::as_path))
+        }
+        Some(Command::Diff(diff_args)) => inputs.extend([&*diff_args.old, &*diff_args.new]),
+        Some(Command::Snapshot(snapshot_args)) => inputs.push(&snapshot_args.input),
+        Some(Command::Dashboard(dashboard_args)) => {
+            inputs.extend(dashboard_args.inputs.iter().map(PathBuf::as_path))
+        }
+        Some(Command::Check(check_args)) => {
+            inputs.extend([&*check_args.input, &*check_args.baseline])
+        }
+        Some(Command::Ratchet(ratchet_args)) => match &ratchet_args.command {
+            RatchetCommand::Init(init_args) => inputs.push(&init_args.input),
+            RatchetCommand::Check(check_args) => {
+                inputs.extend([&*check_args.input, &*check_args.budgets])
+            }
+        },
+        #[cfg(feature = "dev-fixtures")]
+        Some(Command::GenFixture(_)) => {}
+        None => {}
+    }
+    inputs.extend(args.palette_map.as_deref());
+    inputs.extend(args.sign_key.as_deref());
+    for input in inputs {
+        let url = input.to_string_lossy();
+        if let Some((scheme, _)) = url.split_once("://") {
+            if !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+            {
+                anyhow::bail!("{url} is a URL, but --offline only allows local files");
+            }
+        }
+    }
     Ok(())
 }
 
+/// The `--debug-file`s, or else the separate debug module that the module's `external_debug_info`
+/// section names, relative to the input file. A missing one is only warned about.
+fn debug_files(args: &Args, input_data: &[u8]) -> anyhow::Result<Vec<DebugFile>> {
+    let read = |path: &Path| -> anyhow::Result<DebugFile> {
+        Ok(DebugFile {
+            name: path.display().to_string(),
+            data: read_input(path)?.into(),
+        })
+    };
+    if !args.debug_file.is_empty() {
+        return args.debug_file.iter().map(|path| read(path)).collect();
+    }
+    let input = args.input.as_deref().and_then(input_path);
+    let mut files = vec![];
+    let external = wasm::external_debug_info(input_data);
+    // A source map is only of use without DWARF.
+    let source_map = match external.is_none() && !wasm::has_dwarf(input_data) {
+        true => wasm::source_mapping_url(input_data),
+        false => None,
+    };
+    for external in external.into_iter().chain(source_map) {
+        let dir = input.and_then(Path::parent).unwrap_or(Path::new(""));
+        let path = dir.join(&external);
+        // URLs are for browser devtools, only files next to the module can be read.
+        if external.contains("://") || !path.is_file() {
+            let message = Message::ExternalDebugInfoMissing { path: external };
+            eprintln!(
+                "{}",
+                Message::Warning {
+                    message: message.text(args.lang)
+                }
+                .text(args.lang)
+            );
+        } else {
+            files.push(read(&path)?);
+        }
+    }
+    // `llvm-dwp` packages the split DWARF of a `-gsplit-dwarf` build as `<module>.dwp`.
+    if let Some(input) = input {
+        let mut package = input.as_os_str().to_owned();
+        package.push(".dwp");
+        let package = PathBuf::from(package);
+        if package.is_file() {
+            files.push(read(&package)?);
+        }
+    }
+    Ok(files)
+}
+
+/// The part of a truncated or corrupted `module` that can be parsed, with a warning on stderr that
+/// the rest is left out, like the analysis does.
+fn parseable(module: &[u8], lang: Lang) -> anyhow::Result<&[u8]> {
+    let (parseable, corruption) = wasmphobia::wasm::parseable_prefix(module)?;
+    if let Some(err) = corruption {
+        let message = Message::Truncated {
+            error: err.to_string(),
+            analyzed: parseable,
+        };
+        let message = message.text(lang);
+        eprintln!("{}", Message::Warning { message }.text(lang));
+    }
+    Ok(&module[..parseable])
+}
+
+fn read_input(path: &Path) -> anyhow::Result<Vec<u8>> {
+    if path == Path::new(STDINOUT_MARKER) {
+        return Ok(read_stdin()?);
+    }
+    std::fs::read(path).with_context(|| format!("Reading {}", path.display()))
+}
+
 fn read_stdin() -> std::io::Result<Vec<u8>> {
     let mut buf = vec![];
     std::io::stdin().read_to_end(&mut buf)?;
     Ok(buf)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use clap::CommandFactory;
+
+    use super::*;
+
+    /// The flags of the web form, as the Vite plugin extracts them, or `None` without Node.js.
+    fn web_flags() -> Option<Vec<serde_json::Value>> {
+        let script = "import { readFileSync } from 'node:fs';
+            import { extractFlags } from './cli-flags.plugin.js';
+            console.log(JSON.stringify(extractFlags(readFileSync('src/main.rs', 'utf8'))));";
+        let output = std::process::Command::new("node")
+            .args(["--input-type=module", "-e", script])
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .output();
+        match output {
+            Ok(output) if output.status.success() => {
+                Some(serde_json::from_slice(&output.stdout).expect("flags as JSON"))
+            }
+            Ok(output) => panic!("{}", String::from_utf8_lossy(&output.stderr)),
+            Err(err) => {
+                eprintln!("Not checking the web form, Node.js isn't available: {err}");
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn web_form_has_every_flag() {
+        let Some(flags) = web_flags() else {
+            return;
+        };
+        let extracted: BTreeSet<_> = flags
+            .iter()
+            .map(|flag| flag["flag"].as_str().expect("flag name").to_string())
+            .collect();
+        // The page reads the module and the output itself.
+        let own = ["input", "output", "help", "version"];
+        let command = Args::command();
+        let expected: BTreeSet<_> = command
+            .get_arguments()
+            .filter_map(|arg| arg.get_long())
+            .filter(|long| !own.contains(long))
+            .map(|long| format!("--{long}"))
+            .collect();
+        assert_eq!(extracted, expected);
+    }
+
+    #[test]
+    fn web_form_switches_are_checkboxes() {
+        let Some(flags) = web_flags() else {
+            return;
+        };
+        let command = Args::command();
+        for arg in command.get_arguments() {
+            let Some(long) = arg.get_long() else {
+                continue;
+            };
+            let name = format!("--{long}");
+            let Some(flag) = flags.iter().find(|flag| flag["flag"] == name.as_str()) else {
+                continue;
+            };
+            let switch = matches!(arg.get_action(), clap::ArgAction::SetTrue);
+            assert_eq!(flag["type"] == "bool", switch, "{name}");
+        }
+    }
+
+    #[test]
+    fn web_form_defaults_parse() {
+        let Some(flags) = web_flags() else {
+            return;
+        };
+        // What web/main.jsx submits for an untouched form.
+        let mut argv = vec!["wasmphobia".to_string()];
+        for flag in &flags {
+            let name = flag["flag"].as_str().expect("flag name");
+            match (flag["type"].as_str(), flag["def"].as_str()) {
+                (Some("bool"), Some("true")) => argv.push(name.to_string()),
+                (Some("bool"), _) => {}
+                (Some("usize"), def) => argv.push(format!("{name}={}", def.unwrap_or_default())),
+                (_, Some(def)) if !def.is_empty() => argv.push(format!("{name}={def}")),
+                _ => {}
+            }
+        }
+        if let Err(err) = Args::try_parse_from(&argv) {
+            panic!("{argv:?}: {err}");
+        }
+    }
+
+    #[test]
+    fn parses_percentages() {
+        assert_eq!(parse_percent("2%"), Ok(2.0));
+        assert_eq!(parse_percent("0.5"), Ok(0.5));
+        assert_eq!(parse_percent("0%"), Ok(0.0));
+        assert!(parse_percent("-1%").is_err());
+        assert!(parse_percent("2 percent").is_err());
+        assert!(parse_percent("%").is_err());
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert!(parse_duration("1.5s").is_err());
+        assert!(parse_duration("2d").is_err());
+        assert!(parse_duration("99999999999999999999h").is_err());
+        assert!(parse_duration(&format!("{}h", u64::MAX / 60)).is_err());
+        assert!(parse_duration(&format!("{}m", u64::MAX)).is_err());
+    }
+}
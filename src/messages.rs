@@ -0,0 +1,440 @@
+//! Catalog of the warnings and advice shown to users, so they can be read in the language of
+//! whoever reviews the sizes. Report headers, table columns and names from the module stay as
+//! they are.
+
+use clap::ValueEnum;
+
+/// Language of warnings and advice.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    /// English.
+    #[default]
+    En,
+    /// German.
+    De,
+    /// Japanese.
+    Ja,
+}
+
+/// A warning or piece of advice, with the values it mentions.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    /// A warning printed on its own, with a prefix.
+    Warning {
+        message: String,
+    },
+    DebugBuild {
+        signals: Vec<String>,
+        savings_kb: u64,
+    },
+    Truncated {
+        error: String,
+        analyzed: usize,
+    },
+    SegmentsUnreadable {
+        error: String,
+    },
+    UnitRangesUnreadable {
+        error: String,
+    },
+    LtoCheckFailed {
+        error: String,
+    },
+    LinelessUnitsUnreadable {
+        error: String,
+    },
+    TooManyRanges {
+        unit: String,
+        max: usize,
+    },
+    NonDefaultSegments {
+        bytes: u64,
+    },
+    OutsideSections {
+        bytes: u64,
+    },
+    NoDebugInfo {
+        functions: usize,
+    },
+    DebugFileWithoutDwarf {
+        file: String,
+    },
+    ExternalDebugInfoMissing {
+        path: String,
+    },
+    SplitDwarfMissing {
+        units: usize,
+    },
+    SourceMapIgnored {
+        file: String,
+    },
+    DuplicateSections {
+        sections: String,
+    },
+    CustomSectionUnparsable {
+        name: String,
+        error: String,
+    },
+    NoCustomSections,
+    NoDuplicates {
+        min_size: usize,
+    },
+    Duplicates {
+        count: usize,
+        savings: u64,
+    },
+    NoExceptionHandling,
+    PoorCoverage {
+        percent: f64,
+    },
+    OverBudget {
+        name: String,
+        size: u64,
+        budget: u64,
+    },
+    NoBudget {
+        name: String,
+        size: u64,
+    },
+    WithinBudgets,
+    LooseBudgets {
+        names: Vec<String>,
+    },
+    Grown {
+        count: usize,
+        max_percent: f64,
+    },
+    NoGrowth {
+        max_percent: f64,
+    },
+    OutOfMemory {
+        limit_mb: u64,
+    },
+    CheckpointFailed {
+        error: String,
+    },
+    NoChecks,
+    OverflowChecks {
+        bytes: u64,
+    },
+    DebugAssertions {
+        bytes: u64,
+    },
+    BoundsChecks {
+        bytes: u64,
+    },
+    StrippableSections {
+        bytes: u64,
+        debug: u64,
+        names: u64,
+    },
+    NoNameSection,
+    NameSectionCost {
+        size: u64,
+        module_size: u64,
+        stripped_size: u64,
+        gzip_size: u64,
+        stripped_gzip_size: u64,
+    },
+    NoFlagSuggestions,
+    OptimizeForSize {
+        flag: String,
+        low: u64,
+        high: u64,
+    },
+    EnableLto {
+        flag: String,
+        low: u64,
+        high: u64,
+    },
+    AbortOnPanic {
+        flag: String,
+        low: u64,
+        high: u64,
+    },
+    TrapOnPanic {
+        flag: String,
+        low: u64,
+        high: u64,
+    },
+    RemoveNameSection {
+        flag: String,
+        bytes: u64,
+    },
+    ShortenNames {
+        flag: String,
+        low: u64,
+        high: u64,
+    },
+    NameStrings {
+        count: usize,
+        bytes: u64,
+        savings: u64,
+    },
+}
+
+impl Message {
+    pub fn text(&self, lang: Lang) -> String {
+        use Lang::*;
+        match self {
+            Message::Warning { message } => match lang {
+                En => format!("Warning: {message}"),
+                De => format!("Warnung: {message}"),
+                Ja => format!("警告: {message}"),
+            },
+            Message::DebugBuild {
+                signals,
+                savings_kb,
+            } => {
+                let signals = signals.join(", ");
+                match lang {
+                    En => format!(
+                        "This looks like a debug build ({signals}), sizes are not representative of a release build. \
+                         An optimized build is likely around {savings_kb} KB smaller."
+                    ),
+                    De => format!(
+                        "Das sieht nach einem Debug-Build aus ({signals}), die Größen sind für einen Release-Build nicht repräsentativ. \
+                         Ein optimierter Build ist wahrscheinlich etwa {savings_kb} KB kleiner."
+                    ),
+                    Ja => format!(
+                        "デバッグビルドのようです（{signals}）。サイズはリリースビルドの参考になりません。\
+                         最適化ビルドはおそらく約 {savings_kb} KB 小さくなります。"
+                    ),
+                }
+            }
+            Message::Truncated { error, analyzed } => match lang {
+                En => format!("The module is truncated or corrupted ({error}), only its first {analyzed} bytes are analyzed"),
+                De => format!("Das Modul ist abgeschnitten oder beschädigt ({error}), nur die ersten {analyzed} Bytes werden analysiert"),
+                Ja => format!("モジュールが途中で切れているか破損しています（{error}）。先頭の {analyzed} バイトのみを解析します"),
+            },
+            Message::SegmentsUnreadable { error } => match lang {
+                En => format!("Could not read address range segments: {error}"),
+                De => format!("Die Segmente der Adressbereiche konnten nicht gelesen werden: {error}"),
+                Ja => format!("アドレス範囲のセグメントを読み取れませんでした: {error}"),
+            },
+            Message::UnitRangesUnreadable { error } => match lang {
+                En => format!("Could not read compilation unit ranges: {error}"),
+                De => format!("Die Adressbereiche der Übersetzungseinheiten konnten nicht gelesen werden: {error}"),
+                Ja => format!("コンパイル単位のアドレス範囲を読み取れませんでした: {error}"),
+            },
+            Message::LtoCheckFailed { error } => match lang {
+                En => format!("Could not check for LTO-merged units: {error}"),
+                De => format!("Durch LTO zusammengeführte Übersetzungseinheiten konnten nicht geprüft werden: {error}"),
+                Ja => format!("LTO で統合されたコンパイル単位を確認できませんでした: {error}"),
+            },
+            Message::LinelessUnitsUnreadable { error } => match lang {
+                En => format!("Could not read the files of units without line info: {error}"),
+                De => format!("Die Dateien der Übersetzungseinheiten ohne Zeileninformationen konnten nicht gelesen werden: {error}"),
+                Ja => format!("行情報のないコンパイル単位のファイルを読み取れませんでした: {error}"),
+            },
+            Message::TooManyRanges { unit, max } => match lang {
+                En => format!("{unit} has more than {max} address ranges, ignoring the rest"),
+                De => format!("{unit} hat mehr als {max} Adressbereiche, der Rest wird ignoriert"),
+                Ja => format!("{unit} には {max} 個を超えるアドレス範囲があります。残りは無視します"),
+            },
+            Message::NonDefaultSegments { bytes } => match lang {
+                En => format!("{bytes} bytes of debug info ranges lie in non-default address segments"),
+                De => format!("{bytes} Bytes der Debug-Info-Bereiche liegen außerhalb des Standard-Adresssegments"),
+                Ja => format!("デバッグ情報の範囲のうち {bytes} バイトがデフォルト以外のアドレスセグメントにあります"),
+            },
+            Message::DuplicateSections { sections } => match lang {
+                En => format!("The module is invalid: sections appear more than once ({sections}); the duplicates are shown as sections of their own, without debug info"),
+                De => format!("Das Modul ist ungültig: Sektionen kommen mehrfach vor ({sections}); die Duplikate werden als eigene Sektionen ohne Debug-Infos angezeigt"),
+                Ja => format!("モジュールが不正です: セクションが重複しています ({sections})。重複分はデバッグ情報なしの独立したセクションとして表示されます"),
+            },
+            Message::NoDebugInfo { functions } => match lang {
+                En => format!("The module has no debug info, its {functions} functions are named after the name section"),
+                De => format!("Das Modul hat keine Debug-Infos, seine {functions} Funktionen sind nach der Name-Sektion benannt"),
+                Ja => format!("モジュールにデバッグ情報がないため、{functions} 個の関数は name セクションの名前で表示されます"),
+            },
+            Message::DebugFileWithoutDwarf { file } => match lang {
+                En => format!("{file} has no debug info, neither DWARF nor split DWARF"),
+                De => format!("{file} enthält keine Debug-Infos, weder DWARF noch Split-DWARF"),
+                Ja => format!("{file} にはデバッグ情報 (DWARF も分割 DWARF も) がありません"),
+            },
+            Message::ExternalDebugInfoMissing { path } => match lang {
+                En => format!("The module's debug info is in {path}, which wasn't found; pass it with --debug-file"),
+                De => format!("Die Debug-Infos des Moduls liegen in {path}, das nicht gefunden wurde; es lässt sich mit --debug-file angeben"),
+                Ja => format!("モジュールのデバッグ情報は {path} にありますが、見つかりませんでした。--debug-file で指定してください"),
+            },
+            Message::SplitDwarfMissing { units } => match lang {
+                En => format!("The split DWARF of {units} units wasn't found, so their functions can't be named; pass their .dwo files or .dwp package with --debug-file"),
+                De => format!("Das Split-DWARF von {units} Einheiten wurde nicht gefunden, ihre Funktionen bleiben daher unbenannt; ihre .dwo-Dateien oder ihr .dwp-Paket lassen sich mit --debug-file angeben"),
+                Ja => format!("{units} 個のユニットの分割 DWARF が見つからないため、関数名を表示できません。.dwo ファイルまたは .dwp パッケージを --debug-file で指定してください"),
+            },
+            Message::SourceMapIgnored { file } => match lang {
+                En => format!("The module has DWARF, which locates code more precisely, so the source map {file} is ignored"),
+                De => format!("Das Modul hat DWARF, das Code genauer zuordnet, daher wird die Source Map {file} ignoriert"),
+                Ja => format!("モジュールにはより正確にコードを特定できる DWARF があるため、ソースマップ {file} は無視されます"),
+            },
+            Message::OutsideSections { bytes } => match lang {
+                En => format!("{bytes} bytes of debug info ranges lie outside of any section"),
+                De => format!("{bytes} Bytes der Debug-Info-Bereiche liegen außerhalb aller Sektionen"),
+                Ja => format!("デバッグ情報の範囲のうち {bytes} バイトがどのセクションにも含まれていません"),
+            },
+            Message::CustomSectionUnparsable { name, error } => match lang {
+                En => format!("Could not parse custom section {name}, treating it as opaque: {error}"),
+                De => format!("Die Custom Section {name} konnte nicht gelesen werden und wird als opak behandelt: {error}"),
+                Ja => format!("カスタムセクション {name} を解析できなかったため、不透明なデータとして扱います: {error}"),
+            },
+            Message::NoCustomSections => match lang {
+                En => "No custom sections were analyzed, give hints with --custom-section.".to_string(),
+                De => "Es wurden keine Custom Sections analysiert, Hinweise lassen sich mit --custom-section geben.".to_string(),
+                Ja => "カスタムセクションは解析されていません。--custom-section でヒントを指定してください。".to_string(),
+            },
+            Message::NoDuplicates { min_size } => match lang {
+                En => format!("No duplicated data of {min_size} bytes or more found."),
+                De => format!("Keine doppelten Daten ab {min_size} Bytes gefunden."),
+                Ja => format!("{min_size} バイト以上の重複データは見つかりませんでした。"),
+            },
+            Message::Duplicates { count, savings } => match lang {
+                En => format!("{count} duplicated blobs, potential savings: {savings} bytes"),
+                De => format!("{count} doppelte Datenblöcke, mögliche Einsparung: {savings} Bytes"),
+                Ja => format!("重複したデータ {count} 個、削減できる可能性: {savings} バイト"),
+            },
+            Message::NoExceptionHandling => match lang {
+                En => "The module doesn't use exception handling.".to_string(),
+                De => "Das Modul verwendet keine Ausnahmebehandlung.".to_string(),
+                Ja => "このモジュールは例外処理を使用していません。".to_string(),
+            },
+            Message::PoorCoverage { percent } => match lang {
+                En => format!("Units with less than {percent:.0}% coverage:"),
+                De => format!("Übersetzungseinheiten mit weniger als {percent:.0}% Abdeckung:"),
+                Ja => format!("カバレッジが {percent:.0}% 未満のコンパイル単位:"),
+            },
+            Message::OverBudget { name, size, budget } => {
+                let over = size - budget;
+                match lang {
+                    En => format!("{name}: {size} bytes, {over} over its budget of {budget} bytes"),
+                    De => format!("{name}: {size} Bytes, {over} über dem Budget von {budget} Bytes"),
+                    Ja => format!("{name}: {size} バイト、予算 {budget} バイトを {over} バイト超過"),
+                }
+            }
+            Message::NoBudget { name, size } => match lang {
+                En => format!("{name}: {size} bytes, but it has no budget"),
+                De => format!("{name}: {size} Bytes, aber ohne Budget"),
+                Ja => format!("{name}: {size} バイト、予算が設定されていません"),
+            },
+            Message::WithinBudgets => match lang {
+                En => "All sizes are within their budgets.".to_string(),
+                De => "Alle Größen liegen innerhalb ihrer Budgets.".to_string(),
+                Ja => "すべてのサイズが予算内です。".to_string(),
+            },
+            Message::LooseBudgets { names } => {
+                let (count, names) = (names.len(), names.join(", "));
+                match lang {
+                    En => format!("{count} budgets could be lowered ({names}), run `wasmphobia ratchet init` again to tighten them."),
+                    De => format!("{count} Budgets könnten gesenkt werden ({names}), `wasmphobia ratchet init` erneut ausführen, um sie zu verschärfen."),
+                    Ja => format!("{count} 個の予算を下げられます（{names}）。`wasmphobia ratchet init` を再実行すると予算が厳しくなります。"),
+                }
+            }
+            Message::Grown { count, max_percent } => match lang {
+                En => format!("{count} sizes grew by more than {max_percent}% since the baseline:"),
+                De => format!("{count} Größen sind seit der Baseline um mehr als {max_percent}% gewachsen:"),
+                Ja => format!("{count} 個のサイズがベースラインから {max_percent}% を超えて増加しました:"),
+            },
+            Message::NoGrowth { max_percent } => match lang {
+                En => format!("Nothing grew by more than {max_percent}% since the baseline."),
+                De => format!("Nichts ist seit der Baseline um mehr als {max_percent}% gewachsen."),
+                Ja => format!("ベースラインから {max_percent}% を超えて増加したものはありません。"),
+            },
+            Message::OutOfMemory { limit_mb } => match lang {
+                En => format!("Analysis truncated due to memory: it used more than {limit_mb} MB, so everything not attributed by then is shown as unmapped"),
+                De => format!("Analyse wegen Speichermangels abgebrochen: Sie hat mehr als {limit_mb} MB belegt, daher wird alles bis dahin nicht Zugeordnete als nicht zugeordnet angezeigt"),
+                Ja => format!("メモリ不足のため解析を打ち切りました。{limit_mb} MB を超えて使用したため、それまでに割り当てられなかった部分はマッピングなしとして表示されます"),
+            },
+            Message::CheckpointFailed { error } => match lang {
+                En => format!("Could not write a checkpoint: {error}"),
+                De => format!("Ein Zwischenstand konnte nicht geschrieben werden: {error}"),
+                Ja => format!("途中経過を書き込めませんでした: {error}"),
+            },
+            Message::NoChecks => match lang {
+                En => "No overflow checks, debug assertions or bounds checks found.".to_string(),
+                De => "Keine Überlaufprüfungen, Debug-Assertions oder Bereichsprüfungen gefunden.".to_string(),
+                Ja => "オーバーフローチェック、デバッグアサーション、境界チェックは見つかりませんでした。".to_string(),
+            },
+            Message::OverflowChecks { bytes } => match lang {
+                En => format!("Building with overflow-checks = false would save about {bytes} bytes."),
+                De => format!("Ein Build mit overflow-checks = false würde etwa {bytes} Bytes sparen."),
+                Ja => format!("overflow-checks = false でビルドすると約 {bytes} バイト削減できます。"),
+            },
+            Message::DebugAssertions { bytes } => match lang {
+                En => format!("Building with debug-assertions = false (or -DNDEBUG for C) would save about {bytes} bytes."),
+                De => format!("Ein Build mit debug-assertions = false (oder -DNDEBUG für C) würde etwa {bytes} Bytes sparen."),
+                Ja => format!("debug-assertions = false（C では -DNDEBUG）でビルドすると約 {bytes} バイト削減できます。"),
+            },
+            Message::BoundsChecks { bytes } => match lang {
+                En => format!("Bounds checks take about {bytes} bytes; they stay in release builds."),
+                De => format!("Bereichsprüfungen belegen etwa {bytes} Bytes; sie bleiben auch in Release-Builds erhalten."),
+                Ja => format!("境界チェックは約 {bytes} バイトを占めます。リリースビルドでも残ります。"),
+            },
+            Message::StrippableSections { bytes, debug, names } => match lang {
+                En => format!("Stripping custom sections would save {bytes} bytes ({debug} bytes of debug info, {names} bytes of names)."),
+                De => format!("Das Entfernen der Custom-Sektionen würde {bytes} Bytes sparen ({debug} Bytes Debug-Infos, {names} Bytes Namen)."),
+                Ja => format!("カスタムセクションを削除すると {bytes} バイト削減できます (デバッグ情報 {debug} バイト、名前 {names} バイト)。"),
+            },
+            Message::NoNameSection => match lang {
+                En => "The module has no name section.".to_string(),
+                De => "Das Modul hat keine Name-Sektion.".to_string(),
+                Ja => "モジュールには name セクションがありません。".to_string(),
+            },
+            Message::NameSectionCost {
+                size,
+                module_size,
+                stripped_size,
+                gzip_size,
+                stripped_gzip_size,
+            } => {
+                let percent = |saved: u64, of: u64| saved as f64 * 100.0 / of.max(1) as f64;
+                let share = percent(*size, *module_size);
+                let gzip_saved = gzip_size.saturating_sub(*stripped_gzip_size);
+                let gzip_share = percent(gzip_saved, *gzip_size);
+                match lang {
+                    En => format!("The name section takes {size} bytes ({share:.1}%). Without it, the module would be {stripped_size} instead of {module_size} bytes, and {stripped_gzip_size} instead of {gzip_size} bytes with gzip ({gzip_saved} bytes, {gzip_share:.1}% less)."),
+                    De => format!("Die Name-Sektion belegt {size} Bytes ({share:.1} %). Ohne sie wäre das Modul {stripped_size} statt {module_size} Bytes groß, mit gzip {stripped_gzip_size} statt {gzip_size} Bytes ({gzip_saved} Bytes, {gzip_share:.1} % weniger)."),
+                    Ja => format!("name セクションは {size} バイト ({share:.1}%) を占めます。削除するとモジュールは {module_size} バイトから {stripped_size} バイトに、gzip 圧縮後は {gzip_size} バイトから {stripped_gzip_size} バイトになります ({gzip_saved} バイト、{gzip_share:.1}% 削減)。"),
+                }
+            }
+            Message::NoFlagSuggestions => match lang {
+                En => "No compiler flags to suggest.".to_string(),
+                De => "Keine Compiler-Flags vorzuschlagen.".to_string(),
+                Ja => "提案できるコンパイラフラグはありません。".to_string(),
+            },
+            Message::OptimizeForSize { flag, low, high } => match lang {
+                En => format!("Optimizing for size with {flag} would likely save {low}-{high} bytes."),
+                De => format!("Eine Optimierung auf Größe mit {flag} würde wahrscheinlich {low}-{high} Bytes sparen."),
+                Ja => format!("{flag} でサイズ優先の最適化をすると {low}〜{high} バイト削減できる見込みです。"),
+            },
+            Message::EnableLto { flag, low, high } => match lang {
+                En => format!("Link-time optimization with {flag} would likely save {low}-{high} bytes."),
+                De => format!("Link-Time-Optimierung mit {flag} würde wahrscheinlich {low}-{high} Bytes sparen."),
+                Ja => format!("{flag} でリンク時最適化を有効にすると {low}〜{high} バイト削減できる見込みです。"),
+            },
+            Message::AbortOnPanic { flag, low, high } => match lang {
+                En => format!("Aborting instead of unwinding with {flag} would likely save {low}-{high} bytes."),
+                De => format!("Abbrechen statt Stack-Unwinding mit {flag} würde wahrscheinlich {low}-{high} Bytes sparen."),
+                Ja => format!("{flag} でアンワインドの代わりに中断すると {low}〜{high} バイト削減できる見込みです。"),
+            },
+            Message::TrapOnPanic { flag, low, high } => match lang {
+                En => format!("Trapping on panics without printing them with {flag} would likely save {low}-{high} bytes."),
+                De => format!("Panics ohne Ausgabe mit {flag} abzubrechen würde wahrscheinlich {low}-{high} Bytes sparen."),
+                Ja => format!("{flag} でパニック時にメッセージを出さずにトラップすると {low}〜{high} バイト削減できる見込みです。"),
+            },
+            Message::RemoveNameSection { flag, bytes } => match lang {
+                En => format!("Removing the name section with {flag} would save {bytes} bytes."),
+                De => format!("Das Entfernen der Namenssektion mit {flag} würde {bytes} Bytes sparen."),
+                Ja => format!("{flag} で名前セクションを削除すると {bytes} バイト削減できます。"),
+            },
+            Message::ShortenNames { flag, low, high } => match lang {
+                En => format!("Shortening import and export names with {flag}, and renaming them in the JS glue, would likely save {low}-{high} bytes."),
+                De => format!("Kürzere Import- und Exportnamen mit {flag}, auch im JS-Glue-Code umbenannt, würden wahrscheinlich {low}-{high} Bytes sparen."),
+                Ja => format!("{flag} でインポート名とエクスポート名を短くし、JS のグルーコードでも名前を変えると {low}〜{high} バイト削減できる見込みです。"),
+            },
+            Message::NameStrings { count, bytes, savings } => match lang {
+                En => format!("The names of {count} imports and exports take up {bytes} bytes; the shortest possible names would save {savings} bytes."),
+                De => format!("Die Namen von {count} Importen und Exporten belegen {bytes} Bytes; die kürzestmöglichen Namen würden {savings} Bytes sparen."),
+                Ja => format!("{count} 個のインポートとエクスポートの名前が {bytes} バイトを占めます。最短の名前にすると {savings} バイト削減できます。"),
+            },
+        }
+    }
+}
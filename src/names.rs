@@ -0,0 +1,115 @@
+use std::io::Write;
+
+use crate::{
+    demangle::Demangler,
+    dwarf::Reader,
+    explain::{outermost_function, strip_disambiguators},
+    group, wasm,
+};
+
+/// Comparison of the function names in the `name` section with the names DWARF gives the same
+/// code. Disagreements usually mean the module was partially stripped or post-processed, and
+/// that the breakdown is less trustworthy than usual.
+#[derive(Clone, Debug, Default)]
+pub struct NameCheck {
+    /// Number of functions that have a name in the `name` section.
+    pub named: usize,
+    /// Functions with a name in the `name` section but no debug info.
+    pub without_dwarf: Vec<NamedFunction>,
+    pub mismatches: Vec<Mismatch>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NamedFunction {
+    pub index: u32,
+    pub name: String,
+    pub size: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Mismatch {
+    pub index: u32,
+    pub name_section: String,
+    pub dwarf: String,
+    pub size: u64,
+}
+
+pub(crate) fn check(
+    module: &[u8],
+    context: &addr2line::Context<Reader>,
+    demangler: &mut Demangler,
+) -> anyhow::Result<NameCheck> {
+    let names = wasm::function_names(module)?;
+    let mut check = NameCheck::default();
+    for function in wasm::functions(module)? {
+        let Some(name) = names.get(&function.index) else {
+            continue;
+        };
+        check.named += 1;
+        let name = demangler.demangle(name).to_string();
+        match outermost_function(context, demangler, function.code_offset)? {
+            None => check.without_dwarf.push(NamedFunction {
+                index: function.index,
+                name,
+                size: function.size,
+            }),
+            Some(dwarf) if !same_function(&name, &dwarf) => check.mismatches.push(Mismatch {
+                index: function.index,
+                name_section: name,
+                dwarf,
+                size: function.size,
+            }),
+            Some(_) => {}
+        }
+    }
+    Ok(check)
+}
+
+/// Whether two demangled names refer to the same function. DWARF may only know the unqualified
+/// name, so only the last path components are compared. Suffixes LLVM appends when it renames
+/// local symbols (`.123`, `.llvm.4567`) don't count as a difference.
+fn same_function(a: &str, b: &str) -> bool {
+    let last = |name: &str| {
+        let mut last = group::namespace_path(&strip_disambiguators(name)).pop()?;
+        while let Some((base, suffix)) = last.rsplit_once('.') {
+            if suffix.is_empty() || !suffix.chars().all(|c| c.is_ascii_digit()) {
+                break;
+            }
+            last = base.strip_suffix(".llvm").unwrap_or(base).to_string();
+        }
+        Some(last)
+    };
+    a == b || last(a) == last(b)
+}
+
+pub fn write_report(check: &NameCheck, mut output: impl Write) -> anyhow::Result<()> {
+    writeln!(output, "== names ==")?;
+    writeln!(
+        output,
+        "{} functions named in the name section, {} without debug info, {} named differently in DWARF",
+        check.named,
+        check.without_dwarf.len(),
+        check.mismatches.len()
+    )?;
+    if !check.without_dwarf.is_empty() {
+        writeln!(output, "Functions without debug info:")?;
+        for function in &check.without_dwarf {
+            writeln!(
+                output,
+                "  {:>10} bytes  #{:<6} {}",
+                function.size, function.index, function.name
+            )?;
+        }
+    }
+    if !check.mismatches.is_empty() {
+        writeln!(output, "Name mismatches (name section / DWARF):")?;
+        for mismatch in &check.mismatches {
+            writeln!(
+                output,
+                "  {:>10} bytes  #{:<6} {}\n                                   {}",
+                mismatch.size, mismatch.index, mismatch.name_section, mismatch.dwarf
+            )?;
+        }
+    }
+    Ok(())
+}
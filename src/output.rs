@@ -0,0 +1,119 @@
+//! Output files that only appear once they are complete, so an interrupted run never leaves a
+//! truncated report behind for CI to upload.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+
+/// A file that is written next to its destination and moved over it by [`AtomicFile::commit`].
+/// If it is dropped without being committed, the destination is left untouched.
+pub struct AtomicFile {
+    path: PathBuf,
+    temp: PathBuf,
+    file: Option<BufWriter<File>>,
+}
+
+impl AtomicFile {
+    pub fn create(path: &Path) -> anyhow::Result<AtomicFile> {
+        // In the same directory, so the rename doesn't cross file systems.
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let temp = path.with_file_name(format!(".{name}.{}.tmp", std::process::id()));
+        let file = File::create(&temp).with_context(|| format!("Writing {}", path.display()))?;
+        Ok(AtomicFile {
+            path: path.to_path_buf(),
+            temp,
+            file: Some(BufWriter::new(file)),
+        })
+    }
+
+    /// Replaces the destination with everything written so far.
+    pub fn commit(mut self) -> anyhow::Result<()> {
+        let file = self.file.take().expect("only taken when committing");
+        let result = persist(file, &self.temp, &self.path);
+        if result.is_err() {
+            // Like an uncommitted file, a failed one leaves nothing behind.
+            let _ = std::fs::remove_file(&self.temp);
+        }
+        result.with_context(|| format!("Writing {}", self.path.display()))
+    }
+}
+
+fn persist(file: BufWriter<File>, temp: &Path, path: &Path) -> std::io::Result<()> {
+    let file = file.into_inner().map_err(|err| err.into_error())?;
+    file.sync_all()?;
+    drop(file);
+    std::fs::rename(temp, path)
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file.as_mut().expect("not committed yet").write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.as_mut().expect("not committed yet").flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if self.file.take().is_some() {
+            let _ = std::fs::remove_file(&self.temp);
+        }
+    }
+}
+
+/// Like [`std::fs::write`], but atomic.
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> anyhow::Result<()> {
+    let mut file = AtomicFile::create(path)?;
+    file.write_all(contents.as_ref())?;
+    file.commit()
+}
+
+/// What to do when an output file already exists.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overwrite {
+    /// Replace it.
+    #[default]
+    Replace,
+    /// Fail before doing any work.
+    Refuse,
+}
+
+impl Overwrite {
+    /// Fails if `path` exists and mustn't be replaced.
+    pub fn check(self, path: &Path) -> anyhow::Result<()> {
+        if self == Overwrite::Refuse && path.exists() {
+            anyhow::bail!(
+                "{} already exists (--no-clobber), use --force to replace it",
+                path.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_commits_leave_nothing_behind() {
+        let dir = std::env::temp_dir().join(format!("wasmphobia-output-{}", std::process::id()));
+        // A file can't replace a directory.
+        let path = dir.join("report.svg");
+        std::fs::create_dir_all(&path).unwrap();
+        let result = write(&path, "<svg/>");
+        let left: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(result.is_err());
+        assert_eq!(left, ["report.svg"]);
+    }
+}
@@ -0,0 +1,92 @@
+use addr2line::gimli;
+use regex::bytes::Regex;
+
+use crate::{
+    dwarf::{self, Reader},
+    messages::{Lang, Message},
+    wasm,
+};
+
+/// Fraction of the code a release build of the same program typically saves. Only meant to give
+/// an idea of the order of magnitude.
+const RELEASE_CODE_SAVINGS: f64 = 0.5;
+
+/// Whether a module looks like an unoptimized debug build, whose sizes say little about the
+/// release build.
+#[derive(Clone, Debug, Default)]
+pub struct BuildProfile {
+    /// What gave the debug build away, e.g. "overflow checks".
+    pub signals: Vec<String>,
+    /// Total size of the function bodies.
+    pub code_size: u64,
+}
+
+impl BuildProfile {
+    pub fn looks_like_debug(&self) -> bool {
+        !self.signals.is_empty()
+    }
+
+    /// Rough estimate of how many bytes of code a release build would save.
+    pub fn estimated_release_savings(&self) -> Option<u64> {
+        if !self.looks_like_debug() {
+            return None;
+        }
+        Some((self.code_size as f64 * RELEASE_CODE_SAVINGS) as u64)
+    }
+
+    /// Warning to show when the module looks like a debug build.
+    pub fn warning(&self, lang: Lang) -> Option<String> {
+        let savings = self.estimated_release_savings()?;
+        let message = Message::DebugBuild {
+            signals: self.signals.clone(),
+            savings_kb: savings / 1000,
+        };
+        Some(message.text(lang))
+    }
+}
+
+pub(crate) fn detect(module: &[u8], dwarf: &gimli::Dwarf<Reader>) -> anyhow::Result<BuildProfile> {
+    let mut signals = vec![];
+
+    // Rust only checks arithmetic for overflow with `overflow-checks`, which is on in debug builds.
+    // The panic functions show up in the name section or as callees in the debug info, and the
+    // messages in the data of older toolchains.
+    let overflow = Regex::new(
+        r"panic_const_(add|sub|mul)_overflow|attempt to (add|subtract|multiply) with overflow",
+    )?;
+    if overflow.is_match(module) {
+        signals.push("overflow checks".to_string());
+    }
+
+    let segments = wasm::data_segments(module)?;
+    let debug_assertion = Regex::new(r"unsafe precondition\(s\) violated")?;
+    if segments
+        .iter()
+        .any(|segment| debug_assertion.is_match(segment.data))
+    {
+        signals.push("debug assertions".to_string());
+    }
+
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        let unit = dwarf.unit(header)?;
+        let mut entries = unit.entries();
+        let Some((_, root)) = entries.next_dfs()? else {
+            continue;
+        };
+        let Some(producer) = root.attr_value(gimli::DW_AT_producer)? else {
+            continue;
+        };
+        let producer = dwarf::attr_string(dwarf, &unit, producer).unwrap_or_default();
+        if producer.split_whitespace().any(|flag| flag == "-O0") {
+            signals.push("compiled with -O0".to_string());
+            break;
+        }
+    }
+
+    let code_size = wasm::functions(module)?
+        .iter()
+        .map(|function| function.size)
+        .sum();
+    Ok(BuildProfile { signals, code_size })
+}
@@ -0,0 +1,71 @@
+use std::{path::Path, time::UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use wasmparser::{BinaryReader, Parser, Payload};
+
+use crate::signing::hex;
+
+/// Identifies the artifact a report describes, so it can be traced back to it later.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// The input path as given, or none for stdin.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub sha256: String,
+    pub size: u64,
+    /// Last modification of the input, in seconds since the Unix epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mtime: Option<u64>,
+    /// The build ID from the module's `build_id` custom section, in hex.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build_id: Option<String>,
+}
+
+impl Provenance {
+    /// Provenance of `module`, read from `path` (or stdin if `path` is none).
+    pub fn new(path: Option<&Path>, module: &[u8]) -> Provenance {
+        let mtime = path
+            .and_then(|path| std::fs::metadata(path).ok()?.modified().ok())
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_secs());
+        Provenance {
+            path: path.map(|path| path.display().to_string()),
+            sha256: hex(&Sha256::digest(module)),
+            size: module.len() as u64,
+            mtime,
+            build_id: build_id(module).map(|id| hex(&id)),
+        }
+    }
+
+    /// Key/value pairs for embedding in report metadata.
+    pub fn metadata(&self) -> Vec<(String, String)> {
+        let mut metadata = vec![];
+        metadata.extend(self.path.clone().map(|path| ("input-path", path)));
+        metadata.push(("input-sha256", self.sha256.clone()));
+        metadata.push(("input-size", self.size.to_string()));
+        metadata.extend(self.mtime.map(|mtime| ("input-mtime", mtime.to_string())));
+        metadata.extend(self.build_id.clone().map(|id| ("build-id", id)));
+        metadata
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect()
+    }
+}
+
+/// Returns the build ID of the module: the byte vector in its `build_id` custom section, as
+/// described in the tool conventions. A malformed section is ignored.
+pub fn build_id(module: &[u8]) -> Option<Vec<u8>> {
+    for payload in Parser::new(0).parse_all(module) {
+        let Ok(Payload::CustomSection(section)) = payload else {
+            continue;
+        };
+        if section.name() != "build_id" {
+            continue;
+        }
+        let mut reader = BinaryReader::new_with_offset(section.data(), section.data_offset());
+        let length = reader.read_var_u32().ok()?;
+        return Some(reader.read_bytes(length as usize).ok()?.to_vec());
+    }
+    None
+}
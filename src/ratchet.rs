@@ -0,0 +1,399 @@
+use std::{collections::BTreeMap, io::Write, str::FromStr};
+
+use anyhow::Context;
+
+use crate::{
+    group,
+    messages::{Lang, Message},
+    Contributors,
+};
+
+/// Total size of a module and the sizes of the Rust crates in it. Crates are named without their
+/// version, so budgets survive upgrades.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CrateSizes {
+    pub total: u64,
+    pub crates: BTreeMap<String, u64>,
+}
+
+impl CrateSizes {
+    /// `contributors` must have been produced with file or crate grouping.
+    pub fn from_contributors(contributors: &Contributors) -> CrateSizes {
+        let mut sizes = CrateSizes::default();
+        for (key, &size) in contributors {
+            sizes.total += size;
+            let frames: Vec<_> = key.split(';').collect();
+            let path: Vec<_> = group::source_frames(&frames)
+                .iter()
+                .take_while(|frame| !frame.starts_with("@function: "))
+                .copied()
+                .collect();
+            let name = match path
+                .first()
+                .and_then(|f| f.strip_prefix(group::CRATE_PREFIX))
+            {
+                Some(name) => Some(name.to_string()),
+                None => group::crate_for_path(&path.join("/")),
+            };
+            let Some(name) = name else {
+                continue;
+            };
+            let name = name.split_once(' ').map_or(name.as_str(), |(name, _)| name);
+            *sizes.crates.entry(name.to_string()).or_default() += size;
+        }
+        sizes
+    }
+}
+
+/// Size budgets for a module and its crates, stored as a small TOML file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Budgets {
+    /// The slack the budgets were generated with, in percent of the sizes at the time.
+    pub slack_percent: f64,
+    pub total: Option<u64>,
+    pub crates: BTreeMap<String, u64>,
+}
+
+/// A crate, or the whole module (`<total>`), that is larger than its budget. Crates without a
+/// budget have appeared since the budgets were generated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub name: String,
+    pub size: u64,
+    pub budget: Option<u64>,
+}
+
+impl Budgets {
+    /// Budgets that allow every crate and the whole module to grow by `slack_percent`.
+    pub fn from_sizes(sizes: &CrateSizes, slack_percent: f64) -> Budgets {
+        let budget = |size: u64| size + (size as f64 * slack_percent / 100.0).ceil() as u64;
+        Budgets {
+            slack_percent,
+            total: Some(budget(sizes.total)),
+            crates: sizes
+                .crates
+                .iter()
+                .map(|(name, &size)| (name.clone(), budget(size)))
+                .collect(),
+        }
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut lines = vec![
+            "# Size budgets in bytes, generated by `wasmphobia ratchet init`.".to_string(),
+            format!("slack_percent = {:?}", self.slack_percent),
+        ];
+        lines.extend(self.total.map(|total| format!("total = {total}")));
+        lines.push(String::new());
+        lines.push("[crates]".to_string());
+        for (name, budget) in &self.crates {
+            lines.push(format!("{} = {budget}", quote(name)));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// Parses the subset of TOML written by [`Budgets::to_toml`]: comments, `key = number` pairs
+    /// with bare or basic-string keys, and a `[crates]` table.
+    pub fn parse(toml: &str) -> anyhow::Result<Budgets> {
+        let mut budgets = Budgets::default();
+        let mut in_crates = false;
+        for (number, line) in toml.lines().enumerate() {
+            let context = || format!("Line {}: {line:?}", number + 1);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(table) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                anyhow::ensure!(table.trim() == "crates", "Unknown table [{table}]");
+                in_crates = true;
+                continue;
+            }
+            let (key, value) = parse_pair(line).with_context(context)?;
+            let value = value.split('#').next().unwrap_or_default().trim();
+            match (in_crates, key.as_str()) {
+                (true, _) => {
+                    let budget = value.replace('_', "").parse().with_context(context)?;
+                    budgets.crates.insert(key, budget);
+                }
+                (false, "slack_percent") => {
+                    budgets.slack_percent = value.parse().with_context(context)?
+                }
+                (false, "total") => {
+                    budgets.total = Some(value.replace('_', "").parse().with_context(context)?)
+                }
+                (false, _) => anyhow::bail!("Unknown key {key:?}"),
+            }
+        }
+        Ok(budgets)
+    }
+
+    /// Budgets given as [`Limit`]s. Later limits for the same crate replace earlier ones.
+    pub fn from_limits(limits: &[Limit]) -> Budgets {
+        let mut budgets = Budgets::default();
+        for limit in limits {
+            match &limit.crate_name {
+                Some(name) => {
+                    budgets.crates.insert(name.clone(), limit.budget);
+                }
+                None => budgets.total = Some(limit.budget),
+            }
+        }
+        budgets
+    }
+
+    /// Returns everything in `sizes` that exceeds its budget, the whole module first.
+    pub fn check(&self, sizes: &CrateSizes) -> Vec<Violation> {
+        self.violations(sizes, true)
+    }
+
+    /// Like [`Budgets::check`], but crates without a budget are fine.
+    pub fn check_budgeted(&self, sizes: &CrateSizes) -> Vec<Violation> {
+        self.violations(sizes, false)
+    }
+
+    fn violations(&self, sizes: &CrateSizes, unbudgeted: bool) -> Vec<Violation> {
+        let mut violations = vec![];
+        if let Some(total) = self.total.filter(|&total| sizes.total > total) {
+            violations.push(Violation {
+                name: "<total>".to_string(),
+                size: sizes.total,
+                budget: Some(total),
+            });
+        }
+        for (name, &size) in &sizes.crates {
+            let budget = self.crates.get(name).copied();
+            let exceeded = match budget {
+                Some(budget) => size > budget,
+                None => unbudgeted,
+            };
+            if exceeded {
+                violations.push(Violation {
+                    name: name.clone(),
+                    size,
+                    budget,
+                });
+            }
+        }
+        violations
+    }
+
+    /// Budgets that could be lowered: even with the slack added, the current size stays below them.
+    pub fn loose(&self, sizes: &CrateSizes) -> Vec<String> {
+        let tight = Budgets::from_sizes(sizes, self.slack_percent);
+        self.crates
+            .iter()
+            .filter(|(name, &budget)| tight.crates.get(*name).is_none_or(|&t| t < budget))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+/// A budget given on the command line: `total=<size>` for the whole module or
+/// `crate:<name>=<size>` for a crate, where the size is in bytes or has a unit like `200KiB` or
+/// `2MB`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Limit {
+    /// The crate, or `None` for the whole module.
+    pub crate_name: Option<String>,
+    pub budget: u64,
+}
+
+impl FromStr for Limit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (target, size) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("expected total=<size> or crate:<name>=<size>, got {s:?}"))?;
+        let crate_name = match target.strip_prefix("crate:") {
+            Some(name) if !name.is_empty() => Some(name.to_string()),
+            _ if target == "total" => None,
+            _ => return Err(format!("expected total or crate:<name>, got {target:?}")),
+        };
+        Ok(Limit {
+            crate_name,
+            budget: parse_size(size)?,
+        })
+    }
+}
+
+/// Parses a size in bytes, with an optional decimal (`KB`, `MB`, `GB`) or binary (`KiB`, `MiB`,
+/// `GiB`) unit.
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let digits = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.' && c != '_')
+        .unwrap_or(s.len());
+    let (value, unit) = s.split_at(digits);
+    let value: f64 = value
+        .replace('_', "")
+        .parse()
+        .map_err(|_| format!("expected a size like \"200KiB\", got {s:?}"))?;
+    let factor = match unit.trim() {
+        "" | "B" => 1,
+        "KB" | "kB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        "KiB" => 1 << 10,
+        "MiB" => 1 << 20,
+        "GiB" => 1 << 30,
+        unit => {
+            return Err(format!(
+                "unknown unit {unit:?}, expected B, KB, KiB, MB, MiB, GB or GiB"
+            ))
+        }
+    };
+    Ok((value * factor as f64).round() as u64)
+}
+
+fn quote(key: &str) -> String {
+    format!("\"{}\"", key.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn parse_pair(line: &str) -> anyhow::Result<(String, &str)> {
+    if let Some(rest) = line.strip_prefix('"') {
+        let mut key = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => key.extend(chars.next().map(|(_, c)| c)),
+                '"' => {
+                    let value = rest[i + 1..].trim_start().strip_prefix('=');
+                    let value = value.context("Expected `=` after the key")?;
+                    return Ok((key, value.trim()));
+                }
+                c => key.push(c),
+            }
+        }
+        anyhow::bail!("Unterminated key");
+    }
+    let (key, value) = line.split_once('=').context("Expected `key = value`")?;
+    Ok((key.trim().to_string(), value.trim()))
+}
+
+pub fn write_check(
+    violations: &[Violation],
+    loose: &[String],
+    lang: Lang,
+    mut output: impl Write,
+) -> anyhow::Result<()> {
+    for violation in violations {
+        let (name, size) = (violation.name.clone(), violation.size);
+        let message = match violation.budget {
+            Some(budget) => Message::OverBudget { name, size, budget },
+            None => Message::NoBudget { name, size },
+        };
+        writeln!(output, "{}", message.text(lang))?;
+    }
+    if violations.is_empty() {
+        writeln!(output, "{}", Message::WithinBudgets.text(lang))?;
+    }
+    if !loose.is_empty() {
+        let message = Message::LooseBudgets {
+            names: loose.to_vec(),
+        };
+        writeln!(output, "{}", message.text(lang))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_comments_quotes_and_underscores() {
+        let toml = r#"
+# Size budgets in bytes.
+slack_percent = 2.5
+total = 1_000_000 # the whole module
+
+[crates]
+serde = 20_000
+"wasm-bindgen" = 3000
+"odd \"name\"" = 1
+"#;
+        let budgets = Budgets::parse(toml).unwrap();
+        assert_eq!(budgets.slack_percent, 2.5);
+        assert_eq!(budgets.total, Some(1_000_000));
+        let crates: Vec<_> = budgets
+            .crates
+            .iter()
+            .map(|(name, &budget)| (name.as_str(), budget))
+            .collect();
+        assert_eq!(
+            crates,
+            [
+                ("odd \"name\"", 1),
+                ("serde", 20_000),
+                ("wasm-bindgen", 3000)
+            ]
+        );
+        assert_eq!(Budgets::parse(&budgets.to_toml()).unwrap(), budgets);
+    }
+
+    #[test]
+    fn looks_for_crates_below_synthetic_frames() {
+        let contributors = Contributors::from([
+            (
+                "@section: data;@data;@libc-runtime;musl;src;string.c".to_string(),
+                10,
+            ),
+            (
+                "@section: code;@compiler-generated;app;src;lib.rs".to_string(),
+                20,
+            ),
+            ("@section: code;app;src;lib.rs".to_string(), 30),
+        ]);
+        let sizes = CrateSizes::from_contributors(&contributors);
+        let crates = BTreeMap::from([("@libc-runtime".to_string(), 10), ("app".to_string(), 50)]);
+        assert_eq!(sizes.crates, crates);
+    }
+
+    #[test]
+    fn rejects_what_it_doesnt_know() {
+        let error = |toml| format!("{:#}", Budgets::parse(toml).unwrap_err());
+        assert_eq!(error("[dependencies]"), "Unknown table [dependencies]");
+        assert_eq!(error("limit = 5"), "Unknown key \"limit\"");
+        assert!(error("[crates]\nserde = 20kB").starts_with("Line 2: "));
+        assert!(error("[crates]\n\"serde = 1").contains("Unterminated key"));
+        assert!(error("total").contains("Expected `key = value`"));
+    }
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(parse_size("1234"), Ok(1234));
+        assert_eq!(parse_size("1_234B"), Ok(1234));
+        assert_eq!(parse_size("200KB"), Ok(200_000));
+        assert_eq!(parse_size("200kB"), Ok(200_000));
+        assert_eq!(parse_size("200KiB"), Ok(204_800));
+        assert_eq!(parse_size("1.5MB"), Ok(1_500_000));
+        assert_eq!(parse_size("1.5 MiB"), Ok(1_572_864));
+        assert_eq!(parse_size("2GiB"), Ok(1 << 31));
+        assert!(parse_size("").is_err());
+        assert!(parse_size("KB").is_err());
+        assert!(parse_size("2TB")
+            .unwrap_err()
+            .contains("unknown unit \"TB\""));
+    }
+
+    #[test]
+    fn parses_limits() {
+        assert_eq!(
+            "total=2MB".parse(),
+            Ok(Limit {
+                crate_name: None,
+                budget: 2_000_000
+            })
+        );
+        assert_eq!(
+            "crate:serde=20KiB".parse(),
+            Ok(Limit {
+                crate_name: Some("serde".to_string()),
+                budget: 20_480
+            })
+        );
+        assert!("crate:=1".parse::<Limit>().is_err());
+        assert!("serde=1".parse::<Limit>().is_err());
+        assert!("total".parse::<Limit>().is_err());
+    }
+}
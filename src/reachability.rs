@@ -0,0 +1,213 @@
+use std::io::Write;
+
+use serde::Serialize;
+use wasmparser::{ElementItems, ElementKind, ExternalKind, Operator, Parser, Payload};
+
+use crate::{demangle::Demangler, wasm};
+
+/// Code that would become unreachable if some exports were removed.
+#[derive(Clone, Debug, Serialize)]
+pub struct Savings {
+    pub removed_exports: Vec<String>,
+    /// Total size of the bodies of the unreachable functions.
+    pub bytes: u64,
+    /// The functions that become unreachable, largest first.
+    pub functions: Vec<UnreachableFunction>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UnreachableFunction {
+    pub index: u32,
+    pub name: Option<String>,
+    pub size: u64,
+}
+
+/// Static call graph of a module.
+#[derive(Default)]
+struct CallGraph {
+    imported_functions: u32,
+    /// Direct callees and `ref.func` targets of every function body, by function index minus
+    /// `imported_functions`.
+    callees: Vec<Vec<u32>>,
+    /// Whether a function body performs an indirect call.
+    calls_indirectly: Vec<bool>,
+    /// Functions placed in tables by active or passive element segments.
+    table_functions: Vec<u32>,
+    exports: Vec<(String, u32)>,
+    exports_table: bool,
+    start: Option<u32>,
+}
+
+impl CallGraph {
+    fn parse(module: &[u8]) -> anyhow::Result<CallGraph> {
+        let mut graph = CallGraph::default();
+        for payload in Parser::new(0).parse_all(module) {
+            match payload? {
+                Payload::ImportSection(reader) => {
+                    for import in reader {
+                        if let wasmparser::TypeRef::Func(_) = import?.ty {
+                            graph.imported_functions += 1;
+                        }
+                    }
+                }
+                Payload::ExportSection(reader) => {
+                    for export in reader {
+                        let export = export?;
+                        match export.kind {
+                            ExternalKind::Func => {
+                                graph.exports.push((export.name.to_string(), export.index))
+                            }
+                            ExternalKind::Table => graph.exports_table = true,
+                            _ => {}
+                        }
+                    }
+                }
+                Payload::StartSection { func, .. } => graph.start = Some(func),
+                Payload::ElementSection(reader) => {
+                    for element in reader {
+                        let element = element?;
+                        if let ElementKind::Declared = element.kind {
+                            continue;
+                        }
+                        match element.items {
+                            ElementItems::Functions(functions) => {
+                                for function in functions {
+                                    graph.table_functions.push(function?);
+                                }
+                            }
+                            ElementItems::Expressions(_, expressions) => {
+                                for expression in expressions {
+                                    for op in expression?.get_operators_reader() {
+                                        if let Operator::RefFunc { function_index } = op? {
+                                            graph.table_functions.push(function_index);
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                Payload::CodeSectionEntry(body) => {
+                    let mut callees = vec![];
+                    let mut calls_indirectly = false;
+                    for op in body.get_operators_reader()? {
+                        match op? {
+                            Operator::Call { function_index }
+                            | Operator::ReturnCall { function_index }
+                            | Operator::RefFunc { function_index } => callees.push(function_index),
+                            Operator::CallIndirect { .. }
+                            | Operator::ReturnCallIndirect { .. }
+                            | Operator::CallRef { .. }
+                            | Operator::ReturnCallRef { .. } => calls_indirectly = true,
+                            _ => {}
+                        }
+                    }
+                    graph.callees.push(callees);
+                    graph.calls_indirectly.push(calls_indirectly);
+                }
+                _ => {}
+            }
+        }
+        Ok(graph)
+    }
+
+    /// Marks every function reachable from `roots`, by function index. Functions in tables are
+    /// considered reachable as soon as any reachable code calls indirectly or a table is exported.
+    fn reachable(&self, roots: impl IntoIterator<Item = u32>) -> Vec<bool> {
+        let total = self.imported_functions as usize + self.callees.len();
+        let mut reachable = vec![false; total];
+        let mut stack: Vec<u32> = roots.into_iter().collect();
+        let mut tables_reachable = false;
+        if self.exports_table {
+            stack.extend(&self.table_functions);
+            tables_reachable = true;
+        }
+        while let Some(function) = stack.pop() {
+            let Some(seen) = reachable.get_mut(function as usize) else {
+                continue;
+            };
+            if std::mem::replace(seen, true) {
+                continue;
+            }
+            let Some(defined) = function.checked_sub(self.imported_functions) else {
+                continue;
+            };
+            let defined = defined as usize;
+            stack.extend(&self.callees[defined]);
+            if self.calls_indirectly[defined] && !tables_reachable {
+                stack.extend(&self.table_functions);
+                tables_reachable = true;
+            }
+        }
+        reachable
+    }
+}
+
+/// Computes which functions would become unreachable, and how many bytes of code that would save,
+/// if the exports named in `exports` were removed from the module.
+///
+/// This is a static estimate: only direct calls are followed precisely, and all functions in
+/// tables are assumed to be reachable as soon as anything reachable calls indirectly.
+pub fn removal_savings(module: &[u8], exports: &[&str]) -> anyhow::Result<Savings> {
+    let graph = CallGraph::parse(module)?;
+    for &export in exports {
+        if !graph.exports.iter().any(|(name, _)| name == export) {
+            anyhow::bail!("The module has no function export named {export:?}");
+        }
+    }
+
+    let roots = |keep: &dyn Fn(&str) -> bool| {
+        graph
+            .exports
+            .iter()
+            .filter(|(name, _)| keep(name))
+            .map(|&(_, index)| index)
+            .chain(graph.start)
+            .collect::<Vec<_>>()
+    };
+    let before = graph.reachable(roots(&|_| true));
+    let after = graph.reachable(roots(&|name| !exports.contains(&name)));
+
+    let names = wasm::function_names(module)?;
+    let mut demangler = Demangler::default();
+    let mut functions: Vec<_> = wasm::functions(module)?
+        .into_iter()
+        .filter(|function| {
+            let index = function.index as usize;
+            before[index] && !after[index]
+        })
+        .map(|function| UnreachableFunction {
+            index: function.index,
+            name: names
+                .get(&function.index)
+                .map(|name| demangler.demangle(name).to_string()),
+            size: function.size,
+        })
+        .collect();
+    functions.sort_by(|a, b| b.size.cmp(&a.size).then(a.index.cmp(&b.index)));
+    Ok(Savings {
+        removed_exports: exports.iter().map(|export| export.to_string()).collect(),
+        bytes: functions.iter().map(|function| function.size).sum(),
+        functions,
+    })
+}
+
+pub fn write_text(savings: &Savings, mut output: impl Write) -> anyhow::Result<()> {
+    writeln!(
+        output,
+        "Removing {} would make {} functions ({} bytes of code) unreachable",
+        savings.removed_exports.join(", "),
+        savings.functions.len(),
+        savings.bytes
+    )?;
+    for function in &savings.functions {
+        writeln!(
+            output,
+            "  {:>10} bytes  #{:<6} {}",
+            function.size,
+            function.index,
+            function.name.as_deref().unwrap_or("<unnamed>")
+        )?;
+    }
+    Ok(())
+}
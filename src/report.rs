@@ -0,0 +1,86 @@
+use std::{io::Write, time::Duration};
+
+use clap::ValueEnum;
+
+use crate::{
+    advice, checks, coverage, custom, duplicates, eh, events::Phase, exports, messages::Lang,
+    names, sections, wasm, Analysis,
+};
+
+/// Additional text reports that can be requested alongside the flame graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Report {
+    /// Identical blobs of static data that appear more than once in the data segments.
+    DuplicatedData,
+    /// Compilation units whose address ranges are poorly covered by line information.
+    Coverage,
+    /// Functions whose name in the name section disagrees with DWARF, or that have no debug info.
+    Names,
+    /// Size, item count and a sample of the custom sections given with `--custom-section`.
+    CustomSections,
+    /// Tags and the functions with exception handlers, with an estimate of their overhead.
+    ExceptionHandling,
+    /// Estimated bytes of overflow checks, debug assertions and bounds checks per crate.
+    Checks,
+    /// Compiler flags that would make the module smaller, per toolchain, with estimated savings.
+    Advice,
+    /// Size of every section, including debug sections, and what stripping would save.
+    Sections,
+    /// The largest import and export names, with the bindings they belong to, and what shorter
+    /// names would save.
+    ExportNames,
+    /// How long each phase of the analysis took.
+    Timings,
+    /// Bytes of the name section by subsection, and the module's size without it, also with gzip.
+    NameSection,
+}
+
+pub fn write_reports(
+    reports: &[Report],
+    module: &[u8],
+    analysis: &Analysis,
+    lang: Lang,
+    mut output: impl Write,
+) -> anyhow::Result<()> {
+    for report in reports {
+        match report {
+            Report::DuplicatedData => {
+                let segments = wasm::data_segments(module)?;
+                duplicates::write_report(&segments, lang, &mut output)?;
+            }
+            Report::Coverage => coverage::write_report(&analysis.units, lang, &mut output)?,
+            Report::Names => names::write_report(&analysis.names, &mut output)?,
+            Report::CustomSections => {
+                custom::write_report(&analysis.custom_sections, lang, &mut output)?
+            }
+            Report::ExceptionHandling => eh::write_report(module, lang, &mut output)?,
+            Report::Checks => checks::write_report(module, lang, &mut output)?,
+            Report::Advice => advice::write_report(module, lang, &mut output)?,
+            Report::Sections => sections::write_report(module, lang, &mut output)?,
+            Report::ExportNames => exports::write_report(module, lang, &mut output)?,
+            Report::Timings => write_timings(&analysis.phases, &mut output)?,
+            Report::NameSection => sections::write_name_report(module, lang, &mut output)?,
+        }
+    }
+    Ok(())
+}
+
+fn write_timings(phases: &[(Phase, Duration)], mut output: impl Write) -> anyhow::Result<()> {
+    writeln!(output, "== timings ==")?;
+    for (phase, elapsed) in phases {
+        writeln!(
+            output,
+            "  {:<10} {:>8.1} ms",
+            phase.name(),
+            elapsed.as_secs_f64() * 1000.0
+        )?;
+    }
+    let total: Duration = phases.iter().map(|(_, elapsed)| *elapsed).sum();
+    writeln!(
+        output,
+        "  {:<10} {:>8.1} ms",
+        "total",
+        total.as_secs_f64() * 1000.0
+    )?;
+    Ok(())
+}
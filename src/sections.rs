@@ -0,0 +1,331 @@
+//! Sizes of the module's sections, to see how much of it is code, data, debug info or names, and
+//! how much stripping would save.
+
+use std::{collections::HashMap, io::Write, ops::Range};
+
+use wasmparser::BinaryReader;
+
+use crate::{
+    compressed,
+    messages::{Lang, Message},
+};
+
+/// What a section holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Kind {
+    Code,
+    Data,
+    /// Types, imports, exports, element segments and the other sections that declare the module's
+    /// structure.
+    Declarations,
+    /// DWARF sections (`.debug_*`) and source maps.
+    Debug,
+    /// The `name` section.
+    Names,
+    /// All other custom sections, like `producers` or `target_features`.
+    Custom,
+}
+
+impl Kind {
+    fn of(name: &str) -> Kind {
+        match name {
+            "<code>" => Kind::Code,
+            "<data>" | "<strings>" => Kind::Data,
+            "name" => Kind::Names,
+            "sourceMappingURL" | "external_debug_info" => Kind::Debug,
+            _ if name.starts_with(".debug_") => Kind::Debug,
+            _ if name.starts_with('<') => Kind::Declarations,
+            _ => Kind::Custom,
+        }
+    }
+
+    /// Whether the section can be removed without changing what the module does.
+    pub fn strippable(self) -> bool {
+        matches!(self, Kind::Debug | Kind::Names | Kind::Custom)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Kind::Code => "code",
+            Kind::Data => "data",
+            Kind::Declarations => "declarations",
+            Kind::Debug => "debug",
+            Kind::Names => "names",
+            Kind::Custom => "custom",
+        }
+    }
+}
+
+/// One section of the module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Section {
+    /// Name as in the breakdown's `@section: ` frames: `<code>`, `<type>` and so on for known
+    /// sections, the name of custom sections. Known sections that appear more than once, which is
+    /// invalid, are numbered from the second one on, like `<code>#2`.
+    pub name: String,
+    pub kind: Kind,
+    /// Size including the section's id, size prefix and, for custom sections, name.
+    pub size: u64,
+    /// Position of the section's contents (after the size prefix) in the file.
+    pub contents: Range<u64>,
+    /// How many known sections with the same id came before this one, plus one. Always 1 for
+    /// custom sections, which may repeat.
+    pub occurrence: u32,
+}
+
+impl Section {
+    /// Whether this is a known section that already appeared before.
+    pub fn is_duplicate(&self) -> bool {
+        self.occurrence > 1
+    }
+}
+
+/// Returns the sections of `module` in order. Parsing stops at the first malformed section
+/// header.
+pub fn sections(module: &[u8]) -> Vec<Section> {
+    let mut sections = vec![];
+    let mut occurrences: HashMap<u8, u32> = HashMap::new();
+    let mut reader = BinaryReader::new(module);
+    if reader.read_bytes(8).is_err() {
+        return sections;
+    }
+    while !reader.eof() {
+        let start = reader.original_position();
+        let Ok((id, size)) = reader
+            .read_u8()
+            .and_then(|id| Ok((id, reader.read_var_u32()?)))
+        else {
+            break;
+        };
+        let contents = reader.original_position();
+        let (name, occurrence) = match id {
+            0 => match reader.read_string() {
+                Ok(name) => (name.to_string(), 1),
+                Err(_) => break,
+            },
+            _ => {
+                let occurrence = occurrences.entry(id).or_default();
+                *occurrence += 1;
+                (format!("<{}>", known_section_name(id)), *occurrence)
+            }
+        };
+        let end = contents + size as usize;
+        if end > module.len() {
+            break;
+        }
+        reader = BinaryReader::new_with_offset(&module[end..], end);
+        let kind = Kind::of(&name);
+        sections.push(Section {
+            name: match occurrence {
+                1 => name,
+                n => format!("{name}#{n}"),
+            },
+            kind,
+            size: (end - start) as u64,
+            contents: contents as u64..end as u64,
+            occurrence,
+        });
+    }
+    sections
+}
+
+/// Copy of `module` in which the duplicate known sections are disguised as custom sections with
+/// an empty name, so that parsers rejecting duplicates can read the rest. The size of every section
+/// stays the same, and so does every offset. Empty duplicates have no room for a name and are left
+/// as they are.
+pub fn hide_duplicates(module: &[u8], sections: &[Section]) -> Vec<u8> {
+    let mut hidden = module.to_vec();
+    for section in sections {
+        if !section.is_duplicate() || section.contents.is_empty() {
+            continue;
+        }
+        let start = (section.contents.end - section.size) as usize;
+        hidden[start] = 0;
+        hidden[section.contents.start as usize] = 0;
+    }
+    hidden
+}
+
+fn known_section_name(id: u8) -> &'static str {
+    match id {
+        1 => "type",
+        2 => "import",
+        3 => "function",
+        4 => "table",
+        5 => "memory",
+        6 => "global",
+        7 => "export",
+        8 => "start",
+        9 => "element",
+        10 => "code",
+        11 => "data",
+        12 => "data_count",
+        13 => "tag",
+        // String literals of the stringref proposal.
+        14 => "strings",
+        _ => "unknown",
+    }
+}
+
+/// What the `name` section costs, for deciding whether to ship it, e.g. for readable stack traces
+/// in error reports.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameCost {
+    /// Bytes of every subsection (like `functions` or `locals`) with its header, in the order
+    /// they appear. The section's own header is `header`.
+    pub subsections: Vec<(&'static str, u64)>,
+    /// Size of the name section, or of all of them if there are several.
+    pub size: u64,
+    pub module_size: u64,
+    /// Size of the module without the name section.
+    pub stripped_size: u64,
+    /// Sizes of the module with and without the name section, compressed with gzip.
+    pub gzip_size: u64,
+    pub stripped_gzip_size: u64,
+}
+
+/// Measures the name section and the module without it, with and without compression.
+pub fn name_cost(module: &[u8]) -> anyhow::Result<NameCost> {
+    let mut subsections: Vec<(&'static str, u64)> = vec![];
+    let mut add = |name, size| match subsections.iter_mut().find(|(n, _)| *n == name) {
+        Some((_, total)) => *total += size,
+        None => subsections.push((name, size)),
+    };
+    let mut stripped = Vec::with_capacity(module.len());
+    let mut copied = 0;
+    for section in sections(module) {
+        if section.kind != Kind::Names {
+            continue;
+        }
+        let start = section.contents.end - section.size;
+        stripped.extend_from_slice(&module[copied..start as usize]);
+        copied = section.contents.end as usize;
+        let contents = &module[section.contents.start as usize..copied];
+        let mut reader = BinaryReader::new_with_offset(contents, section.contents.start as usize);
+        // The section name was already read once.
+        reader.read_string()?;
+        add("header", reader.original_position() as u64 - start);
+        while !reader.eof() {
+            let subsection_start = reader.original_position();
+            let Ok((id, size)) = reader
+                .read_u8()
+                .and_then(|id| Ok((id, reader.read_var_u32()?)))
+            else {
+                add(
+                    "<malformed>",
+                    section.contents.end - subsection_start as u64,
+                );
+                break;
+            };
+            if reader.read_bytes(size as usize).is_err() {
+                add(
+                    "<malformed>",
+                    section.contents.end - subsection_start as u64,
+                );
+                break;
+            }
+            add(
+                name_subsection(id),
+                (reader.original_position() - subsection_start) as u64,
+            );
+        }
+    }
+    stripped.extend_from_slice(&module[copied..]);
+    let module_size = module.len() as u64;
+    let stripped_size = stripped.len() as u64;
+    let gzip_size = compressed::gzip_size(module)?;
+    // Without a name section, there is nothing to compress twice.
+    let stripped_gzip_size = match stripped_size == module_size {
+        true => gzip_size,
+        false => compressed::gzip_size(&stripped)?,
+    };
+    Ok(NameCost {
+        subsections,
+        size: module_size - stripped_size,
+        module_size,
+        stripped_size,
+        gzip_size,
+        stripped_gzip_size,
+    })
+}
+
+/// Name of the name section's subsection `id`, from the extended name section proposal.
+fn name_subsection(id: u8) -> &'static str {
+    match id {
+        0 => "module",
+        1 => "functions",
+        2 => "locals",
+        3 => "labels",
+        4 => "types",
+        5 => "tables",
+        6 => "memories",
+        7 => "globals",
+        8 => "element segments",
+        9 => "data segments",
+        10 => "fields",
+        11 => "tags",
+        _ => "<unknown>",
+    }
+}
+
+pub fn write_name_report(module: &[u8], lang: Lang, mut output: impl Write) -> anyhow::Result<()> {
+    writeln!(output, "== name-section ==")?;
+    let cost = name_cost(module)?;
+    if cost.size == 0 {
+        writeln!(output, "{}", Message::NoNameSection.text(lang))?;
+        return Ok(());
+    }
+    writeln!(output, "  {:>10}  subsection", "bytes")?;
+    for (name, size) in &cost.subsections {
+        writeln!(output, "  {size:>10}  {name}")?;
+    }
+    let message = Message::NameSectionCost {
+        size: cost.size,
+        module_size: cost.module_size,
+        stripped_size: cost.stripped_size,
+        gzip_size: cost.gzip_size,
+        stripped_gzip_size: cost.stripped_gzip_size,
+    };
+    writeln!(output, "{}", message.text(lang))?;
+    Ok(())
+}
+
+pub fn write_report(module: &[u8], lang: Lang, mut output: impl Write) -> anyhow::Result<()> {
+    writeln!(output, "== sections ==")?;
+    let sections = sections(module);
+    let total = module.len().max(1) as f64;
+    writeln!(
+        output,
+        "  {:>10} {:>7}  {:<12}  section",
+        "bytes", "%", "kind"
+    )?;
+    for section in &sections {
+        writeln!(
+            output,
+            "  {:>10} {:>6.1}%  {:<12}  {}",
+            section.size,
+            section.size as f64 * 100.0 / total,
+            section.kind.label(),
+            section.name
+        )?;
+    }
+    let size = |kind| -> u64 {
+        sections
+            .iter()
+            .filter(|section| section.kind == kind)
+            .map(|section| section.size)
+            .sum()
+    };
+    let strippable: u64 = sections
+        .iter()
+        .filter(|section| section.kind.strippable())
+        .map(|section| section.size)
+        .sum();
+    let message = Message::StrippableSections {
+        bytes: strippable,
+        debug: size(Kind::Debug),
+        names: size(Kind::Names),
+    };
+    writeln!(output, "{}", message.text(lang))?;
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use std::{fmt::Write as _, path::Path};
+
+use anyhow::Context;
+use ed25519_dalek::{Signer, SigningKey};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::Contributors;
+
+/// Ties a size report to the exact input it was generated from.
+///
+/// `breakdown_sha256` covers the attributed sizes, so a signed report can't be edited after the
+/// fact. With a key, the signature is an Ed25519 signature over
+/// `wasmphobia-signature-v1\n<input_sha256>\n<breakdown_sha256>\n`.
+#[derive(Clone, Debug, Serialize)]
+pub struct Signature {
+    pub input_sha256: String,
+    pub breakdown_sha256: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl Signature {
+    pub fn new(module: &[u8], contributors: &Contributors, key: Option<&SigningKey>) -> Signature {
+        let input_sha256 = hex(&Sha256::digest(module));
+        let breakdown_sha256 = breakdown_digest(contributors);
+        let message = format!("wasmphobia-signature-v1\n{input_sha256}\n{breakdown_sha256}\n");
+        Signature {
+            public_key: key.map(|key| hex(key.verifying_key().as_bytes())),
+            signature: key.map(|key| hex(&key.sign(message.as_bytes()).to_bytes())),
+            input_sha256,
+            breakdown_sha256,
+        }
+    }
+
+    /// Key/value pairs for embedding in report metadata.
+    pub fn metadata(&self) -> Vec<(String, String)> {
+        let mut metadata = vec![
+            ("input-sha256".to_string(), self.input_sha256.clone()),
+            (
+                "breakdown-sha256".to_string(),
+                self.breakdown_sha256.clone(),
+            ),
+        ];
+        if let (Some(public_key), Some(signature)) = (&self.public_key, &self.signature) {
+            metadata.push(("public-key".to_string(), public_key.clone()));
+            metadata.push(("signature".to_string(), signature.clone()));
+        }
+        metadata
+    }
+}
+
+/// Loads an Ed25519 private key from a file containing the 32-byte seed, either raw or as 64 hex
+/// digits.
+pub fn load_key(path: &Path) -> anyhow::Result<SigningKey> {
+    let data = std::fs::read(path).with_context(|| format!("Reading {}", path.display()))?;
+    let seed: [u8; 32] = match data.len() {
+        32 => data.try_into().unwrap(),
+        _ => {
+            let text = std::str::from_utf8(&data).context("Key is neither raw nor hex")?;
+            parse_hex(text.trim())
+                .and_then(|bytes| bytes.try_into().ok())
+                .context("Key must be a 32-byte Ed25519 seed (raw or 64 hex digits)")?
+        }
+    };
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// Hash over all contributors, independent of their order.
+fn breakdown_digest(contributors: &Contributors) -> String {
+    let mut lines: Vec<_> = contributors.iter().collect();
+    lines.sort();
+    let mut hasher = Sha256::new();
+    for (key, size) in lines {
+        hasher.update(format!("{key} {size}\n"));
+    }
+    hex(&hasher.finalize())
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut s, b| {
+        let _ = write!(s, "{b:02x}");
+        s
+    })
+}
+
+fn parse_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
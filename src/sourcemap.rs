@@ -0,0 +1,163 @@
+//! Source maps (`.wasm.map`), which AssemblyScript and some emscripten builds ship instead of
+//! DWARF. For wasm, a source map has a single generated line, and the generated column of every
+//! mapping is an offset in the module file.
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+
+/// The fields of a source map that locate code.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Json {
+    version: u32,
+    #[serde(default)]
+    source_root: Option<String>,
+    #[serde(default)]
+    sources: Vec<Option<String>>,
+    mappings: String,
+}
+
+/// Where code at an offset of the module comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mapping {
+    /// Offset in the module file where the mapped code starts.
+    pub offset: u64,
+    /// Index into [`SourceMap::sources`], or `None` for code the map explicitly leaves unmapped.
+    pub source: Option<usize>,
+    /// Line in the source, starting at 1.
+    pub line: u32,
+    /// Column in the source, starting at 1.
+    pub column: u32,
+}
+
+/// A parsed source map.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    /// Paths of the source files, with the source root prepended and `.` and `..` components
+    /// resolved.
+    pub sources: Vec<String>,
+    /// Mappings, sorted by offset.
+    pub mappings: Vec<Mapping>,
+}
+
+impl SourceMap {
+    /// Whether `data` looks like a source map rather than a binary, i.e. like a JSON object.
+    pub fn detect(data: &[u8]) -> bool {
+        data.iter()
+            .find(|byte| !byte.is_ascii_whitespace())
+            .is_some_and(|&byte| byte == b'{')
+    }
+
+    pub fn parse(data: &[u8]) -> anyhow::Result<SourceMap> {
+        let json: Json = serde_json::from_slice(data)?;
+        if json.version != 3 {
+            bail!("Unsupported source map version {}", json.version);
+        }
+        let root = json.source_root.unwrap_or_default();
+        let sources = json
+            .sources
+            .into_iter()
+            .map(|source| resolve(&root, source.as_deref().unwrap_or("<unknown file>")))
+            .collect();
+        let mut mappings = decode(&json.mappings).context("Decoding the source map's mappings")?;
+        mappings.sort_by_key(|mapping| mapping.offset);
+        Ok(SourceMap { sources, mappings })
+    }
+
+    /// The path of the source a mapping comes from.
+    pub fn source(&self, mapping: &Mapping) -> Option<&str> {
+        self.sources.get(mapping.source?).map(String::as_str)
+    }
+}
+
+/// Joins `source` to `root` and resolves `.` and `..` components. `..` components that lead out
+/// of the root are dropped, as the map's location isn't part of the breakdown.
+fn resolve(root: &str, source: &str) -> String {
+    let path = match root.is_empty() || source.starts_with('/') || source.contains("://") {
+        true => source.to_string(),
+        false => format!("{}/{source}", root.trim_end_matches('/')),
+    };
+    let mut components: Vec<&str> = vec![];
+    for component in path.split('/') {
+        match component {
+            "." => {}
+            ".." => {
+                components.pop();
+            }
+            _ => components.push(component),
+        }
+    }
+    components.join("/")
+}
+
+/// Decodes the first line of `mappings`; wasm has no other lines.
+fn decode(mappings: &str) -> anyhow::Result<Vec<Mapping>> {
+    let first = mappings.split(';').next().unwrap_or_default();
+    let mut decoded = vec![];
+    // All fields but the generated column are relative to the previous segment's, even across
+    // segments without them.
+    let (mut offset, mut source, mut line, mut column) = (0i64, 0i64, 0i64, 0i64);
+    for segment in first.split(',').filter(|segment| !segment.is_empty()) {
+        let fields = vlq(segment)?;
+        offset += fields[0];
+        let mapped = match fields.len() {
+            1 => false,
+            4 | 5 => {
+                source += fields[1];
+                line += fields[2];
+                column += fields[3];
+                true
+            }
+            count => bail!("Source map segment {segment:?} has {count} fields"),
+        };
+        decoded.push(Mapping {
+            offset: u64::try_from(offset).context("Negative offset")?,
+            source: match mapped {
+                true => Some(usize::try_from(source).context("Negative source index")?),
+                false => None,
+            },
+            line: u32::try_from(line + 1).unwrap_or_default(),
+            column: u32::try_from(column + 1).unwrap_or_default(),
+        });
+    }
+    Ok(decoded)
+}
+
+/// Decodes the base64 VLQ values of a segment.
+fn vlq(segment: &str) -> anyhow::Result<Vec<i64>> {
+    let mut values = vec![];
+    let (mut value, mut shift) = (0i64, 0);
+    for byte in segment.bytes() {
+        let digit = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => bail!(
+                "Invalid character {:?} in source map mappings",
+                byte as char
+            ),
+        } as i64;
+        if shift > 60 {
+            bail!("Source map value too large in {segment:?}");
+        }
+        value |= (digit & 0b11111) << shift;
+        shift += 5;
+        // The continuation bit.
+        if digit & 0b100000 == 0 {
+            // The lowest bit is the sign.
+            let magnitude = value >> 1;
+            values.push(if value & 1 == 1 {
+                -magnitude
+            } else {
+                magnitude
+            });
+            (value, shift) = (0, 0);
+        }
+    }
+    if shift > 0 {
+        bail!("Truncated source map value in {segment:?}");
+    }
+    Ok(values)
+}
@@ -0,0 +1,107 @@
+use std::{collections::HashMap, io::Write};
+
+use serde::Serialize;
+
+use crate::Contributors;
+
+const SCHEMA: &str = "https://www.speedscope.app/file-format-schema.json";
+
+/// A profile in speedscope's file format, with the breakdown as a single "sampled" profile: every
+/// breakdown entry is a sample whose stack is its frames and whose weight is its size. Speedscope's
+/// left heavy and sandwich views then work on sizes as they do on time.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct File {
+    #[serde(rename = "$schema")]
+    pub schema: &'static str,
+    pub name: String,
+    pub exporter: String,
+    pub active_profile_index: usize,
+    pub shared: Shared,
+    pub profiles: Vec<Profile>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Shared {
+    pub frames: Vec<Frame>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Frame {
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub name: String,
+    pub unit: Unit,
+    pub start_value: u64,
+    pub end_value: u64,
+    /// Stacks as indices into [`Shared::frames`], outermost frame first.
+    pub samples: Vec<Vec<usize>>,
+    pub weights: Vec<u64>,
+}
+
+/// What the weights of a profile count.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Unit {
+    Bytes,
+    /// Plain counts, like debug info entries.
+    None,
+}
+
+/// Builds a speedscope profile called `name` from `contributors`.
+pub fn profile(name: &str, contributors: &Contributors, unit: Unit) -> File {
+    let mut entries: Vec<_> = contributors.iter().collect();
+    entries.sort();
+    let mut frames = vec![];
+    let mut indices: HashMap<&str, usize> = HashMap::new();
+    let mut samples = vec![];
+    let mut weights = vec![];
+    for (key, &size) in entries {
+        let stack = key
+            .split(';')
+            .map(|frame| {
+                *indices.entry(frame).or_insert_with(|| {
+                    frames.push(Frame {
+                        name: frame.to_string(),
+                    });
+                    frames.len() - 1
+                })
+            })
+            .collect();
+        samples.push(stack);
+        weights.push(size);
+    }
+    File {
+        schema: SCHEMA,
+        name: name.to_string(),
+        exporter: format!("wasmphobia {}", env!("CARGO_PKG_VERSION")),
+        active_profile_index: 0,
+        shared: Shared { frames },
+        profiles: vec![Profile {
+            kind: "sampled",
+            name: name.to_string(),
+            unit,
+            start_value: 0,
+            end_value: weights.iter().sum(),
+            samples,
+            weights,
+        }],
+    }
+}
+
+pub fn write_profile(
+    name: &str,
+    contributors: &Contributors,
+    unit: Unit,
+    mut output: impl Write,
+) -> anyhow::Result<()> {
+    serde_json::to_writer(&mut output, &profile(name, contributors, unit))?;
+    writeln!(output)?;
+    Ok(())
+}
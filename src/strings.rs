@@ -0,0 +1,126 @@
+//! Finds the bytes a module spends on strings with the string proposals, so early adopters can
+//! compare them with what their current string encoding costs:
+//!
+//! - JS string builtins, imported from the `wasm:js-string` module,
+//! - string constants imported as globals from the `'` module, where the import's name is the
+//!   string itself (the JS API's `importedStringConstants`),
+//! - the literals in the string literals section (id 14) of the stringref proposal, which
+//!   [`crate::sections`] shows as `<strings>`.
+
+use std::ops::Range;
+
+use wasmparser::{BinaryReader, Parser, Payload, TypeRef};
+
+/// Module that JS string builtins are imported from.
+const BUILTINS_MODULE: &str = "wasm:js-string";
+/// Module that string constants are imported from, by convention.
+const CONSTANTS_MODULE: &str = "'";
+/// Id of the string literals section.
+const LITERALS_SECTION: u8 = 14;
+
+/// Strings longer than this (in characters) are shortened in their frame.
+const MAX_FRAME_CHARS: usize = 60;
+
+/// How a string gets into the module.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// An import of a JS string builtin, named after the builtin.
+    Builtin,
+    /// An imported string constant.
+    Constant,
+    /// A literal of the string literals section.
+    Literal,
+}
+
+impl Kind {
+    /// Frame that strings of this kind are grouped under, below their section.
+    pub fn frame(self) -> &'static str {
+        match self {
+            Kind::Builtin => "@js-string-builtins",
+            Kind::Constant => "@string-constants",
+            Kind::Literal => "@string-literals",
+        }
+    }
+}
+
+/// A string, or a builtin, and the bytes it takes up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StringEntry {
+    pub kind: Kind,
+    /// The builtin's name, or the string.
+    pub name: String,
+    /// Position of the entry in the file, i.e. the whole import or the literal with its length.
+    pub file_range: Range<u64>,
+}
+
+impl StringEntry {
+    /// Frames for the entry in the breakdown, below its section.
+    pub fn frames(&self) -> [String; 2] {
+        if self.kind == Kind::Builtin {
+            return [self.kind.frame().to_string(), self.name.clone()];
+        }
+        let mut string: String = self.name.chars().take(MAX_FRAME_CHARS).collect();
+        if string.len() < self.name.len() {
+            string.push('…');
+        }
+        // Quoted and escaped, so line breaks and the like don't break the frame.
+        [self.kind.frame().to_string(), format!("{string:?}")]
+    }
+}
+
+/// Returns the imports of JS string builtins and string constants, and the string literals, in
+/// the order they appear in the module.
+pub fn entries(module: &[u8]) -> anyhow::Result<Vec<StringEntry>> {
+    let mut entries = vec![];
+    for payload in Parser::new(0).parse_all(module) {
+        match payload? {
+            Payload::ImportSection(reader) => {
+                let end = reader.range().end as u64;
+                let mut imports = reader.into_iter_with_offsets().peekable();
+                while let Some(import) = imports.next() {
+                    let (offset, import) = import?;
+                    let next = match imports.peek() {
+                        Some(Ok((next, _))) => *next as u64,
+                        _ => end,
+                    };
+                    let kind = match (import.module, import.ty) {
+                        (BUILTINS_MODULE, TypeRef::Func(_)) => Kind::Builtin,
+                        (CONSTANTS_MODULE, TypeRef::Global(_)) => Kind::Constant,
+                        _ => continue,
+                    };
+                    entries.push(StringEntry {
+                        kind,
+                        name: import.name.to_string(),
+                        file_range: offset as u64..next,
+                    });
+                }
+            }
+            Payload::UnknownSection {
+                id: LITERALS_SECTION,
+                contents,
+                range,
+            } => {
+                // A literal section that doesn't parse is left unattributed.
+                let _ = literals(contents, range.start, &mut entries);
+            }
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+/// Reads the literals of a string literals section: a reserved byte, then a vector of strings.
+fn literals(contents: &[u8], offset: usize, entries: &mut Vec<StringEntry>) -> anyhow::Result<()> {
+    let mut reader = BinaryReader::new_with_offset(contents, offset);
+    reader.read_u8()?;
+    for _ in 0..reader.read_var_u32()? {
+        let start = reader.original_position() as u64;
+        let name = reader.read_string()?.to_string();
+        entries.push(StringEntry {
+            kind: Kind::Literal,
+            name,
+            file_range: start..reader.original_position() as u64,
+        });
+    }
+    Ok(())
+}
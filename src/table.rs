@@ -0,0 +1,173 @@
+//! The largest contributors as a plain text table, for a quick look in the terminal.
+
+use std::{collections::HashMap, io::Write};
+
+use crate::{
+    compressed::Uncertainty,
+    explain::strip_disambiguators,
+    group,
+    tree::{Instantiation, SizeRange},
+    Contributors,
+};
+
+/// Contributors of the same function in the same file, added up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Row {
+    /// The innermost function, without crate disambiguators, or empty for bytes outside of
+    /// functions.
+    pub function: String,
+    /// The section and the path below it, joined with `/`.
+    pub file: String,
+    /// The Rust crate, or empty if it isn't known.
+    pub crate_name: String,
+    pub size: u64,
+    /// With collapsed generics, how many instantiations of the function the row adds up, or 0
+    /// for functions that aren't generic.
+    pub instantiations: usize,
+    /// For estimated sizes, the range the size could be in.
+    pub range: Option<SizeRange>,
+}
+
+/// The `limit` largest rows of `contributors`, the largest first. `instantiations` are the ones
+/// of [`crate::Analysis::instantiations`], if generics were collapsed, and `uncertainty` the one
+/// of [`crate::Analysis::uncertainty`], if sizes were estimated.
+pub fn rows(
+    contributors: &Contributors,
+    instantiations: &HashMap<String, Vec<Instantiation>>,
+    uncertainty: Option<&Uncertainty>,
+    limit: usize,
+) -> Vec<Row> {
+    let mut sizes: HashMap<(String, String, String), (u64, usize, SizeRange)> = HashMap::new();
+    for (key, &size) in contributors {
+        let frames: Vec<_> = key.split(';').collect();
+        let functions = frames
+            .iter()
+            .position(|frame| frame.starts_with("@function: "))
+            .unwrap_or(frames.len());
+        let function = frames[functions..]
+            .last()
+            .and_then(|frame| frame.strip_prefix("@function: "))
+            .map(strip_disambiguators)
+            .unwrap_or_default();
+        let path = &frames[..functions];
+        let file = path
+            .iter()
+            .map(|frame| frame.strip_prefix("@section: ").unwrap_or(frame))
+            .collect::<Vec<_>>()
+            .join("/");
+        let source = group::source_frames(path).join("/");
+        let crate_name = path
+            .iter()
+            .find_map(|frame| frame.strip_prefix(group::CRATE_PREFIX))
+            .map(str::to_string)
+            .or_else(|| group::crate_for_path(&source))
+            .or_else(|| group::crate_for_symbol(&function))
+            .unwrap_or_default();
+        // Inlined callees are collapsed separately, so only the innermost function's own
+        // instantiations count.
+        let count = instantiations.get(key).map_or(0, Vec::len);
+        let entry = sizes.entry((function, file, crate_name)).or_default();
+        entry.0 += size;
+        entry.1 = entry.1.max(count);
+        if let Some(uncertainty) = uncertainty {
+            entry.2.low += uncertainty.low.get(key).copied().unwrap_or_default();
+            entry.2.high += uncertainty.high.get(key).copied().unwrap_or_default();
+        }
+    }
+    let mut rows: Vec<_> = sizes
+        .into_iter()
+        .map(
+            |((function, file, crate_name), (size, instantiations, range))| Row {
+                function,
+                file,
+                crate_name,
+                size,
+                instantiations,
+                range: uncertainty.map(|_| range),
+            },
+        )
+        .collect();
+    rows.sort_by(|a, b| {
+        b.size
+            .cmp(&a.size)
+            .then_with(|| (&a.function, &a.file).cmp(&(&b.function, &b.file)))
+    });
+    rows.truncate(limit);
+    rows
+}
+
+/// Writes the `limit` largest rows of `contributors` as a column-aligned table, with their share
+/// of the total. With `instantiations`, a column shows how many instantiations a generic
+/// function's size adds up, and with `uncertainty`, one shows the range of estimated sizes.
+pub fn write_table(
+    contributors: &Contributors,
+    instantiations: &HashMap<String, Vec<Instantiation>>,
+    uncertainty: Option<&Uncertainty>,
+    limit: usize,
+    mut output: impl Write,
+) -> anyhow::Result<()> {
+    let total: u64 = contributors.values().sum();
+    let rows = rows(contributors, instantiations, uncertainty, limit);
+    let counts = !instantiations.is_empty();
+    let ranges: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            row.range
+                .map(|range| format!("{}–{}", range.low, range.high))
+        })
+        .collect();
+    let range_width = ranges
+        .iter()
+        .flatten()
+        .map(|range| range.chars().count())
+        .chain(["range".len()])
+        .max()
+        .unwrap_or_default();
+    let width = |header: &str, column: fn(&Row) -> &str| {
+        rows.iter()
+            .map(|row| column(row).chars().count())
+            .chain([header.len()])
+            .max()
+            .unwrap_or_default()
+    };
+    let crate_width = width("crate", |row| &row.crate_name);
+    let function_width = width("function", |row| &row.function);
+    write!(output, "{:>10} {:>7}  ", "bytes", "%")?;
+    if uncertainty.is_some() {
+        write!(output, "{:>range_width$}  ", "range")?;
+    }
+    if counts {
+        write!(output, "{:>6}  ", "inst.")?;
+    }
+    writeln!(
+        output,
+        "{:<crate_width$}  {:<function_width$}  file",
+        "crate", "function"
+    )?;
+    for (row, range) in rows.iter().zip(&ranges) {
+        let percent = 100.0 * row.size as f64 / total.max(1) as f64;
+        write!(output, "{:>10} {:>6.2}%  ", row.size, percent)?;
+        if let Some(range) = range {
+            write!(output, "{range:>range_width$}  ")?;
+        }
+        if counts {
+            match row.instantiations {
+                0 => write!(output, "{:>6}  ", "")?,
+                count => write!(output, "{count:>6}  ")?,
+            }
+        }
+        writeln!(
+            output,
+            "{:<crate_width$}  {:<function_width$}  {}",
+            row.crate_name, row.function, row.file
+        )?;
+    }
+    let shown: u64 = rows.iter().map(|row| row.size).sum();
+    writeln!(
+        output,
+        "{:>10} {:>6.2}%  of {total} bytes",
+        shown,
+        100.0 * shown as f64 / total.max(1) as f64
+    )?;
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! Recognizes the runtime support code that threads and shared memory pull into a module, so its
+//! size can be reported as a whole under [`FRAME`].
+
+/// Frame that groups the threads runtime in the breakdown.
+pub const FRAME: &str = "@threads-runtime";
+
+/// Functions synthesized by wasm-ld or provided by wasi-libc/emscripten for threads.
+const NAMES: &[&str] = &[
+    "__wasm_init_tls",
+    "__wasm_init_memory",
+    "__wasm_apply_tls_relocs",
+    "__wasm_apply_global_tls_relocs",
+    "__wasm_set_tls_base",
+    "__wasi_thread_start",
+    "__wasi_thread_start_C",
+    "wasi_thread_start",
+    "_emscripten_tls_init",
+    "_emscripten_thread_init",
+    "_emscripten_thread_exit",
+    "_emscripten_thread_free_data",
+    "_emscripten_thread_crashed",
+    "_emscripten_yield",
+    "__emscripten_init_main_thread",
+    "__emscripten_init_main_thread_js",
+];
+
+/// Name prefixes of the pthread API, its internals and emscripten's threading and proxying shims.
+const PREFIXES: &[&str] = &[
+    "pthread_",
+    "__pthread_",
+    "_pthread_",
+    "emscripten_thread_",
+    "_emscripten_thread_",
+    "__emscripten_thread_",
+    "emscripten_futex_",
+    "_emscripten_futex_",
+    "emscripten_proxy_",
+    "_emscripten_proxy_",
+    "emscripten_run_in_main_runtime_thread",
+    "_emscripten_run_in_main_runtime_thread",
+    "emscripten_main_thread_process_queued_calls",
+    "emscripten_current_thread_process_queued_calls",
+    "__wait",
+    "__timedwait",
+    "__lock",
+    "__unlock",
+    "__tl_lock",
+    "__tl_unlock",
+];
+
+/// Whether the function called `name` belongs to the threads runtime.
+pub fn is_threads_runtime(name: &str) -> bool {
+    NAMES.contains(&name) || PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
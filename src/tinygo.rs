@@ -0,0 +1,118 @@
+//! Support for modules built with TinyGo, whose Go runtime and garbage collector would otherwise
+//! show up as one big `runtime` package.
+
+/// Frame that groups the TinyGo runtime (except the garbage collector) in the breakdown.
+pub const RUNTIME_FRAME: &str = "@tinygo-runtime";
+/// Frame that groups TinyGo's garbage collector in the breakdown.
+pub const GC_FRAME: &str = "@gc";
+
+/// Functions of TinyGo's runtime that belong to the garbage collector and allocator.
+const GC_FUNCTIONS: &[&str] = &[
+    "alloc",
+    "free",
+    "realloc",
+    "GC",
+    "runGC",
+    "initHeap",
+    "growHeap",
+    "markRoots",
+    "markRoot",
+    "markStack",
+    "markGlobals",
+    "markCurrentGoroutineStack",
+    "startMark",
+    "finishMark",
+    "sweep",
+    "looksLikePointer",
+    "scanstack",
+    "scanConservative",
+    "findGlobals",
+    "ReadMemStats",
+    "SetFinalizer",
+    "KeepAlive",
+];
+
+/// Returns the frame to group the function called `name` under, if it belongs to TinyGo's runtime.
+pub fn frame_for(name: &str) -> Option<&'static str> {
+    if name.starts_with("tinygo_scan") {
+        return Some(GC_FRAME);
+    }
+    if name.starts_with("tinygo_") {
+        return Some(RUNTIME_FRAME);
+    }
+    let path = go_path(name)?;
+    if path.first().map(String::as_str) != Some("runtime") {
+        return None;
+    }
+    let function = path.last()?;
+    let is_gc = GC_FUNCTIONS.contains(&function.as_str())
+        || function.starts_with("gc")
+        || path.get(1).is_some_and(|t| t.starts_with("gc"));
+    Some(if is_gc { GC_FRAME } else { RUNTIME_FRAME })
+}
+
+/// Splits a Go function name into its package path, receiver type (if any) and function name:
+/// `(*github.com/foo/bar.Conn).Close` becomes `["github.com/foo/bar", "Conn", "Close"]`.
+///
+/// Returns `None` for names that don't look like Go.
+pub fn go_path(name: &str) -> Option<Vec<String>> {
+    if name.contains("::") || name.contains(' ') {
+        return None;
+    }
+    // Method with a receiver: `(*pkg.Type).Method` or `(pkg.Type).Method`.
+    if let Some(rest) = name.strip_prefix('(') {
+        let (receiver, method) = rest.split_once(").")?;
+        let receiver = receiver.trim_start_matches('*');
+        let (package, type_name) = split_package(receiver)?;
+        return Some(vec![package.into(), type_name.into(), method.into()]);
+    }
+    let (package, function) = split_package(name)?;
+    match function.split_once('.') {
+        // `pkg.Type.Method`, as some producers write methods.
+        Some((type_name, method)) if !type_name.is_empty() && !method.is_empty() => {
+            Some(vec![package.into(), type_name.into(), method.into()])
+        }
+        _ => Some(vec![package.into(), function.into()]),
+    }
+}
+
+/// Splits `github.com/foo/bar.Name` at the first `.` after the last `/`.
+fn split_package(name: &str) -> Option<(&str, &str)> {
+    let last_slash = name.rfind('/').map_or(0, |i| i + 1);
+    let dot = last_slash + name[last_slash..].find('.')?;
+    let (package, name) = (&name[..dot], &name[dot + 1..]);
+    // `foo.123` and `foo.llvm.456` are suffixes LLVM appends to local symbols, not Go names.
+    if package.is_empty() || name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    if name.starts_with("llvm.") {
+        return None;
+    }
+    Some((package, name))
+}
+
+/// Undoes the escaping of Go symbol names, where the linker replaces characters like `.` in the
+/// last element of package paths with `%xx` hex escapes.
+pub fn demangle(name: &str) -> Option<String> {
+    if !name.contains('%') || go_path(name).is_none() {
+        return None;
+    }
+    let mut result = String::with_capacity(name.len());
+    let mut rest = name;
+    while let Some(percent) = rest.find('%') {
+        result.push_str(&rest[..percent]);
+        let escape = rest.get(percent + 1..percent + 3);
+        match escape.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(byte) if byte.is_ascii() => {
+                result.push(byte as char);
+                rest = &rest[percent + 3..];
+            }
+            _ => {
+                result.push('%');
+                rest = &rest[percent + 1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    Some(result)
+}
@@ -0,0 +1,419 @@
+use std::collections::{hash_map::Entry, BTreeMap, HashMap};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::Contributors;
+
+/// A node of the size breakdown. The size of a node includes the sizes of all its children.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeTree {
+    pub name: String,
+    /// Total size, including the children.
+    pub size: u64,
+    /// Bytes attributed to this node directly rather than to one of its children.
+    #[serde(rename = "self")]
+    pub self_size: u64,
+    /// Children, sorted by size in descending order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SizeTree>,
+    /// With collapsed generics, the instantiations this node stands for, the largest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub instantiations: Vec<Instantiation>,
+    /// Metadata from an annotations file whose rules match this node, see
+    /// [`crate::annotations`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+    /// For estimated sizes, the range the size could be in, see
+    /// [`crate::compressed::Uncertainty`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<SizeRange>,
+}
+
+/// The lowest and highest of several estimates of a size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeRange {
+    pub low: u64,
+    pub high: u64,
+}
+
+/// One instantiation of generic code, which the breakdown shows collapsed with all others.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Instantiation {
+    /// The generic arguments, in the order they appear in the name.
+    pub args: Vec<String>,
+    pub size: u64,
+}
+
+/// How [`SizeTree::merge`] combines two trees. Nodes are matched by their path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Add up the sizes, e.g. to total the shards of one module built in pieces.
+    Sum,
+    /// Keep the larger size of every node, e.g. for a worst case over several builds.
+    Max,
+    /// Add the other tree as a child with this name, so several modules can be shown side by
+    /// side. A child with that name is summed up with it.
+    Labelled(String),
+}
+
+/// What a node has besides its sizes, which [`SizeTree::merge`] carries over to the merged tree.
+#[derive(Clone, Debug, Default)]
+struct Extras {
+    instantiations: Vec<Instantiation>,
+    annotations: BTreeMap<String, String>,
+    range: Option<SizeRange>,
+}
+
+/// A node found by one of the lookup functions, together with the names of all its ancestors.
+#[derive(Clone, Debug)]
+pub struct Match<'a> {
+    pub path: Vec<&'a str>,
+    pub node: &'a SizeTree,
+}
+
+/// Result of a lookup. Matches nested inside other matches are not reported separately, so `size`
+/// never counts a byte twice.
+#[derive(Clone, Debug, Default)]
+pub struct Lookup<'a> {
+    pub size: u64,
+    pub matches: Vec<Match<'a>>,
+}
+
+impl SizeTree {
+    pub fn new(name: impl Into<String>) -> SizeTree {
+        SizeTree {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Builds a tree from the flat contributor map. The root node is called `all`.
+    pub fn from_contributors(contributors: &Contributors) -> SizeTree {
+        let mut entries: Vec<(Vec<&str>, u64)> = contributors
+            .iter()
+            .map(|(key, &size)| (key.split(';').collect(), size))
+            .collect();
+        // With sorted paths, a frame that already exists is always the most recently added child.
+        entries.sort();
+        let mut root = SizeTree::new("all");
+        for (frames, size) in entries {
+            root.insert(&frames, size);
+        }
+        root.sort();
+        root
+    }
+
+    /// Combines `other` into this tree. The roots are merged whatever their names.
+    ///
+    /// Self sizes are combined node by node, and the total sizes are recomputed from them. Nodes
+    /// only in `other` keep their instantiations and annotations, nodes in both keep the ones of
+    /// this tree, and add those of `other` it doesn't have. Ranges are combined like sizes, and
+    /// dropped where only one of the trees has them.
+    pub fn merge(&mut self, other: &SizeTree, strategy: &MergeStrategy) {
+        let (label, combine): (_, fn(u64, u64) -> u64) = match strategy {
+            MergeStrategy::Sum => (None, u64::saturating_add),
+            MergeStrategy::Max => (None, u64::max),
+            MergeStrategy::Labelled(label) => (Some(label.as_str()), u64::saturating_add),
+        };
+        let under_label = |path: &str| match label {
+            Some(label) if path.is_empty() => label.to_string(),
+            Some(label) => format!("{label};{path}"),
+            None => path.to_string(),
+        };
+        // The trees are merged as flat maps, with one lookup per node of `other` and without
+        // recursing into deeply nested frames.
+        let mut contributors = self.to_contributors();
+        let mut extras = self.extras_by_path();
+        let mut self_size = self.self_size;
+        for (path, size) in other.to_contributors() {
+            let merged = contributors.entry(under_label(&path)).or_default();
+            *merged = combine(*merged, size);
+        }
+        match label {
+            Some(label) if other.self_size > 0 => {
+                let merged = contributors.entry(label.to_string()).or_default();
+                *merged = combine(*merged, other.self_size);
+            }
+            Some(_) => {}
+            None => self_size = combine(self_size, other.self_size),
+        }
+        let combine_ranges = |ours: Option<SizeRange>, theirs: Option<SizeRange>| {
+            ours.zip(theirs).map(|(ours, theirs)| SizeRange {
+                low: combine(ours.low, theirs.low),
+                high: combine(ours.high, theirs.high),
+            })
+        };
+        for (path, theirs) in other.extras_by_path() {
+            match extras.entry(under_label(&path)) {
+                Entry::Vacant(entry) => {
+                    entry.insert(theirs);
+                }
+                Entry::Occupied(mut entry) => {
+                    let ours = entry.get_mut();
+                    if ours.instantiations.is_empty() {
+                        ours.instantiations = theirs.instantiations;
+                    }
+                    for (key, value) in theirs.annotations {
+                        ours.annotations.entry(key).or_insert(value);
+                    }
+                    ours.range = combine_ranges(ours.range, theirs.range);
+                }
+            }
+        }
+        // A label's node is a child of the root, which still holds all of `other`.
+        if label.is_some() {
+            let root = extras.entry(String::new()).or_default();
+            root.range = combine_ranges(root.range, other.range);
+        }
+        let name = std::mem::take(&mut self.name);
+        *self = SizeTree::from_contributors(&contributors);
+        self.name = name;
+        self.size += self_size;
+        self.self_size = self_size;
+        self.attach_extras(extras);
+    }
+
+    /// Flattens the tree back into contributors, one entry per node with a self size. The root's
+    /// name is not part of the paths, as in [`SizeTree::from_contributors`].
+    pub fn to_contributors(&self) -> Contributors {
+        let mut contributors = Contributors::new();
+        let mut stack: Vec<(String, &SizeTree)> = self
+            .children
+            .iter()
+            .map(|child| (child.name.clone(), child))
+            .collect();
+        while let Some((path, node)) = stack.pop() {
+            if node.self_size > 0 {
+                contributors.insert(path.clone(), node.self_size);
+            }
+            for child in &node.children {
+                stack.push((format!("{path};{}", child.name), child));
+            }
+        }
+        contributors
+    }
+
+    /// The extras of every node by its path, the root's being the empty path.
+    fn extras_by_path(&self) -> HashMap<String, Extras> {
+        let mut extras = HashMap::new();
+        let mut stack = vec![(String::new(), self)];
+        while let Some((path, node)) = stack.pop() {
+            for child in &node.children {
+                let child_path = match path.is_empty() {
+                    true => child.name.clone(),
+                    false => format!("{path};{}", child.name),
+                };
+                stack.push((child_path, child));
+            }
+            let node_extras = Extras {
+                instantiations: node.instantiations.clone(),
+                annotations: node.annotations.clone(),
+                range: node.range,
+            };
+            extras.insert(path, node_extras);
+        }
+        extras
+    }
+
+    fn attach_extras(&mut self, mut extras: HashMap<String, Extras>) {
+        let mut stack = vec![(self, String::new())];
+        while let Some((node, path)) = stack.pop() {
+            let Extras {
+                instantiations,
+                annotations,
+                range,
+            } = extras.remove(&path).unwrap_or_default();
+            node.instantiations = instantiations;
+            node.annotations = annotations;
+            node.range = range;
+            for child in &mut node.children {
+                let child_path = match path.is_empty() {
+                    true => child.name.clone(),
+                    false => format!("{path};{}", child.name),
+                };
+                stack.push((child, child_path));
+            }
+        }
+    }
+
+    /// Attaches instantiations, as returned by [`crate::group::collapse_generic_frames`], to the
+    /// nodes at their paths.
+    pub fn annotate(&mut self, instantiations: &HashMap<String, Vec<Instantiation>>) {
+        for (path, list) in instantiations {
+            if let Some(node) = self.get_mut(path) {
+                node.instantiations = list.clone();
+            }
+        }
+    }
+
+    /// Attaches the range of every node's size, given the `low` and `high` estimates of the
+    /// contributors the tree was built from.
+    pub fn attach_ranges(&mut self, low: &Contributors, high: &Contributors) {
+        // Totals of every path and all of its prefixes, the root's being the empty path.
+        let totals = |contributors: &Contributors| {
+            let mut totals: HashMap<String, u64> = HashMap::new();
+            for (key, &size) in contributors {
+                let ends = key.match_indices(';').map(|(i, _)| i);
+                for end in [0].into_iter().chain(ends).chain([key.len()]) {
+                    *totals.entry(key[..end].to_string()).or_default() += size;
+                }
+            }
+            totals
+        };
+        let (low, high) = (totals(low), totals(high));
+        let mut stack = vec![(self, String::new())];
+        while let Some((node, path)) = stack.pop() {
+            node.range = Some(SizeRange {
+                low: low.get(&path).copied().unwrap_or_default(),
+                high: high.get(&path).copied().unwrap_or_default(),
+            });
+            for child in &mut node.children {
+                let child_path = match path.is_empty() {
+                    true => child.name.clone(),
+                    false => format!("{path};{}", child.name),
+                };
+                stack.push((child, child_path));
+            }
+        }
+    }
+
+    fn get_mut(&mut self, path: &str) -> Option<&mut SizeTree> {
+        let mut node = self;
+        for frame in path.split(';') {
+            node = node.children.iter_mut().find(|child| child.name == frame)?;
+        }
+        Some(node)
+    }
+
+    fn insert(&mut self, frames: &[&str], size: u64) {
+        let mut node = self;
+        node.size += size;
+        for frame in frames {
+            if !node
+                .children
+                .last()
+                .is_some_and(|child| child.name == *frame)
+            {
+                node.children.push(SizeTree::new(*frame));
+            }
+            node = node.children.last_mut().expect("just added");
+            node.size += size;
+        }
+        node.self_size += size;
+    }
+
+    fn sort(&mut self) {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            node.children
+                .sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+            stack.extend(node.children.iter_mut());
+        }
+    }
+
+    /// Returns the node at the `;`-separated `path` below this node.
+    pub fn get(&self, path: &str) -> Option<&SizeTree> {
+        self.resolve(path).map(|found| found.node)
+    }
+
+    fn resolve(&self, path: &str) -> Option<Match<'_>> {
+        let mut found = Match {
+            path: vec![],
+            node: self,
+        };
+        for frame in path.split(';') {
+            found.node = found
+                .node
+                .children
+                .iter()
+                .find(|child| child.name == frame)?;
+            found.path.push(found.node.name.as_str());
+        }
+        Some(found)
+    }
+
+    /// Looks up a node by its `;`-separated path (e.g. `@section: <code>;src;main.rs`) or, if no such
+    /// path exists, all frames with the given name. Function names can be given with or without the
+    /// `@function: ` prefix.
+    pub fn lookup(&self, path_or_symbol: &str) -> Lookup<'_> {
+        if let Some(found) = self.resolve(path_or_symbol) {
+            return Lookup {
+                size: found.node.size,
+                matches: vec![found],
+            };
+        }
+        self.find(|name| {
+            name == path_or_symbol || name.strip_prefix("@function: ") == Some(path_or_symbol)
+        })
+    }
+
+    /// Returns all frames whose name matches `pattern`.
+    pub fn lookup_matching(&self, pattern: &Regex) -> Lookup<'_> {
+        self.find(|name| pattern.is_match(name))
+    }
+
+    fn find(&self, predicate: impl Fn(&str) -> bool) -> Lookup<'_> {
+        let mut lookup = Lookup::default();
+        let mut stack: Vec<(Vec<&str>, &SizeTree)> = vec![(vec![], self)];
+        while let Some((path, node)) = stack.pop() {
+            for child in node.children.iter().rev() {
+                let mut child_path = path.clone();
+                child_path.push(child.name.as_str());
+                if predicate(&child.name) {
+                    lookup.size += child.size;
+                    lookup.matches.push(Match {
+                        path: child_path,
+                        node: child,
+                    });
+                } else {
+                    stack.push((child_path, child));
+                }
+            }
+        }
+        lookup
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(contributors: &[(&str, u64)]) -> SizeTree {
+        let contributors = contributors
+            .iter()
+            .map(|&(key, size)| (key.to_string(), size))
+            .collect();
+        let mut tree = SizeTree::from_contributors(&contributors);
+        tree.attach_ranges(&contributors, &contributors);
+        tree
+    }
+
+    #[test]
+    fn merges_annotations_and_ranges() {
+        let mut ours = tree(&[("app;main.rs", 10), ("core;fmt.rs", 5)]);
+        let mut theirs = tree(&[("app;main.rs", 20), ("serde;de.rs", 7)]);
+        let owner = |name: &str| BTreeMap::from([("owner".to_string(), name.to_string())]);
+        ours.children[0].annotations = owner("us");
+        theirs.children[0].annotations = owner("them");
+        theirs.get_mut("serde").unwrap().annotations = owner("them");
+        ours.merge(&theirs, &MergeStrategy::Sum);
+        let app = ours.get("app").unwrap();
+        assert_eq!(app.annotations, owner("us"));
+        assert_eq!(app.range, Some(SizeRange { low: 30, high: 30 }));
+        let serde = ours.get("serde").unwrap();
+        assert_eq!(serde.annotations, owner("them"));
+        assert_eq!(serde.range, Some(SizeRange { low: 7, high: 7 }));
+        assert_eq!(ours.range, Some(SizeRange { low: 42, high: 42 }));
+
+        let mut labelled = tree(&[("app;main.rs", 10)]);
+        labelled.merge(&theirs, &MergeStrategy::Labelled("other".to_string()));
+        let other = labelled.get("other").unwrap();
+        assert_eq!(other.range, Some(SizeRange { low: 27, high: 27 }));
+        assert_eq!(
+            labelled.get("other;app").unwrap().annotations,
+            owner("them")
+        );
+        assert_eq!(labelled.range, Some(SizeRange { low: 37, high: 37 }));
+    }
+}
@@ -0,0 +1,175 @@
+"use strict";
+// Treemap of the size tree, embedded as JSON in `#data`. Every node is a rectangle whose area is its
+// size, with its children nested inside. Clicking a node zooms into it, the breadcrumbs zoom out
+// again.
+
+// Height of the label at the top of a node with children, in pixels.
+const HEADER = 18;
+// Nodes smaller than this in either dimension (in pixels) are not drawn.
+const MIN_SIDE = 4;
+// Number of levels drawn below the zoomed-in node.
+const DEPTH = 4;
+
+const root = JSON.parse(document.getElementById("data").textContent);
+const map = document.getElementById("treemap");
+const crumbs = document.getElementById("crumbs");
+const tooltip = document.getElementById("tooltip");
+
+(function link(node, parent) {
+  node.parent = parent;
+  node.children = (node.children ?? []).filter(child => child.size > 0);
+  for (const child of node.children) link(child, node);
+})(root, null);
+
+let focus = root;
+
+function color(name, depth) {
+  let hash = 0;
+  for (const c of name) hash = (hash * 31 + c.charCodeAt(0)) | 0;
+  const hue = name.startsWith("@") ? 200 + Math.abs(hash % 40) : Math.abs(hash % 60);
+  return `hsl(${hue}, 70%, ${Math.min(55 + depth * 8, 90)}%)`;
+}
+
+function percent(size, total) {
+  return total === 0 ? "0.00" : (size * 100 / total).toFixed(2);
+}
+
+function label(node) {
+  const annotations = Object.entries(node.annotations ?? {}).map(([key, value]) => `\n${key}: ${value}`);
+  const range = node.range ? ` (estimated ${node.range.low}–${node.range.high})` : "";
+  return `${node.name}\n${node.size} bytes${range}, ${node.self} self, ${percent(node.size, root.size)}% of the module${annotations.join("")}`;
+}
+
+// Worst aspect ratio of a row of `sizes` (in pixels²) laid out along a side of length `side`.
+function worst(sizes, side) {
+  const sum = sizes.reduce((a, b) => a + b, 0);
+  const max = Math.max(...sizes);
+  const min = Math.min(...sizes);
+  return Math.max((side * side * max) / (sum * sum), (sum * sum) / (side * side * min));
+}
+
+// Squarified layout of `nodes` (sorted by size, largest first) in the rectangle `rect`.
+function squarify(nodes, rect, total) {
+  const rects = [];
+  let { x, y, w, h } = rect;
+  const scale = (w * h) / total;
+  let row = [];
+  for (let i = 0; i < nodes.length; ) {
+    const side = Math.min(w, h);
+    const sizes = row.map(node => node.size * scale);
+    const next = nodes[i].size * scale;
+    if (row.length === 0 || worst([...sizes, next], side) <= worst(sizes, side)) {
+      row.push(nodes[i++]);
+      continue;
+    }
+    ({ x, y, w, h } = place(row, { x, y, w, h }, scale, rects));
+    row = [];
+  }
+  if (row.length > 0) place(row, { x, y, w, h }, scale, rects);
+  return rects;
+}
+
+// Lays out `row` along the shorter side of `rect`, and returns what is left of it.
+function place(row, rect, scale, rects) {
+  const area = row.reduce((sum, node) => sum + node.size * scale, 0);
+  const horizontal = rect.w >= rect.h;
+  const thickness = area / (horizontal ? rect.h : rect.w);
+  let offset = 0;
+  for (const node of row) {
+    const length = (node.size * scale) / thickness;
+    rects.push(horizontal
+      ? { node, x: rect.x, y: rect.y + offset, w: thickness, h: length }
+      : { node, x: rect.x + offset, y: rect.y, w: length, h: thickness });
+    offset += length;
+  }
+  return horizontal
+    ? { x: rect.x + thickness, y: rect.y, w: rect.w - thickness, h: rect.h }
+    : { x: rect.x, y: rect.y + thickness, w: rect.w, h: rect.h - thickness };
+}
+
+function zoom(node) {
+  focus = node;
+  render();
+}
+
+function box(node, rect, depth) {
+  const el = document.createElement("div");
+  el.className = "node";
+  el.style.left = `${rect.x}px`;
+  el.style.top = `${rect.y}px`;
+  el.style.width = `${rect.w}px`;
+  el.style.height = `${rect.h}px`;
+  el.style.background = color(node.name, depth);
+  el.tabIndex = 0;
+  el.setAttribute("role", "button");
+  el.setAttribute("aria-label", label(node));
+  const name = document.createElement("span");
+  name.textContent = node.name;
+  el.append(name);
+  el.onclick = ev => {
+    ev.stopPropagation();
+    zoom(node);
+  };
+  el.onkeydown = ev => {
+    if (ev.key !== "Enter" && ev.key !== " ") return;
+    ev.preventDefault();
+    ev.stopPropagation();
+    zoom(node);
+  };
+  el.onmousemove = ev => {
+    ev.stopPropagation();
+    tooltip.textContent = label(node);
+    tooltip.hidden = false;
+    tooltip.style.left = `${ev.pageX + 12}px`;
+    tooltip.style.top = `${ev.pageY + 12}px`;
+  };
+  return el;
+}
+
+// Draws the children of `node` into the rectangle `rect` (relative to `parent`).
+function draw(node, rect, depth, parent) {
+  const children = [...node.children].sort((a, b) => b.size - a.size);
+  // Code attributed to the node itself takes up space next to its children.
+  const total = children.reduce((sum, child) => sum + child.size, 0) + node.self;
+  if (children.length === 0 || total === 0) return;
+  for (const placed of squarify(children, rect, total)) {
+    if (placed.w < MIN_SIDE || placed.h < MIN_SIDE) continue;
+    const el = box(placed.node, placed, depth);
+    parent.append(el);
+    const inner = { x: 1, y: HEADER, w: placed.w - 2, h: placed.h - HEADER - 1 };
+    if (depth + 1 < DEPTH && inner.w >= MIN_SIDE && inner.h >= MIN_SIDE) {
+      draw(placed.node, inner, depth + 1, el);
+    }
+  }
+}
+
+function renderCrumbs() {
+  crumbs.replaceChildren();
+  const path = [];
+  for (let node = focus; node; node = node.parent) path.unshift(node);
+  for (const node of path) {
+    if (node !== root) crumbs.append(" › ");
+    const link = document.createElement("a");
+    link.href = "#";
+    link.textContent = `${node.name} (${node.size} bytes)`;
+    link.onclick = ev => {
+      ev.preventDefault();
+      zoom(node);
+    };
+    crumbs.append(link);
+  }
+}
+
+function render() {
+  map.replaceChildren();
+  const rect = { x: 0, y: 0, w: map.clientWidth, h: map.clientHeight };
+  if (focus.children.length === 0) map.append(box(focus, rect, 0));
+  else draw(focus, rect, 0, map);
+  renderCrumbs();
+}
+
+map.onmouseleave = () => {
+  tooltip.hidden = true;
+};
+window.onresize = render;
+render();
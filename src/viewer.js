@@ -0,0 +1,227 @@
+"use strict";
+// Viewer for the self-contained HTML report. Expects the size tree as JSON in `#data`, and the
+// code of some functions in `#code-data`.
+
+const ROW_HEIGHT = 20;
+// Frames narrower than this (in percent of the zoomed-in node) are not drawn.
+const MIN_WIDTH = 0.1;
+
+const root = JSON.parse(document.getElementById("data").textContent);
+const graph = document.getElementById("graph");
+const crumbs = document.getElementById("crumbs");
+const table = document.getElementById("children");
+const search = document.getElementById("search");
+const matched = document.getElementById("matched");
+const code = document.getElementById("code");
+// Hexdumps and disassemblies, by the name of the function's frame.
+const functionCode = new Map(
+  JSON.parse(document.getElementById("code-data").textContent).map(f => [`@function: ${f.name}`, f]),
+);
+
+(function link(node, parent) {
+  node.parent = parent;
+  node.children = node.children ?? [];
+  for (const child of node.children) link(child, node);
+})(root, null);
+
+let focus = root;
+let pattern = null;
+// Column the children table is sorted by, and whether in ascending order.
+let sortKey = "size";
+let ascending = false;
+// Whether the code of a selected function is shown as "hexdump" or "disassembly".
+let codeView = "disassembly";
+
+function color(name) {
+  let hash = 0;
+  for (const c of name) hash = (hash * 31 + c.charCodeAt(0)) | 0;
+  const hue = name.startsWith("@") ? 200 + Math.abs(hash % 40) : Math.abs(hash % 60);
+  return `hsl(${hue}, 75%, ${60 + Math.abs(hash % 15)}%)`;
+}
+
+function percent(size, total) {
+  return total === 0 ? "0.00" : (size * 100 / total).toFixed(2);
+}
+
+function label(node) {
+  const annotations = Object.entries(node.annotations ?? {}).map(([key, value]) => `\n${key}: ${value}`);
+  const range = node.range ? `, estimated ${node.range.low}–${node.range.high}` : "";
+  return `${node.name} (${node.size} bytes${range}, ${node.self} self, ${percent(node.size, root.size)}%)${annotations.join("")}`;
+}
+
+function ancestors(node) {
+  const path = [];
+  for (; node; node = node.parent) path.unshift(node);
+  return path;
+}
+
+function zoom(node) {
+  focus = node;
+  render();
+}
+
+function frame(node, depth, left, width) {
+  const el = document.createElement("div");
+  el.className = "frame";
+  if (pattern?.test(node.name)) el.classList.add("match");
+  el.style.left = `${left}%`;
+  el.style.width = `${width}%`;
+  el.style.top = `${depth * ROW_HEIGHT}px`;
+  el.style.background = color(node.name);
+  el.textContent = node.name;
+  el.title = label(node);
+  el.tabIndex = 0;
+  el.setAttribute("role", "button");
+  el.setAttribute("aria-label", label(node));
+  el.onclick = () => zoom(node);
+  el.onkeydown = ev => {
+    if (ev.key !== "Enter" && ev.key !== " ") return;
+    ev.preventDefault();
+    zoom(node);
+  };
+  return el;
+}
+
+function draw(node, depth, left, width, frames) {
+  frames.push(frame(node, depth, left, width));
+  let maxDepth = depth;
+  let childLeft = left;
+  for (const child of node.children) {
+    const childWidth = node.size === 0 ? 0 : width * child.size / node.size;
+    if (childWidth >= MIN_WIDTH) {
+      maxDepth = Math.max(maxDepth, draw(child, depth + 1, childLeft, childWidth, frames));
+    }
+    childLeft += childWidth;
+  }
+  return maxDepth;
+}
+
+function renderCrumbs() {
+  crumbs.replaceChildren();
+  for (const node of ancestors(focus)) {
+    if (node !== root) crumbs.append(" › ");
+    const link = document.createElement("a");
+    link.href = "#";
+    link.textContent = node.name;
+    link.onclick = ev => {
+      ev.preventDefault();
+      zoom(node);
+    };
+    crumbs.append(link);
+  }
+}
+
+function compare(a, b) {
+  const order = sortKey === "name" ? a.name.localeCompare(b.name) : a[sortKey] - b[sortKey];
+  return ascending ? order : -order;
+}
+
+function renderTable() {
+  for (const th of table.tHead.querySelectorAll("th[data-sort]")) {
+    if (th.dataset.sort === sortKey) th.setAttribute("aria-sort", ascending ? "ascending" : "descending");
+    else th.removeAttribute("aria-sort");
+  }
+  const rows = [...focus.children].sort(compare).map(child => {
+    const row = document.createElement("tr");
+    const name = document.createElement("th");
+    name.scope = "row";
+    name.className = "name";
+    const link = document.createElement("a");
+    link.href = "#";
+    link.textContent = child.name;
+    link.onclick = ev => {
+      ev.preventDefault();
+      zoom(child);
+    };
+    name.append(link);
+    const self = document.createElement("td");
+    self.className = "num";
+    self.textContent = child.self;
+    const size = document.createElement("td");
+    size.className = "num";
+    size.textContent = child.size;
+    const share = document.createElement("td");
+    share.className = "num";
+    share.textContent = percent(child.size, focus.size);
+    row.append(name, self, size, share);
+    return row;
+  });
+  table.tBodies[0].replaceChildren(...rows);
+}
+
+function renderMatches() {
+  if (!pattern) {
+    matched.textContent = "";
+    return;
+  }
+  // Matches nested inside other matches are not counted again.
+  let size = 0;
+  let count = 0;
+  (function find(node) {
+    if (pattern.test(node.name)) {
+      size += node.size;
+      count++;
+      return;
+    }
+    node.children.forEach(find);
+  })(focus);
+  matched.textContent = `${count} matches, ${size} bytes (${percent(size, focus.size)}%)`;
+}
+
+function renderCode() {
+  const entry = functionCode.get(focus.name);
+  code.hidden = !entry;
+  if (!entry) return;
+  code.querySelector("h2").textContent =
+    `${entry.name}: ${entry.size} bytes at offset 0x${entry.file_offset.toString(16)}`;
+  for (const button of code.querySelectorAll("button")) {
+    button.setAttribute("aria-pressed", button.dataset.view === codeView);
+  }
+  // Only the selected view is put into the page.
+  const text = entry[codeView];
+  code.querySelector("pre").textContent = entry.truncated ? `${text}…\n` : text;
+}
+
+function render() {
+  const frames = [];
+  const depth = draw(focus, 0, 0, 100, frames);
+  graph.replaceChildren(...frames);
+  graph.style.height = `${(depth + 1) * ROW_HEIGHT}px`;
+  renderCrumbs();
+  renderTable();
+  renderMatches();
+  renderCode();
+}
+
+for (const th of table.tHead.querySelectorAll("th[data-sort]")) {
+  const button = document.createElement("button");
+  button.type = "button";
+  button.append(...th.childNodes);
+  button.onclick = () => {
+    // Clicking the current column flips the order; names sort ascending first, sizes descending.
+    ascending = th.dataset.sort === sortKey ? !ascending : th.dataset.sort === "name";
+    sortKey = th.dataset.sort;
+    renderTable();
+  };
+  th.append(button);
+}
+
+for (const button of code.querySelectorAll("button")) {
+  button.onclick = () => {
+    codeView = button.dataset.view;
+    renderCode();
+  };
+}
+
+search.oninput = () => {
+  try {
+    pattern = search.value ? new RegExp(search.value, "i") : null;
+    search.setCustomValidity("");
+  } catch (e) {
+    search.setCustomValidity(e.message);
+    return;
+  }
+  render();
+};
+
+render();
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+use wasmparser::{
+    BinaryReader, DataKind, FunctionBody, Name, NameSectionReader, Operator, Parser, Payload,
+    TypeRef,
+};
+
+/// A data segment of the module, with its payload's position in the file.
+pub struct DataSegment<'a> {
+    pub file_offset: u64,
+    /// Address in linear memory for active segments with a constant offset.
+    pub memory_offset: Option<u64>,
+    pub data: &'a [u8],
+}
+
+/// Returns all data segments of the module in order of appearance.
+pub fn data_segments(module: &[u8]) -> anyhow::Result<Vec<DataSegment<'_>>> {
+    let mut segments = vec![];
+    for payload in Parser::new(0).parse_all(module) {
+        let Payload::DataSection(reader) = payload? else {
+            continue;
+        };
+        for data in reader {
+            let data = data?;
+            let memory_offset = match data.kind {
+                DataKind::Active { offset_expr, .. } => {
+                    match offset_expr.get_operators_reader().read()? {
+                        Operator::I32Const { value } => Some(value as u32 as u64),
+                        Operator::I64Const { value } => Some(value as u64),
+                        _ => None,
+                    }
+                }
+                DataKind::Passive => None,
+            };
+            segments.push(DataSegment {
+                file_offset: file_offset(module, data.data),
+                memory_offset,
+                data: data.data,
+            });
+        }
+    }
+    Ok(segments)
+}
+
+/// Position of `slice` within `module`. `slice` must be borrowed from `module`.
+fn file_offset(module: &[u8], slice: &[u8]) -> u64 {
+    (slice.as_ptr() as usize - module.as_ptr() as usize) as u64
+}
+
+/// A function body from the code section.
+pub struct Function<'a> {
+    /// Index in the function index space, i.e. counting imported functions.
+    pub index: u32,
+    /// Offset of the body relative to the start of the code section's contents. This is how DWARF
+    /// addresses code in wasm.
+    pub code_offset: u64,
+    /// Offset of the body (after its size prefix) in the file.
+    pub file_offset: u64,
+    pub size: u64,
+    /// Length of the LEB128 size prefix in front of the body. It isn't always minimal, linkers
+    /// may pad it.
+    pub size_prefix: u64,
+    pub body: FunctionBody<'a>,
+}
+
+impl Function<'_> {
+    pub fn code_range(&self) -> std::ops::Range<u64> {
+        self.code_offset..self.code_offset + self.size
+    }
+
+    /// Size of the function's entry in the code section, i.e. the body and its size prefix.
+    pub fn encoded_size(&self) -> u64 {
+        self.size_prefix + self.size
+    }
+}
+
+/// Returns the file offset of the code section's contents, which DWARF addresses are relative to,
+/// or 0 if there is no code section.
+pub fn code_start(module: &[u8]) -> anyhow::Result<u64> {
+    for payload in Parser::new(0).parse_all(module) {
+        if let Payload::CodeSectionStart { range, .. } = payload? {
+            return Ok(range.start as u64);
+        }
+    }
+    Ok(0)
+}
+
+/// Returns all function bodies of the code section in order.
+pub fn functions(module: &[u8]) -> anyhow::Result<Vec<Function<'_>>> {
+    let mut functions = vec![];
+    let mut imported_functions = 0;
+    let mut code_start = 0;
+    // Where the next entry's size prefix starts.
+    let mut entry_start = 0;
+    for payload in Parser::new(0).parse_all(module) {
+        match payload? {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    if let TypeRef::Func(_) = import?.ty {
+                        imported_functions += 1;
+                    }
+                }
+            }
+            Payload::CodeSectionStart { range, .. } => {
+                code_start = range.start as u64;
+                let mut reader = BinaryReader::new_with_offset(&module[range.clone()], range.start);
+                reader.read_var_u32()?;
+                entry_start = reader.original_position() as u64;
+            }
+            Payload::CodeSectionEntry(body) => {
+                let range = body.range();
+                functions.push(Function {
+                    index: imported_functions + functions.len() as u32,
+                    code_offset: range.start as u64 - code_start,
+                    file_offset: range.start as u64,
+                    size: range.len() as u64,
+                    size_prefix: range.start as u64 - entry_start,
+                    body,
+                });
+                entry_start = range.end as u64;
+            }
+            _ => {}
+        }
+    }
+    Ok(functions)
+}
+
+/// Returns the file ranges of the bytes that only encode the functions rather than their code: the
+/// function section, which holds every function's type index, and the code section's entry count
+/// and the size prefixes of its bodies.
+pub fn encoding_overhead(module: &[u8]) -> anyhow::Result<Vec<std::ops::Range<u64>>> {
+    let mut ranges = vec![];
+    for payload in Parser::new(0).parse_all(module) {
+        match payload? {
+            Payload::FunctionSection(reader) => {
+                let range = reader.range();
+                ranges.push(range.start as u64..range.end as u64);
+            }
+            Payload::CodeSectionStart { range, .. } => {
+                let mut reader = BinaryReader::new_with_offset(&module[range.clone()], range.start);
+                reader.read_var_u32()?;
+                ranges.push(range.start as u64..reader.original_position() as u64);
+            }
+            _ => {}
+        }
+    }
+    ranges.extend(
+        functions(module)?
+            .into_iter()
+            .map(|function| function.file_offset - function.size_prefix..function.file_offset),
+    );
+    Ok(ranges)
+}
+
+/// Returns the function names from the `name` custom section, keyed by function index.
+pub fn function_names(module: &[u8]) -> anyhow::Result<HashMap<u32, &str>> {
+    let mut names = HashMap::new();
+    for payload in Parser::new(0).parse_all(module) {
+        let Payload::CustomSection(section) = payload? else {
+            continue;
+        };
+        if section.name() != "name" {
+            continue;
+        }
+        for subsection in NameSectionReader::new(section.data(), section.data_offset()) {
+            // A broken name section shouldn't prevent the analysis, names are optional.
+            let Ok(Name::Function(map)) = subsection else {
+                continue;
+            };
+            for naming in map.into_iter().flatten() {
+                names.insert(naming.index, naming.name);
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// Returns the length of the longest prefix of `module` that ends at a section boundary and can be
+/// parsed, together with the reason the rest can't, for truncated or corrupted files. Fails only if
+/// not even the module header can be parsed.
+pub fn parseable_prefix(module: &[u8]) -> anyhow::Result<(usize, Option<anyhow::Error>)> {
+    let error = match object::wasm::WasmFile::parse(module) {
+        Ok(_) => return Ok((module.len(), None)),
+        Err(err) => anyhow::Error::from(err),
+    };
+    let mut ends = vec![];
+    for payload in Parser::new(0).parse_all(module) {
+        let Ok(payload) = payload else {
+            break;
+        };
+        match payload {
+            Payload::Version { range, .. } => ends.push(range.end),
+            payload => ends.extend(payload.as_section().map(|(_, range)| range.end)),
+        }
+    }
+    ends.retain(|&end| end < module.len());
+    ends.sort_unstable();
+    ends.dedup();
+    for &end in ends.iter().rev() {
+        if object::wasm::WasmFile::parse(&module[..end]).is_ok() {
+            return Ok((end, Some(error)));
+        }
+    }
+    Err(error)
+}
+
+/// Returns the path or URL of the separate debug module, from the `external_debug_info` custom
+/// section that emscripten's `-gseparate-dwarf` leaves in the module.
+pub fn external_debug_info(module: &[u8]) -> Option<String> {
+    custom_section_string(module, "external_debug_info")
+}
+
+/// Returns the path or URL of the module's source map, from its `sourceMappingURL` custom
+/// section.
+pub fn source_mapping_url(module: &[u8]) -> Option<String> {
+    custom_section_string(module, "sourceMappingURL")
+}
+
+/// Whether the module has DWARF of its own.
+pub fn has_dwarf(module: &[u8]) -> bool {
+    Parser::new(0).parse_all(module).any(|payload| {
+        matches!(payload, Ok(Payload::CustomSection(section)) if section.name() == ".debug_info")
+    })
+}
+
+/// Reads the string that makes up the custom section `name`.
+fn custom_section_string(module: &[u8], name: &str) -> Option<String> {
+    for payload in Parser::new(0).parse_all(module) {
+        let Ok(Payload::CustomSection(section)) = payload else {
+            continue;
+        };
+        if section.name() == name {
+            let mut reader = BinaryReader::new_with_offset(section.data(), section.data_offset());
+            return reader.read_string().ok().map(str::to_string);
+        }
+    }
+    None
+}
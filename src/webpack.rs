@@ -0,0 +1,148 @@
+use std::{collections::BTreeMap, io::Write};
+
+use serde::Serialize;
+
+use crate::{provenance::Provenance, Contributors};
+
+/// An approximation of webpack's `stats.json`, describing the module as a single asset in a
+/// single chunk, with one webpack module per breakdown entry. This is enough for bundle analysis
+/// tools like webpack-bundle-analyzer or Statoscope to show it.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Stats {
+    pub name: String,
+    pub assets: Vec<Asset>,
+    pub chunks: Vec<Chunk>,
+    pub modules: Vec<Module>,
+    pub entrypoints: BTreeMap<String, Entrypoint>,
+    /// Not part of webpack's format, tools ignore it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Asset {
+    pub name: String,
+    pub size: u64,
+    pub chunks: Vec<u32>,
+    pub chunk_names: Vec<String>,
+    pub emitted: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Chunk {
+    pub id: u32,
+    pub names: Vec<String>,
+    pub files: Vec<String>,
+    pub size: u64,
+    pub initial: bool,
+    pub entry: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Module {
+    pub id: usize,
+    pub identifier: String,
+    pub name: String,
+    pub size: u64,
+    pub chunks: Vec<u32>,
+    pub reasons: Vec<Reason>,
+}
+
+/// Why a module is part of the asset: it was included by the wasm module, in the given section.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reason {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub module_name: String,
+    pub user_request: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entrypoint {
+    pub chunks: Vec<u32>,
+    pub assets: Vec<EntrypointAsset>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct EntrypointAsset {
+    pub name: String,
+}
+
+const CHUNK: u32 = 0;
+
+/// Builds the stats for `contributors`, as if they were the modules of an asset called `asset`.
+/// Module names are the frames of each entry joined with `/`, so tools that group modules by
+/// directory show the same hierarchy as the flame graph.
+pub fn stats(asset: &str, contributors: &Contributors, provenance: Option<&Provenance>) -> Stats {
+    let mut entries: Vec<_> = contributors.iter().collect();
+    entries.sort();
+    let modules: Vec<Module> = entries
+        .into_iter()
+        .enumerate()
+        .map(|(id, (key, &size))| {
+            let frames: Vec<&str> = key.split(';').collect();
+            let name = format!("./{}", frames.join("/"));
+            Module {
+                id,
+                identifier: name.clone(),
+                name,
+                size,
+                chunks: vec![CHUNK],
+                reasons: vec![Reason {
+                    kind: "wasm".to_string(),
+                    module_name: asset.to_string(),
+                    user_request: frames[0].to_string(),
+                }],
+            }
+        })
+        .collect();
+    let size = modules.iter().map(|module| module.size).sum();
+
+    Stats {
+        name: asset.to_string(),
+        assets: vec![Asset {
+            name: asset.to_string(),
+            size,
+            chunks: vec![CHUNK],
+            chunk_names: vec![asset.to_string()],
+            emitted: true,
+        }],
+        chunks: vec![Chunk {
+            id: CHUNK,
+            names: vec![asset.to_string()],
+            files: vec![asset.to_string()],
+            size,
+            initial: true,
+            entry: true,
+        }],
+        modules,
+        entrypoints: BTreeMap::from([(
+            asset.to_string(),
+            Entrypoint {
+                chunks: vec![CHUNK],
+                assets: vec![EntrypointAsset {
+                    name: asset.to_string(),
+                }],
+            },
+        )]),
+        provenance: provenance.cloned(),
+    }
+}
+
+pub fn write_stats(
+    asset: &str,
+    contributors: &Contributors,
+    provenance: Option<&Provenance>,
+    mut output: impl Write,
+) -> anyhow::Result<()> {
+    let stats = stats(asset, contributors, provenance);
+    serde_json::to_writer_pretty(&mut output, &stats)?;
+    writeln!(output)?;
+    Ok(())
+}
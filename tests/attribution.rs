@@ -0,0 +1,56 @@
+//! Attribution of the code section, by DWARF and by the functions' code section entries.
+
+mod common;
+
+use wasmphobia::{Attribution, Options};
+
+fn options(attribution: Attribution) -> Options {
+    Options {
+        attribution,
+        unmapped_functions: true,
+        ..Options::default()
+    }
+}
+
+#[test]
+fn dwarf_and_code_section_attribution_agree() {
+    let module = common::module("rust");
+    let dwarf = common::contributors(&module, &options(Attribution::Dwarf));
+    let code_section = common::contributors(&module, &options(Attribution::CodeSection));
+    assert_eq!(
+        common::section_totals(&dwarf),
+        common::section_totals(&code_section)
+    );
+    assert_eq!(
+        common::below(&dwarf, "@unmapped"),
+        common::below(&code_section, "@unmapped")
+    );
+}
+
+#[test]
+fn only_functions_without_line_info_are_unmapped() {
+    let module = common::module("rust");
+    let contributors = common::contributors(&module, &options(Attribution::Dwarf));
+    let unmapped: Vec<_> = common::below(&contributors, "@unmapped")
+        .into_keys()
+        .collect();
+    assert_eq!(unmapped, ["@function: __wasm_call_ctors"]);
+}
+
+#[test]
+fn code_and_function_sections_are_fully_accounted_for() {
+    let module = common::module("rust");
+    for attribution in [Attribution::Dwarf, Attribution::CodeSection] {
+        let contributors = common::contributors(&module, &options(attribution));
+        assert!(
+            !contributors
+                .keys()
+                .any(|path| path.starts_with("@section: <unknown section>")),
+            "{attribution:?}"
+        );
+        for section in ["<code>", "<function>"] {
+            let key = format!("@section: {section};<no mapping info>");
+            assert_eq!(contributors.get(&key), Some(&0), "{attribution:?}");
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! The command line: sizes and percentages in flags, and the exit codes of the checks.
+
+mod common;
+
+use std::{
+    io::Write,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+};
+
+/// Runs wasmphobia with `args`, with `module` on its stdin.
+fn run(args: &[&str], module: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_wasmphobia"))
+        .args(args)
+        .env("RUST_BACKTRACE", "0")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("running wasmphobia");
+    let mut stdin = child.stdin.take().expect("piped stdin");
+    // It exits without reading the module if the flags are invalid.
+    let _ = stdin.write_all(module);
+    drop(stdin);
+    child.wait_with_output().expect("running wasmphobia")
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+/// A path in the temporary directory that no other test uses.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("wasmphobia-{}-{name}", std::process::id()))
+}
+
+#[test]
+fn budgets_take_sizes_with_units() {
+    // The module is 995 bytes.
+    let module = common::module("rust");
+    let output = run(&["--format", "folded", "--budget", "total=1KiB"], &module);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let output = run(&["--format", "folded", "--budget", "total=0.5KB"], &module);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("over its budget of 500 bytes"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn budgets_apply_to_folded_crates() {
+    // `serde` is a fifth of the module, small enough to be folded into `@other`.
+    let args = ["--min-percent", "30", "--budget", "crate:serde=100"];
+    let output = run(&args, &common::module("rust"));
+    assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("serde: 200 bytes"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn crate_budgets_need_full_paths() {
+    let module = common::module("rust");
+    let budget = ["--format", "folded", "--budget", "crate:serde=100"];
+    let output = run(&[&budget[..], &["--root", "src"]].concat(), &module);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("without --root"),
+        "{}",
+        stderr(&output)
+    );
+    // Crate grouping doesn't look at the roots.
+    let crates = ["--group-by", "crate", "--root", "src"];
+    let output = run(&[&budget[..], &crates].concat(), &module);
+    assert!(
+        stderr(&output).contains("serde: 200 bytes"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn rejects_unknown_size_units() {
+    let output = run(&["--budget", "total=12parsecs"], &common::module("rust"));
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr(&output).contains("unknown unit \"parsecs\""),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn max_growth_takes_percentages() {
+    let baseline = temp_path("baseline.json");
+    let output = run(
+        &["snapshot", "-", "-o", baseline.to_str().unwrap()],
+        &common::module("rust"),
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let check = |module: &[u8], max_growth: &str| {
+        let baseline = baseline.to_str().unwrap();
+        run(
+            &[
+                "check",
+                "-",
+                "--baseline",
+                baseline,
+                // With `=`, so negative values aren't taken for flags.
+                &format!("--max-growth={max_growth}"),
+            ],
+            module,
+        )
+    };
+    let unchanged = check(&common::module("rust"), "2%");
+    let grown = check(&common::module("c"), "0.5");
+    let negative = check(&common::module("rust"), "-2%");
+    let _ = std::fs::remove_file(&baseline);
+
+    assert!(unchanged.status.success(), "{}", stderr(&unchanged));
+    assert_eq!(grown.status.code(), Some(1));
+    assert_eq!(negative.status.code(), Some(2));
+    assert!(
+        stderr(&negative).contains("expected a percentage like \"2%\""),
+        "{}",
+        stderr(&negative)
+    );
+}
+
+#[test]
+fn writes_events_unless_they_would_clobber() {
+    let events = temp_path("events.ndjson");
+    let emit_events = format!("--emit-events={}", events.display());
+    let module = common::module("rust");
+    let output = run(&["--format", "folded", &emit_events], &module);
+    let written = std::fs::read_to_string(&events).unwrap_or_default();
+    let refused = run(
+        &["--format", "folded", &emit_events, "--no-clobber"],
+        &module,
+    );
+    let _ = std::fs::remove_file(&events);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        written.contains("\"event\":\"analysis-finished\""),
+        "{written}"
+    );
+    assert!(!refused.status.success());
+    assert!(stderr(&refused).contains("already exists (--no-clobber)"));
+}
+
+#[test]
+fn prints_analysis_warnings() {
+    let module = common::module("rust");
+    let output = run(&["--format", "folded"], &module[..module.len() / 2]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("Warning: The module is truncated or corrupted"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn reads_the_parseable_part_of_truncated_modules() {
+    let module = common::module("rust");
+    let truncated = &module[..module.len() / 2];
+    let reports = "duplicated-data,names,checks";
+    let output = run(&["--format", "folded", "--report", reports], truncated);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let output = run(&["strings", "-"], truncated);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Warning: The module is truncated or corrupted"));
+}
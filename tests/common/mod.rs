@@ -0,0 +1,54 @@
+//! Helpers shared by the integration tests, which analyze the modules of `gen-fixture`.
+
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+
+use wasmphobia::{fixture, Contributors, Options};
+
+/// The module of the fixture `name`.
+pub fn module(name: &str) -> Vec<u8> {
+    let fixture = fixture::find(name).unwrap_or_else(|| panic!("no fixture {name:?}"));
+    fixture::generate(fixture).expect("generating the fixture")
+}
+
+pub fn contributors(module: &[u8], options: &Options) -> Contributors {
+    wasmphobia::analyze(module, options)
+        .expect("analyzing the fixture")
+        .contributors
+}
+
+/// Bytes of every section, i.e. of every first frame.
+pub fn section_totals(contributors: &Contributors) -> BTreeMap<String, u64> {
+    let mut totals = BTreeMap::new();
+    for (path, size) in contributors {
+        let section = path.split(';').next().unwrap_or_default();
+        *totals.entry(section.to_string()).or_insert(0) += size;
+    }
+    totals
+}
+
+/// Paths below `frame` (e.g. `@unmapped`), without the frames above it, that some bytes were
+/// attributed to, and their sizes.
+pub fn below(contributors: &Contributors, frame: &str) -> BTreeMap<String, u64> {
+    contributors
+        .iter()
+        .filter(|(_, &size)| size > 0)
+        .filter_map(|(path, &size)| {
+            let (_, rest) = path.split_once(&format!(";{frame};"))?;
+            Some((rest.to_string(), size))
+        })
+        .collect()
+}
+
+/// The functions of the code section, by their path below it.
+pub fn functions(contributors: &Contributors) -> BTreeMap<String, u64> {
+    contributors
+        .iter()
+        .filter_map(|(path, &size)| {
+            let rest = path.strip_prefix("@section: <code>;")?;
+            rest.contains("@function: ")
+                .then(|| (rest.to_string(), size))
+        })
+        .collect()
+}
@@ -0,0 +1,31 @@
+//! The gzip estimate and its uncertainty.
+
+mod common;
+
+use wasmphobia::{Metric, Options, SizeTree};
+
+#[test]
+fn estimates_lie_within_their_range() {
+    let module = common::module("rust");
+    let options = Options {
+        metric: Metric::Gzip,
+        ..Options::default()
+    };
+    let analysis = wasmphobia::analyze(&module, &options).expect("analyzing the fixture");
+    let uncertainty = analysis.uncertainty.expect("gzip sizes are estimates");
+    let mut tree = SizeTree::from_contributors(&analysis.contributors);
+    tree.attach_ranges(&uncertainty.low, &uncertainty.high);
+    let mut stack = vec![&tree];
+    while let Some(node) = stack.pop() {
+        let range = node.range.expect("every node has a range");
+        assert!(
+            range.low <= node.size && node.size <= range.high,
+            "{}: {} not in {range:?}",
+            node.name,
+            node.size
+        );
+        stack.extend(&node.children);
+    }
+    let root = tree.range.expect("the root has a range");
+    assert!(root.low < root.high);
+}
@@ -0,0 +1,65 @@
+//! Attribution of the data segments to the variables in the debug info and to strings.
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use wasmphobia::{data, Options};
+
+fn data_sizes(fixture: &str, options: &Options) -> BTreeMap<String, u64> {
+    let module = common::module(fixture);
+    common::below(&common::contributors(&module, options), data::FRAME)
+}
+
+#[test]
+fn variables_and_strings_are_attributed() {
+    assert_eq!(
+        data_sizes("rust", &Options::default()),
+        BTreeMap::from([
+            ("@strings".to_string(), 44),
+            (
+                "home;user;app;src;lib.rs;@variable: app::DEFAULT_CONFIG".to_string(),
+                24
+            ),
+        ])
+    );
+    assert_eq!(
+        data_sizes("c", &Options::default()),
+        BTreeMap::from([
+            ("@strings".to_string(), 25),
+            (
+                "home;user;app;main.c;@variable: lookup_table".to_string(),
+                64
+            ),
+        ])
+    );
+}
+
+#[test]
+fn files_only_stops_at_the_declaring_file() {
+    let options = Options {
+        files_only: true,
+        ..Options::default()
+    };
+    assert_eq!(
+        data_sizes("c", &options),
+        BTreeMap::from([
+            ("@strings".to_string(), 25),
+            ("home;user;app;main.c".to_string(), 64),
+        ])
+    );
+}
+
+#[test]
+fn largest_strings_skip_variables() {
+    let module = common::module("rust");
+    let largest = data::largest_strings(&module, 10).unwrap();
+    assert_eq!((largest.count, largest.bytes), (1, 44));
+    let string = &largest.strings[0];
+    // The string follows the 24 bytes of `app::DEFAULT_CONFIG` at 1024.
+    assert_eq!(string.address, Some(1048));
+    assert_eq!(
+        string.preview,
+        r#""called `Option::unwrap()` on a `None` value""#
+    );
+}
@@ -0,0 +1,91 @@
+//! Grouping by namespace and collapsing generics, on the names compilers give functions.
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use wasmphobia::{group::GroupBy, Options};
+
+fn functions(fixture: &str, options: &Options) -> BTreeMap<String, u64> {
+    let module = common::module(fixture);
+    common::functions(&common::contributors(&module, options))
+}
+
+fn expected<const N: usize>(functions: [(&str, u64); N]) -> BTreeMap<String, u64> {
+    functions
+        .into_iter()
+        .map(|(path, size)| (path.to_string(), size))
+        .collect()
+}
+
+#[test]
+fn groups_cpp_by_namespace() {
+    let options = Options {
+        group_by: GroupBy::Namespace,
+        ..Options::default()
+    };
+    assert_eq!(
+        functions("cpp", &options),
+        expected([
+            ("@function: operator<<", 60),
+            ("Widget;@function: draw", 150),
+            ("Widget;@function: non-virtual thunk to draw", 10),
+            ("Widget;@function: ~Widget", 50),
+            (
+                "std;__2;vector<Widget, std::__2::allocator<Widget> >;@function: push_back",
+                80
+            ),
+        ])
+    );
+}
+
+#[test]
+fn collapses_cpp_templates() {
+    let options = Options {
+        group_by: GroupBy::Namespace,
+        collapse_generics: true,
+        ..Options::default()
+    };
+    let functions = functions("cpp", &options);
+    assert_eq!(
+        functions.get("std;__2;vector;@function: push_back"),
+        Some(&80)
+    );
+    assert_eq!(functions.get("@function: operator<<"), Some(&60));
+}
+
+#[test]
+fn collapses_rust_generics_and_keeps_the_instantiations() {
+    let module = common::module("rust");
+    let options = Options {
+        collapse_generics: true,
+        ..Options::default()
+    };
+    let analysis = wasmphobia::analyze(&module, &options).unwrap();
+    let ptr = "rustc;9b00956e56009bab2aa15d7bff10916599e3d6d6;library;core;src;ptr;mod.rs";
+    let functions = common::functions(&analysis.contributors);
+    assert_eq!(
+        functions.get(&format!("{ptr};@function: core::ptr::drop_in_place")),
+        Some(&30)
+    );
+    assert_eq!(
+        functions.get(&format!(
+            "{ptr};@function: core::ptr::drop_in_place;@function: app::run"
+        )),
+        Some(&40)
+    );
+
+    // Both instantiations add up in the collapsed node, the inlined one included.
+    let path = format!("@section: <code>;{ptr};@function: core::ptr::drop_in_place");
+    let args: Vec<_> = analysis.instantiations[&path]
+        .iter()
+        .map(|instantiation| (instantiation.args.join(", "), instantiation.size))
+        .collect();
+    assert_eq!(
+        args,
+        [
+            ("app::State".to_string(), 40),
+            ("alloc::vec::Vec<u8>".to_string(), 30)
+        ]
+    );
+}
@@ -0,0 +1,53 @@
+//! Per-crate budgets of `ratchet init` and `ratchet check`.
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use wasmphobia::{group::GroupBy, ratchet, Options};
+
+fn crate_sizes(fixture: &str) -> ratchet::CrateSizes {
+    let options = Options {
+        files_only: true,
+        group_by: GroupBy::Files,
+        ..Options::default()
+    };
+    let module = common::module(fixture);
+    ratchet::CrateSizes::from_contributors(&common::contributors(&module, &options))
+}
+
+#[test]
+fn finds_crates_by_their_source_paths() {
+    let sizes = crate_sizes("rust");
+    // `app` has a variable besides its functions, and what `core` inlined into it counts for
+    // `core`.
+    let crates = BTreeMap::from([
+        ("app".to_string(), 120 + 60 + 24),
+        ("core".to_string(), 300 + 30 + 40),
+        ("serde".to_string(), 200),
+    ]);
+    assert_eq!(sizes.crates, crates);
+}
+
+#[test]
+fn budgets_survive_their_toml() {
+    let sizes = crate_sizes("rust");
+    let budgets = ratchet::Budgets::from_sizes(&sizes, 5.0);
+    let parsed = ratchet::Budgets::parse(&budgets.to_toml()).unwrap();
+    assert_eq!(parsed, budgets);
+    assert!(parsed.check(&sizes).is_empty());
+    assert!(parsed.loose(&sizes).is_empty());
+}
+
+#[test]
+fn checks_other_modules_against_the_budgets() {
+    let budgets = ratchet::Budgets::from_sizes(&crate_sizes("rust"), 5.0);
+    let violations = budgets.check(&crate_sizes("c"));
+    let names: Vec<_> = violations
+        .iter()
+        .map(|violation| (violation.name.as_str(), violation.budget))
+        .collect();
+    // The C module is larger, and wasi-libc has no budget.
+    assert_eq!(names, [("<total>", budgets.total), ("@libc-runtime", None)]);
+    assert!(budgets.check_budgeted(&crate_sizes("c")).len() == 1);
+}
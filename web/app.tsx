@@ -6,6 +6,7 @@ import DropZone from "./drop-zone.js";
 import ErrorBar from "./error-bar.js";
 import Footer from "./footer.js";
 import Readme from "./readme.jsx";
+import WarningBar from "./warning-bar.js";
 
 export default function App() {
   return (
@@ -16,6 +17,7 @@ export default function App() {
             <PageLayout.Content>
               <DropZone />
               <ErrorBar sx={{ m: 4 }} hidden />
+              <WarningBar sx={{ m: 4 }} hidden />
               <Readme />
             </PageLayout.Content>
             <PageLayout.Footer sx={{ padding: 2 }}>
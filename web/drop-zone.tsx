@@ -17,7 +17,7 @@ import * as styles from "./styles.module.css";
 
 import options from "cli-flags:";
 
-const HIDDEN_FLAGS = new Set(["--title"]);
+const HIDDEN_FLAGS = new Set(["--title", "--report", "--emit-events", "--sign-key", "--self-contained-html", "--embed-code", "--palette-map", "--format", "--checkpoint-every", "--no-clobber", "--force", "--offline", "--budget", "--demangler-cmd", "--top", "--memory-limit", "--root-frame", "--debug-file", "--annotations"]);
 
 export default function DropZone() {
   const theme = useTheme();
@@ -87,7 +87,7 @@ function CliOption({ option, ...rest }) {
     case "usize":
       return <NumberCliOption option={option} {...rest} />;
     default:
-      throw Error(`Unknown CLI flag type: ${option.type}`);
+      return <TextCliOption option={option} {...rest} />;
   }
 }
 
@@ -110,3 +110,13 @@ function NumberCliOption({ option, ...rest }) {
     </FormControl>
   );
 }
+
+function TextCliOption({ option, ...rest }) {
+  return (
+    <FormControl {...rest}>
+      <TextInput type="text" name={option.flag} defaultValue={option.def ?? ""} />
+      <FormControl.Label>{option.title}</FormControl.Label>
+      <FormControl.Caption>{option.description}</FormControl.Caption>
+    </FormControl>
+  );
+}
@@ -1,12 +1,14 @@
 import { ConsoleStdout, File as WasiFile, OpenFile, WASI } from "@bjorn3/browser_wasi_shim";
 
-export default async function renderFlameGraph(file, options = []) {
+// Runs the CLI on `file`. Events are written to stderr as JSON lines, so the warnings can be told
+// apart from the error message.
+async function run(file, args) {
   const fileName = file.name;
   const data = await new Response(file).arrayBuffer();
   const input = new WasiFile(data);
   const output = new WasiFile();
   const error = new WasiFile();
-  const wasi = new WASI(["__", `--title=${fileName}`, ...options], [], [
+  const wasi = new WASI(["__", `--title=${fileName}`, "--emit-events=-", ...args], [], [
     new OpenFile(input),
     new OpenFile(output),
     new OpenFile(error),
@@ -20,9 +22,42 @@ export default async function renderFlameGraph(file, options = []) {
   });
 
   const ret = wasi.start({ exports: instance.exports });
+  const warnings = [];
+  const messages = [];
+  for (const line of new TextDecoder().decode(error.data).split("\n")) {
+    const event = parseEvent(line);
+    if (event?.event === "warning") {
+      warnings.push(event.message);
+    } else if (!event && line) {
+      messages.push(line);
+    }
+  }
   if (ret != 0) {
-    const errorMessage = new TextDecoder().decode(error.data);
-    throw Error("Could not create flamegraph: " + errorMessage);
+    throw Error("Could not create flamegraph: " + messages.join("\n"));
+  }
+  return { output: new TextDecoder().decode(output.data), warnings };
+}
+
+function parseEvent(line) {
+  if (!line.startsWith("{")) return null;
+  try {
+    return JSON.parse(line);
+  } catch {
+    return null;
   }
-  return new TextDecoder().decode(output.data);
+}
+
+// Renders the flame graph SVG, with the warnings of the analysis.
+export default async function renderFlameGraph(file, options = []) {
+  const { output, warnings } = await run(file, options);
+  return { svg: output, warnings };
+}
+
+// Analyzes `file` without rendering anything: the breakdown as a tree of nodes with `name`, `size`,
+// `self` and `children`, the warnings of the analysis and notes about the module, like the ones
+// embedded in the SVG.
+export async function analyze(file, options = []) {
+  const { output, warnings } = await run(file, ["--format=json", ...options]);
+  const { tree, metadata = {} } = JSON.parse(output);
+  return { tree, warnings, metadata };
 }
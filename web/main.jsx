@@ -26,6 +26,8 @@ let idCounter = 0;
 async function process(file) {
   try {
     showSpinner();
+    errorBar.hidden = true;
+    warningBar.hidden = true;
     const id = idCounter++;
     const options = getSelectedOptions();
     worker.postMessage({ id, file, options });
@@ -34,6 +36,10 @@ async function process(file) {
       throw Error(result.error);
     }
     const url = URL.createObjectURL(result.svg);
+    if (result.warnings.length > 0) {
+      showWarnings(result.warnings, url);
+      return;
+    }
     location.href = url;
   } catch (e) {
     showError(e.message);
@@ -108,6 +114,20 @@ function showError(text) {
   errorBar.hidden = false;
 }
 
+const warningBar = document.querySelector(`.${styles.warningBar}`);
+const warningList = document.querySelector(`.${styles.warningList}`);
+const warningLink = document.querySelector(`.${styles.warningLink}`);
+
+function showWarnings(warnings, url) {
+  warningList.replaceChildren(...warnings.map(warning => {
+    const item = document.createElement("li");
+    item.textContent = warning;
+    return item;
+  }));
+  warningLink.href = url;
+  warningBar.hidden = false;
+}
+
 function getSelectedOptions() {
   return Array.from(optionsForm.elements).flatMap(el => {
     switch (el.type) {
@@ -115,6 +135,8 @@ function getSelectedOptions() {
         return el.checked ? [el.name] : [];
       case "number":
         return `${el.name}=${el.value}`;
+      case "text":
+        return el.value ? [`${el.name}=${el.value}`] : [];
       default:
         throw Error("Unknown option field");
     }
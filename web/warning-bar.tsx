@@ -0,0 +1,13 @@
+import * as React from "react";
+
+import { Flash, Link } from "@primer/react";
+import * as styles from "./styles.module.css";
+
+export default function WarningBar({ ...others }) {
+  return (
+    <Flash variant="warning" {...others} className={styles.warningBar}>
+      <ul className={styles.warningList} />
+      <Link className={styles.warningLink}>Open the flame graph anyway</Link>
+    </Flash>
+  );
+}
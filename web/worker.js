@@ -1,11 +1,15 @@
-import renderFlameGraph from "./flamegraph.js";
+import renderFlameGraph, { analyze } from "./flamegraph.js";
 
 addEventListener("message", async ev => {
-  const { id, file, options } = ev.data;
+  const { id, file, options, format } = ev.data;
   try {
-    const svgContent = await renderFlameGraph(file, options);
+    if (format === "json") {
+      postMessage({ id, ...await analyze(file, options) });
+      return;
+    }
+    const { svg: svgContent, warnings } = await renderFlameGraph(file, options);
     const svg = new File([svgContent], `${file.name}.svg`, { type: "image/svg+xml" });
-    postMessage({ id, svg });
+    postMessage({ id, svg, warnings });
   } catch (e) {
     postMessage({ id, error: e.message });
   }